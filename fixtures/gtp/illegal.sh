@@ -0,0 +1,11 @@
+#!/bin/sh
+# A GTP engine that always plays A1 (which is illegal the second
+# time).
+while read command arguments; do
+    case "$command" in
+        name) printf "= illegal\n\n" ;;
+        genmove) printf "= A1\n\n" ;;
+        quit) printf "= \n\n"; exit 0 ;;
+        *) printf "= \n\n" ;;
+    esac
+done
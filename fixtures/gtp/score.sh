@@ -0,0 +1,11 @@
+#!/bin/sh
+# A GTP engine that passes on every move and thinks black won.
+while read command arguments; do
+    case "$command" in
+        name) printf "= score\n\n" ;;
+        genmove) printf "= pass\n\n" ;;
+        final_score) printf "= B+12\n\n" ;;
+        quit) printf "= \n\n"; exit 0 ;;
+        *) printf "= \n\n" ;;
+    esac
+done
@@ -0,0 +1,9 @@
+#!/bin/sh
+# A GTP engine that takes two seconds for every move.
+while read command arguments; do
+    case "$command" in
+        genmove) sleep 2; printf "= pass\n\n" ;;
+        quit) printf "= \n\n"; exit 0 ;;
+        *) printf "= \n\n" ;;
+    esac
+done
@@ -24,17 +24,26 @@ use board::Color;
 use board::Coord;
 
 use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::BuildHasherDefault;
+
+/// The liberties of a chain. `DefaultHasher::new()` always uses the
+/// same keys (unlike the `RandomState` of a plain `HashSet`), so the
+/// liberties are iterated in the same order in every run. Otherwise
+/// the playouts, and thus the self-play of `build-book`, couldn't be
+/// reproduced from a seed.
+pub type Liberties = HashSet<Coord, BuildHasherDefault<DefaultHasher>>;
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Chain {
     color:  Color,
     coords: Vec<Coord>,
     id:     usize,
-    libs:   HashSet<Coord>,
+    libs:   Liberties,
 }
 
 impl Chain {
-    pub fn new(id: usize, color: Color, c: Coord, libs: HashSet<Coord>) -> Chain {
+    pub fn new(id: usize, color: Color, c: Coord, libs: Liberties) -> Chain {
         Chain {
             color:  color,
             coords: vec!(c),
@@ -51,7 +60,7 @@ impl Chain {
         &self.coords
     }
 
-    pub fn liberties(&self) -> &HashSet<Coord> {
+    pub fn liberties(&self) -> &Liberties {
         &self.libs
     }
 
@@ -33,10 +33,10 @@ pub use self::movement::Resign;
 
 use ruleset::Ruleset;
 use score::Score;
+use self::chain::Liberties;
 use self::point::Point;
 
 use quicksort::quicksort;
-use std::collections::HashSet;
 use std::fmt;
 use std::sync::Arc;
 use smallvec::SmallVec4;
@@ -45,8 +45,11 @@ mod chain;
 mod coord;
 mod hypotheticals;
 mod movement;
+mod nakade;
 mod point;
 mod reading;
+mod safety;
+mod seki;
 mod test;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -138,11 +141,13 @@ impl Cache {
 #[derive(Debug)]
 pub struct Board {
     adv_stones_removed:    Vec<Coord>,
+    black_prisoners:       usize,
     board:                 Vec<Point>,
     chains:                Vec<Chain>,
     consecutive_passes:    u8,
     cache:                 Arc<Cache>,
     friend_stones_removed: Vec<Coord>,
+    handicap:              u16,
     ko:                    Option<Coord>,
     komi:                  f32,
     previous_player:       Color,
@@ -150,17 +155,20 @@ pub struct Board {
     ruleset:               Ruleset,
     size:                  u8,
     vacant:                Vec<Coord>,
+    white_prisoners:       usize,
 }
 
 impl Clone for Board {
     fn clone(&self) -> Board {
         Board {
             adv_stones_removed:    self.adv_stones_removed.clone(),
+            black_prisoners:       self.black_prisoners,
             board:                 self.board.clone(),
             chains:                self.chains.clone(),
             cache:                 self.cache.clone(),
             consecutive_passes:    self.consecutive_passes,
             friend_stones_removed: self.friend_stones_removed.clone(),
+            handicap:              self.handicap,
             ko:                    self.ko,
             komi:                  self.komi,
             previous_player:       self.previous_player,
@@ -168,6 +176,7 @@ impl Clone for Board {
             ruleset:               self.ruleset,
             size:                  self.size,
             vacant:                self.vacant.clone(),
+            white_prisoners:       self.white_prisoners,
         }
     }
 }
@@ -176,11 +185,13 @@ impl Board {
     pub fn new(size: u8, komi: f32, ruleset: Ruleset) -> Board {
         Board {
             adv_stones_removed:    Vec::new(),
+            black_prisoners:       0,
             board:                 (0..size as usize*size as usize).map(|_| Point::new()).collect(),
             chains:                Vec::new(),
             consecutive_passes:    0,
             cache:                 Arc::new(Cache::new(size)),
             friend_stones_removed: Vec::new(),
+            handicap:              0,
             ko:                    None,
             komi:                  komi,
             previous_player:       White,
@@ -188,6 +199,7 @@ impl Board {
             ruleset:               ruleset,
             size:                  size,
             vacant:                Coord::for_board_size(size),
+            white_prisoners:       0,
         }
     }
 
@@ -225,10 +237,38 @@ impl Board {
         self.board[c.to_index(self.size)].color
     }
 
-    /// Remove dead stone for scoring. Board becomes unplayable after
+    /// Remove dead stone for scoring. The stone is added to the
+    /// prisoners of the opponent. Board becomes unplayable after
     /// doing this!
     pub fn remove_dead_stone(&mut self, c: &Coord) {
+        let color = self.color(c);
+        self.add_prisoners(color.opposite(), 1);
         self.board[c.to_index(self.size)].color = Empty;
+        self.vacant.push(*c);
+        let chain_ids: SmallVec4<_> = self.neighbours(*c).iter()
+            .filter(|n| self.color(n) != Empty)
+            .map(|n| self.chain_id(n))
+            .collect();
+        for &id in chain_ids.iter() {
+            self.chains[id].add_liberty(*c);
+        }
+    }
+
+    /// The number of stones captured by `color`.
+    pub fn prisoners(&self, color: Color) -> usize {
+        match color {
+            Black => self.black_prisoners,
+            White => self.white_prisoners,
+            Empty => 0
+        }
+    }
+
+    fn add_prisoners(&mut self, color: Color, count: usize) {
+        match color {
+            Black => { self.black_prisoners += count; }
+            White => { self.white_prisoners += count; }
+            Empty => {}
+        }
     }
 
     pub fn chain_id(&self, c: &Coord) -> usize {
@@ -248,14 +288,61 @@ impl Board {
         self.komi
     }
 
+    pub fn ruleset(&self) -> Ruleset {
+        self.ruleset
+    }
+
     pub fn set_komi(&mut self, komi: f32) {
         self.komi = komi;
     }
 
+    /// The komi including the compensation white receives for the
+    /// handicap stones under the current ruleset. This is what's
+    /// used when counting the score.
+    pub fn adjusted_komi(&self) -> f32 {
+        self.komi + self.ruleset.handicap_compensation(self.handicap)
+    }
+
+    pub fn handicap(&self) -> u16 {
+        self.handicap
+    }
+
+    /// Places the handicap stones for black on an empty board. White
+    /// is the next player after this.
+    pub fn place_handicap(&mut self, coords: &[Coord]) -> Result<(), IllegalMove> {
+        for coord in coords {
+            if !coord.is_inside(self.size) {
+                return Err(IllegalMove::PlayOutOfBoard);
+            }
+            if self.color(coord) != Empty {
+                return Err(IllegalMove::IntersectionNotEmpty);
+            }
+            self.play_legal_move(Play(Black, coord.col, coord.row));
+        }
+        self.handicap = coords.len() as u16;
+        Ok(())
+    }
+
     pub fn next_player(&self) -> Color {
         self.previous_player.opposite()
     }
 
+    pub fn set_next_player(&mut self, color: Color) {
+        self.previous_player = color.opposite();
+    }
+
+    /// The point that can't be played because of the ko rule (if it
+    /// would recapture the ko).
+    pub fn ko(&self) -> Option<Coord> {
+        self.ko
+    }
+
+    /// Continues a game that's over because both players passed,
+    /// e.g. to read out the status of the remaining groups.
+    pub fn resume(&mut self) {
+        self.consecutive_passes = 0;
+    }
+
     pub fn is_eye(&self, coord: &Coord, color: Color) -> bool {
         let neighbours = self.neighbours(*coord);
         if neighbours.iter().all(|c| self.color(c) == color) {
@@ -392,11 +479,15 @@ impl Board {
         self.update_libs_of_adjacent_opposing_chains(&m);
         // Removes captured opposing chains
         self.adv_stones_removed = self.remove_captured_opponent_stones(&m);
+        let captured = self.adv_stones_removed.len();
+        self.add_prisoners(*m.color(), captured);
         // Adds removed stones as liberties to the neighbouring chains
         self.add_removed_adv_stones_as_libs(&m);
         // Checks for suicide play
         if self.get_chain(m.coord()).unwrap().is_captured() {
             self.friend_stones_removed = self.remove_suicide_chain(&m);
+            let captured = self.friend_stones_removed.len();
+            self.add_prisoners(m.color().opposite(), captured);
             self.add_removed_friendly_stones_as_libs(&m);
         }
         if self.adv_stones_removed.len() == 1 && self.friend_stones_removed.len() == 0 {
@@ -589,7 +680,7 @@ impl Board {
         new_chain_id
     }
 
-    fn liberties(&self, c: &Coord) -> HashSet<Coord> {
+    fn liberties(&self, c: &Coord) -> Liberties {
         self.neighbours(*c).iter().filter(|&c| self.color(c) == Empty).cloned().collect()
     }
 
@@ -0,0 +1,122 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::{Board, Color, Coord, Empty};
+
+// The largest eye space that can be killed by a nakade (the rabbity
+// six).
+const MAX_NAKADE_SIZE: usize = 6;
+
+impl Board {
+
+    ///returns the vital point of an empty eye space next to the
+    ///coordinate (usually the last move). Playing there kills the
+    ///group (or makes two eyes if the owner plays it). Only the shapes
+    ///with a single vital point are recognized: the straight and bent
+    ///three, the pyramid four, the bulky and crossed five and the
+    ///rabbity six.
+    pub fn nakade_point(&self, coord: Coord) -> Option<Coord> {
+        let mut seen: Vec<Coord> = vec![];
+        for &c in self.neighbours(coord).iter() {
+            if self.color(&c) != Empty || seen.contains(&c) {
+                continue;
+            }
+            match self.eye_space(c) {
+                Some(space) => {
+                    if let Some(vital) = self.vital_point(&space) {
+                        return Some(vital);
+                    }
+                    seen.extend(space);
+                },
+                None => {}
+            }
+        }
+        None
+    }
+
+    //the empty points connected to the coordinate if they are
+    //surrounded by stones of a single color and there are at most
+    //MAX_NAKADE_SIZE of them
+    fn eye_space(&self, coord: Coord) -> Option<Vec<Coord>> {
+        let mut space = vec![coord];
+        let mut owner: Option<Color> = None;
+        let mut i = 0;
+        while i < space.len() {
+            let current = space[i];
+            for &n in self.neighbours(current).iter() {
+                let color = self.color(&n);
+                if color == Empty {
+                    if !space.contains(&n) {
+                        if space.len() == MAX_NAKADE_SIZE {
+                            return None;
+                        }
+                        space.push(n);
+                    }
+                } else if owner.is_none() {
+                    owner = Some(color);
+                } else if owner != Some(color) {
+                    return None;
+                }
+            }
+            i += 1;
+        }
+        if owner.is_some() {
+            Some(space)
+        } else {
+            None
+        }
+    }
+
+    ///returns the vital point of an empty eye space (see
+    ///nakade_point), if it has one.
+    pub fn vital_point(&self, space: &Vec<Coord>) -> Option<Coord> {
+        if space.len() < 3 || space.len() > MAX_NAKADE_SIZE {
+            return None;
+        }
+        let degrees: Vec<usize> = space.iter()
+            .map(|&c| self.neighbours(c).iter().filter(|n| space.contains(n)).count())
+            .collect();
+        let max = *degrees.iter().max().unwrap();
+        if degrees.iter().filter(|&&degree| degree == max).count() > 1 {
+            return None;
+        }
+        let killable = match space.len() {
+            3 => max == 2,
+            4 => max == 3,
+            5 => max == 4 || (max == 3 && Self::has_square(space)),
+            _ => max == 4 && Self::has_square(space),
+        };
+        if killable {
+            degrees.iter().position(|&degree| degree == max).map(|i| space[i])
+        } else {
+            None
+        }
+    }
+
+    fn has_square(space: &Vec<Coord>) -> bool {
+        space.iter().any(|c| {
+            space.contains(&Coord::new(c.col + 1, c.row)) &&
+                space.contains(&Coord::new(c.col, c.row + 1)) &&
+                space.contains(&Coord::new(c.col + 1, c.row + 1))
+        })
+    }
+
+}
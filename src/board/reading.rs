@@ -19,7 +19,7 @@
  *                                                                      *
  ************************************************************************/
 
-use board::{Board, Chain, Coord, Empty, Move, Pass, Play};
+use board::{Board, Chain, Color, Coord, Empty, Move, Pass, Play};
 
 use smallvec::SmallVec4;
 
@@ -215,6 +215,63 @@ impl Board {
         }
     }
     
+    ///returns the moves of the player that capture an opponent chain
+    ///in atari
+    pub fn capturing_moves(&self, player: Color) -> Vec<Move> {
+        let mut solutions = vec![];
+        let enemy = player.opposite();
+        for chain in self.chains().iter() {
+            if chain.color() == enemy && chain.liberties().len() == 1 {
+                let liberty = chain.liberties().iter().next().unwrap();
+                let m = Play(player, liberty.col, liberty.row);
+                if !solutions.contains(&m) && self.is_legal(m).is_ok() {
+                    solutions.push(m);
+                }
+            }
+        }
+        solutions
+    }
+
+    ///returns the moves of the player that attack or defend the chains
+    ///with two liberties next to the coordinate (usually the last move)
+    pub fn two_liberty_moves(&self, player: Color, coord: Coord) -> Vec<Move> {
+        let mut chain_ids: SmallVec4<usize> = SmallVec4::new();
+        for &c in self.neighbours(coord).iter().chain(Some(coord).iter()) {
+            if self.color(&c) != Empty && !chain_ids.contains(&self.chain_id(&c)) {
+                chain_ids.push(self.chain_id(&c));
+            }
+        }
+
+        let mut solutions = vec![];
+        for &id in chain_ids.iter() {
+            let chain = &self.chains()[id];
+            if chain.liberties().len() != 2 {
+                continue;
+            }
+            let liberties: Vec<Coord> = chain.liberties().iter().cloned().collect();
+            for (i, liberty) in liberties.iter().enumerate() {
+                let m = Play(player, liberty.col, liberty.row);
+                if self.is_legal(m).is_err() || solutions.contains(&m) {
+                    continue;
+                }
+                let works = if chain.color() == player {
+                    //defend: extending gives us at least three liberties
+                    self.new_chain_liberties_greater_than(m, 2)
+                } else {
+                    //attack: extending at the other liberty gives them at
+                    //most two liberties (our move still counts as one now)
+                    let other = liberties[1 - i];
+                    let escape = Play(chain.color(), other.col, other.row);
+                    self.is_not_self_atari(&m) && !self.new_chain_liberties_greater_than(escape, 3)
+                };
+                if works {
+                    solutions.push(m);
+                }
+            }
+        }
+        solutions
+    }
+
     pub fn play_in_middle_of_eye(&self, m: Move) -> Option<Move> {
         let mut candidate = None;
         let mut color = None;
@@ -0,0 +1,161 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::{Black, Board, Color, Coord, Empty, White};
+
+/// A region enclosed by a chain in which all empty points are
+/// liberties of the chain, i.e. a (potential) eye. The region may
+/// contain opponent stones.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EyeSpace {
+    points: Vec<Coord>,
+    vital_point: Option<Coord>,
+}
+
+impl EyeSpace {
+
+    pub fn points(&self) -> &Vec<Coord> {
+        &self.points
+    }
+
+    /// The point that decides whether an empty eye space of three to
+    /// six points makes one or two eyes (see `Board::nakade_point`).
+    pub fn vital_point(&self) -> Option<Coord> {
+        self.vital_point
+    }
+
+}
+
+// A maximal connected set of points without stones of a color
+// together with the ids of the chains of that color around it.
+struct Region {
+    chains: Vec<usize>,
+    points: Vec<Coord>,
+}
+
+impl Board {
+
+    ///returns the eye spaces of the chain at the coordinate
+    pub fn eye_spaces(&self, coord: Coord) -> Vec<EyeSpace> {
+        let chain = self.get_chain(coord).unwrap();
+        self.regions(chain.color()).into_iter()
+            .filter(|region| self.is_vital(region, chain.id()))
+            .map(|region| {
+                let empty = region.points.iter().all(|c| self.color(c) == Empty);
+                let vital_point = if empty { self.vital_point(&region.points) } else { None };
+                EyeSpace { points: region.points, vital_point: vital_point }
+            })
+            .collect()
+    }
+
+    ///returns the owner of every point (indexed by Coord::to_index)
+    ///that is unconditionally alive according to Benson's algorithm
+    ///or Empty if it isn't. These are the stones of the chains that
+    ///can't be captured even if their owner always passes and the
+    ///eye spaces of these chains.
+    pub fn pass_alive(&self) -> Vec<Color> {
+        let mut owner = vec![Empty; self.size() as usize * self.size() as usize];
+        for &color in [Black, White].iter() {
+            let regions = self.regions(color);
+            let mut chains: Vec<usize> = vec![];
+            for region in regions.iter() {
+                for &id in region.chains.iter() {
+                    if !chains.contains(&id) {
+                        chains.push(id);
+                    }
+                }
+            }
+            // A chain needs two vital regions that are only
+            // surrounded by chains that are still considered alive.
+            let mut healthy = vec![true; regions.len()];
+            loop {
+                let count = chains.len();
+                chains.retain(|&id| {
+                    regions.iter().enumerate()
+                        .filter(|&(i, region)| healthy[i] && self.is_vital(region, id))
+                        .count() >= 2
+                });
+                if chains.len() == count {
+                    break;
+                }
+                for (i, region) in regions.iter().enumerate() {
+                    if region.chains.iter().any(|id| !chains.contains(id)) {
+                        healthy[i] = false;
+                    }
+                }
+            }
+            for &id in chains.iter() {
+                for c in self.chains[id].coords().iter() {
+                    owner[c.to_index(self.size())] = color;
+                }
+            }
+            for (i, region) in regions.iter().enumerate() {
+                if healthy[i] && chains.iter().any(|&id| self.is_vital(region, id)) {
+                    for c in region.points.iter() {
+                        owner[c.to_index(self.size())] = color;
+                    }
+                }
+            }
+        }
+        owner
+    }
+
+    //the regions enclosed by the chains of the color
+    fn regions(&self, color: Color) -> Vec<Region> {
+        let mut seen = vec![false; self.size() as usize * self.size() as usize];
+        let mut regions = vec![];
+        for coord in Coord::for_board_size(self.size()) {
+            if self.color(&coord) == color || seen[coord.to_index(self.size())] {
+                continue;
+            }
+            seen[coord.to_index(self.size())] = true;
+            let mut region = Region { chains: vec![], points: vec![coord] };
+            let mut i = 0;
+            while i < region.points.len() {
+                let current = region.points[i];
+                for &n in self.neighbours(current).iter() {
+                    if self.color(&n) == color {
+                        let id = self.get_chain(n).unwrap().id();
+                        if !region.chains.contains(&id) {
+                            region.chains.push(id);
+                        }
+                    } else if !seen[n.to_index(self.size())] {
+                        seen[n.to_index(self.size())] = true;
+                        region.points.push(n);
+                    }
+                }
+                i += 1;
+            }
+            regions.push(region);
+        }
+        regions
+    }
+
+    //a region is vital to a chain if all of its empty points are
+    //liberties of the chain
+    fn is_vital(&self, region: &Region, id: usize) -> bool {
+        let liberties = self.chains[id].liberties();
+        region.points.iter()
+            .filter(|c| self.color(c) == Empty)
+            .all(|c| liberties.contains(c))
+    }
+
+}
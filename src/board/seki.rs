@@ -0,0 +1,92 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::{Black, Board, Color, Coord, Empty, Play, White};
+
+impl Board {
+
+    /// Returns the stones of all chains that are in seki. These are
+    /// chains that share liberties with chains of the other color
+    /// where neither side can approach, i.e. playing on any of the
+    /// shared liberties would be self atari for both players.
+    pub fn stones_in_seki(&self) -> Vec<Coord> {
+        let mut candidates: Vec<usize> = self.chains().iter()
+            .filter(|chain| self.color(&chain.coords()[0]) == chain.color())
+            .map(|chain| chain.id())
+            .collect();
+        loop {
+            let shared = self.unapproachable_liberties(&candidates);
+            let remaining: Vec<usize> = candidates.iter()
+                .filter(|&&id| self.can_live_in_seki(id, &shared))
+                .cloned()
+                .collect();
+            if remaining.len() == candidates.len() {
+                break;
+            }
+            candidates = remaining;
+        }
+        let mut stones: Vec<Coord> = candidates.iter()
+            .flat_map(|&id| self.chains()[id].coords().iter())
+            .cloned()
+            .collect();
+        stones.sort();
+        stones
+    }
+
+    /// The liberties that are shared by candidate chains of both
+    /// colors and where playing is self atari for both colors.
+    fn unapproachable_liberties(&self, candidates: &Vec<usize>) -> Vec<Coord> {
+        self.vacant().iter()
+            .filter(|&&coord| {
+                self.is_next_to_candidate(coord, Black, candidates) &&
+                    self.is_next_to_candidate(coord, White, candidates) &&
+                    !self.is_not_self_atari(&Play(Black, coord.col, coord.row)) &&
+                    !self.is_not_self_atari(&Play(White, coord.col, coord.row))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn is_next_to_candidate(&self, coord: Coord, color: Color, candidates: &Vec<usize>) -> bool {
+        self.neighbours(coord).iter()
+            .filter(|c| self.color(c) == color)
+            .any(|c| candidates.contains(&self.chain_id(c)))
+    }
+
+    /// A chain can only be in seki if it touches at least one
+    /// unapproachable shared liberty, has at least two liberties and
+    /// the opponent can't safely play on any of its other liberties.
+    fn can_live_in_seki(&self, id: usize, shared: &Vec<Coord>) -> bool {
+        let chain = &self.chains()[id];
+        let opponent = chain.color().opposite();
+        let liberties: Vec<Coord> = chain.liberties().iter()
+            .filter(|c| self.color(c) == Empty)
+            .cloned()
+            .collect();
+        liberties.len() >= 2 &&
+            liberties.iter().any(|c| shared.contains(c)) &&
+            liberties.iter().all(|c| {
+                shared.contains(c) ||
+                    !self.is_not_self_atari(&Play(opponent, c.col, c.row))
+            })
+    }
+
+}
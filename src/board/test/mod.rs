@@ -41,8 +41,11 @@ use std::path::Path;
 mod diagonals;
 mod eye;
 mod ko;
+mod nakade;
 mod hypotheticals;
 mod reading;
+mod safety;
+mod seki;
 
 #[test]
 fn getting_a_valid_coord_returns_a_color() {
@@ -224,6 +227,42 @@ fn playing_on_all_libs_in_corner_should_capture() {
     assert_eq!(b.color(&Coord::new(2, 1)), White);
 }
 
+#[test]
+fn capturing_adds_the_stones_to_the_prisoners() {
+    let mut b = Board::new(19, 6.5, Minimal);
+
+    b.play(Play(Black, 1, 1));
+    b.play(Play(White, 1, 2));
+    b.play(Play(White, 2, 1));
+
+    assert_eq!(b.prisoners(White), 1);
+    assert_eq!(b.prisoners(Black), 0);
+}
+
+#[test]
+fn suicide_adds_the_stones_to_the_prisoners_of_the_opponent() {
+    let mut b = Board::new(19, 6.5, Minimal);
+
+    b.play(Play(White, 1, 2));
+    b.play(Play(White, 2, 1));
+    b.play(Play(Black, 1, 1));
+
+    assert_eq!(b.prisoners(White), 1);
+    assert_eq!(b.prisoners(Black), 0);
+}
+
+#[test]
+fn removing_a_dead_stone_adds_it_to_the_prisoners() {
+    let mut b = Board::new(19, 6.5, Minimal);
+
+    b.play(Play(Black, 4, 4));
+    b.remove_dead_stone(&Coord::new(4, 4));
+
+    assert_eq!(b.color(&Coord::new(4, 4)), Empty);
+    assert_eq!(b.prisoners(White), 1);
+    assert_eq!(b.vacant_point_count(), 361);
+}
+
 #[test]
 fn playing_on_all_libs_on_side_should_capture() {
     let mut b = Board::new(19, 6.5, Minimal);
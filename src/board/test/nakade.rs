@@ -0,0 +1,72 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2015 Urban Hafner, Igor Polyakov                           *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+use std::path::Path;
+use board::Board;
+use board::Coord;
+use sgf::Parser;
+
+fn board(name: &str) -> Board {
+    let path = format!("fixtures/sgf/nakade/{}.sgf", name);
+    let parser = Parser::from_path(Path::new(&path)).unwrap();
+    parser.game().unwrap().board()
+}
+
+#[test]
+fn straight_three_has_a_vital_point() {
+    assert_eq!(Some(Coord::new(2,1)), board("straight-three").nakade_point(Coord::new(2,2)));
+}
+
+#[test]
+fn bent_three_has_a_vital_point() {
+    assert_eq!(Some(Coord::new(1,1)), board("bent-three").nakade_point(Coord::new(1,3)));
+}
+
+#[test]
+fn pyramid_four_has_a_vital_point() {
+    assert_eq!(Some(Coord::new(2,1)), board("pyramid-four").nakade_point(Coord::new(1,2)));
+}
+
+#[test]
+fn straight_four_has_no_vital_point() {
+    assert_eq!(None, board("straight-four").nakade_point(Coord::new(2,2)));
+}
+
+#[test]
+fn bulky_five_has_a_vital_point() {
+    assert_eq!(Some(Coord::new(2,1)), board("bulky-five").nakade_point(Coord::new(4,1)));
+}
+
+#[test]
+fn rabbity_six_has_a_vital_point() {
+    assert_eq!(Some(Coord::new(2,2)), board("rabbity-six").nakade_point(Coord::new(1,3)));
+}
+
+#[test]
+fn the_eye_space_needs_to_be_next_to_the_coordinate() {
+    assert_eq!(None, board("straight-three").nakade_point(Coord::new(2,3)));
+}
+
+#[test]
+fn large_spaces_have_no_vital_point() {
+    assert_eq!(None, board("straight-three").nakade_point(Coord::new(5,3)));
+}
@@ -21,7 +21,7 @@
  
 #![cfg(test)]
 use std::path::Path;
-use board::{Black, Coord, Play};
+use board::{Black, Coord, Play, White};
 use sgf::Parser;
 
 #[test]
@@ -74,4 +74,36 @@ fn bottom_right_is_ladder() {
     assert_eq!(Play(Black, 17, 5), board.clone().capture_ladder(&chain).unwrap());
     assert_eq!(2, board.save_group(&chain).len());
     assert_eq!(2, board.fix_atari_no_ladder_check(&chain).len()); //it's not in atari technically, but extending works anyway
-}
\ No newline at end of file
+}
+
+#[test]
+fn capturing_moves_capture_chains_in_atari() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/tactics/capture.sgf")).unwrap();
+    let game   = parser.game().unwrap();
+    let board  = game.board();
+
+    assert_eq!(vec![Play(Black, 5, 5)], board.capturing_moves(Black));
+    assert_eq!(0, board.capturing_moves(White).len());
+}
+
+#[test]
+fn two_liberty_moves_attack_chains_that_cant_escape() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/tactics/two-liberties.sgf")).unwrap();
+    let game   = parser.game().unwrap();
+    let board  = game.board();
+    let moves  = board.two_liberty_moves(Black, Coord::new(5, 1));
+
+    assert!(moves.contains(&Play(Black, 5, 2)));
+    assert!(!moves.contains(&Play(Black, 6, 1))); //white escapes to three liberties
+}
+
+#[test]
+fn two_liberty_moves_defend_own_chains() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/tactics/two-liberties.sgf")).unwrap();
+    let game   = parser.game().unwrap();
+    let board  = game.board();
+    let mut moves = board.two_liberty_moves(Black, Coord::new(4, 9));
+    moves.sort_by_key(|m| (m.coord().col, m.coord().row));
+
+    assert_eq!(vec![Play(Black, 4, 8), Play(Black, 5, 8), Play(Black, 6, 9)], moves);
+}
@@ -0,0 +1,82 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2015 Urban Hafner, Igor Polyakov                           *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Coord;
+use board::Empty;
+use board::White;
+use fixtures::load_board;
+
+#[test]
+fn the_whole_board_can_be_pass_alive() {
+    let board = load_board("safety/pass-alive");
+    let owner = board.pass_alive();
+    assert!(owner.iter().all(|&color| color != Empty));
+    assert_eq!(Black, owner[Coord::from_gtp("A5").to_index(5)]);
+    assert_eq!(White, owner[Coord::from_gtp("E1").to_index(5)]);
+}
+
+#[test]
+fn nothing_is_pass_alive_on_an_empty_board() {
+    let board = load_board("empty");
+    assert!(board.pass_alive().iter().all(|&color| color == Empty));
+}
+
+#[test]
+fn a_chain_with_two_eyes_is_pass_alive() {
+    let board = load_board("life-and-death/alive");
+    let owner = board.pass_alive();
+    for vertex in vec!["A1", "A2", "B1", "C1", "D1", "D2"] {
+        assert_eq!(White, owner[Coord::from_gtp(vertex).to_index(9)]);
+    }
+    assert_eq!(Empty, owner[Coord::from_gtp("A3").to_index(9)]);
+}
+
+#[test]
+fn a_chain_with_one_eye_is_not_pass_alive() {
+    let board = load_board("life-and-death/dead");
+    assert!(board.pass_alive().iter().all(|&color| color == Empty));
+}
+
+#[test]
+fn opponent_stones_inside_an_eye_space_belong_to_the_owner() {
+    let board = load_board("safety/dead-stone");
+    assert_eq!(Black, board.pass_alive()[Coord::from_gtp("A5").to_index(5)]);
+}
+
+#[test]
+fn a_straight_three_is_a_single_eye_space_with_a_vital_point() {
+    let board = load_board("nakade/straight-three");
+    let spaces = board.eye_spaces(Coord::new(2,2));
+    assert_eq!(1, spaces.len());
+    assert_eq!(3, spaces[0].points().len());
+    assert_eq!(Some(Coord::new(2,1)), spaces[0].vital_point());
+}
+
+#[test]
+fn two_eyes_are_two_eye_spaces_without_vital_points() {
+    let board = load_board("life-and-death/alive");
+    let spaces = board.eye_spaces(Coord::new(2,2));
+    assert_eq!(2, spaces.len());
+    assert!(spaces.iter().all(|space| space.points().len() == 1 && space.vital_point().is_none()));
+}
@@ -0,0 +1,56 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Coord;
+use fixtures::load_board;
+
+fn coords(vertices: Vec<&'static str>) -> Vec<Coord> {
+    vertices.iter().map(|v| Coord::from_gtp(v)).collect()
+}
+
+#[test]
+fn finds_the_stones_of_both_colors_in_seki() {
+    let board = load_board("seki/eyes");
+    let expected = coords(vec!["A4", "B4", "B5", "C4", "D4", "D5", "E4"]);
+    assert_eq!(expected, board.stones_in_seki());
+}
+
+#[test]
+fn no_seki_when_one_side_can_approach() {
+    let board = load_board("seki/one-side-can-approach");
+    assert_eq!(0, board.stones_in_seki().len());
+}
+
+#[test]
+fn no_seki_without_shared_liberties() {
+    let board = load_board("score/dame");
+    assert_eq!(0, board.stones_in_seki().len());
+}
+
+#[test]
+fn removing_dead_stones_keeps_the_seki() {
+    let mut board = load_board("seki/eyes");
+    board.remove_dead_stone(&Coord::from_gtp("E2"));
+    let expected = coords(vec!["A4", "B4", "B5", "C4", "D4", "D5", "E4"]);
+    assert_eq!(expected, board.stones_in_seki());
+}
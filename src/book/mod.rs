@@ -0,0 +1,250 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Black;
+use board::Board;
+use board::Color;
+use board::Coord;
+use board::Empty;
+use board::Move;
+use board::Play;
+use board::White;
+use config::Config;
+use game::Game;
+use sgf::Parser;
+use sgf::property::Property;
+use sgf::tree::Node;
+
+use rand::Rng;
+use std::collections::HashMap;
+use std::path::Path;
+
+mod test;
+
+// The rotations and reflections of the board as matrices that are
+// applied to the coordinates relative to the center.
+const SYMMETRIES: [(i16, i16, i16, i16); 8] = [
+    (1, 0, 0, 1),
+    (0, -1, 1, 0),
+    (-1, 0, 0, -1),
+    (0, 1, -1, 0),
+    (-1, 0, 0, 1),
+    (1, 0, 0, -1),
+    (0, 1, 1, 0),
+    (0, -1, -1, 0),
+];
+
+/// An opening book that maps positions to weighted moves.
+///
+/// The book is read from SGF files. Every move in every variation of
+/// the game trees (up to a maximum depth) is added with a weight of
+/// one, so moves that appear in several variations are picked more
+/// often. If the comment of a move starts with `visits N` (as written
+/// by the `build-book` subcommand) the weight is `N` instead.
+///
+/// The positions are stored under a Zobrist hash of the stones and
+/// the player to move for all eight symmetries of the board, so that
+/// a rotated or mirrored opening is found as well.
+pub struct Book {
+    positions: HashMap<u64, Vec<(Coord, usize)>>,
+}
+
+impl Book {
+
+    pub fn new() -> Book {
+        Book {
+            positions: HashMap::new(),
+        }
+    }
+
+    /// The book from the file set in the configuration (if any and
+    /// if the book is enabled).
+    pub fn from_config(config: &Config) -> Result<Book, String> {
+        let mut book = Self::new();
+        if config.book.enabled && !config.book.file.is_empty() {
+            try!(book.load(Path::new(&config.book.file), config.book.max_depth));
+        }
+        Ok(book)
+    }
+
+    /// Adds all game trees of the SGF file.
+    pub fn load(&mut self, path: &Path, max_depth: usize) -> Result<(), String> {
+        let parser = try!(Parser::from_path(path).map_err(|e| format!("{}: {}", path.display(), e)));
+        let trees = try!(parser.collection().map_err(|e| format!("{}: {}", path.display(), e)));
+        for tree in trees.iter() {
+            try!(self.add_tree(tree, max_depth).map_err(|e| format!("{}: {}", path.display(), e)));
+        }
+        Ok(())
+    }
+
+    /// Adds the moves of all variations of the game tree that are
+    /// played before the game has `max_depth` moves.
+    pub fn add_tree(&mut self, root: &Node, max_depth: usize) -> Result<(), String> {
+        let mut path = vec!();
+        self.add_node(&mut path, root, max_depth)
+    }
+
+    fn add_node<'a>(&mut self, path: &mut Vec<&'a Node>, node: &'a Node, max_depth: usize) -> Result<(), String> {
+        let game = try!(Parser::replay(path).map_err(|e| format!("illegal move: {}", e)));
+        if game.move_count() >= max_depth {
+            return Ok(());
+        }
+        if let Some(m) = Self::move_of(node, game.size()) {
+            let board = game.board();
+            if board.next_player() == *m.color() {
+                self.add(&board, m, Self::weight_of(node));
+            }
+        }
+        path.push(node);
+        for child in node.children.iter() {
+            try!(self.add_node(path, child, max_depth));
+        }
+        path.pop();
+        Ok(())
+    }
+
+    /// The move played in the node. Passes and points that aren't on
+    /// the board are ignored.
+    pub fn move_of(node: &Node, size: u8) -> Option<Move> {
+        node.properties.iter()
+            .filter_map(|property| match *property {
+                Property::Black(Some(point)) if !point.is_pass(size) => Some((Black, point)),
+                Property::White(Some(point)) if !point.is_pass(size) => Some((White, point)),
+                _ => None
+            })
+            .next()
+            .and_then(|(color, point)| point.to_coord(size).map(|coord| Play(color, coord.col, coord.row)))
+    }
+
+    fn weight_of(node: &Node) -> usize {
+        node.properties.iter()
+            .filter_map(|property| match *property {
+                Property::Comment(ref comment) => {
+                    let words: Vec<&str> = comment.split_whitespace().collect();
+                    match (words.get(0), words.get(1)) {
+                        (Some(&"visits"), Some(visits)) => visits.parse().ok(),
+                        _ => None
+                    }
+                },
+                _ => None
+            })
+            .next()
+            .unwrap_or(1)
+    }
+
+    /// Adds the move (and its rotations and reflections) to the
+    /// position on the board.
+    pub fn add(&mut self, board: &Board, m: Move, weight: usize) {
+        let size = board.size();
+        for symmetry in 0..SYMMETRIES.len() {
+            let coord = Self::transform(m.coord(), size, symmetry);
+            let moves = self.positions.entry(Self::key(board, symmetry)).or_insert(vec!());
+            match moves.iter().position(|&(c, _)| c == coord) {
+                Some(i) => moves[i].1 += weight,
+                None => moves.push((coord, weight))
+            }
+        }
+    }
+
+    /// Picks one of the legal book moves of the position at random,
+    /// with the weights as probabilities. Returns `None` if the
+    /// position isn't in the book or if `color` isn't the player to
+    /// move.
+    pub fn pick<R: Rng>(&self, game: &Game, color: Color, rng: &mut R) -> Option<Move> {
+        let board = game.board();
+        if board.next_player() != color {
+            return None;
+        }
+        let moves: Vec<(Move, usize)> = match self.positions.get(&Self::key(&board, 0)) {
+            Some(moves) => moves.iter()
+                .map(|&(c, weight)| (Play(color, c.col, c.row), weight))
+                .filter(|&(m, _)| game.play(m).is_ok())
+                .collect(),
+            None => return None
+        };
+        let total = moves.iter().fold(0, |acc, &(_, weight)| acc + weight);
+        if total == 0 {
+            return None;
+        }
+        let mut r = rng.gen_range(0, total);
+        for (m, weight) in moves {
+            if r < weight {
+                return Some(m);
+            }
+            r -= weight;
+        }
+        None
+    }
+
+    /// The number of positions in the book (counting each symmetry
+    /// separately).
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// The same hash for all rotations and reflections of the
+    /// position on the board.
+    pub fn canonical_key(board: &Board) -> u64 {
+        (0..SYMMETRIES.len()).map(|symmetry| Self::key(board, symmetry)).min().unwrap()
+    }
+
+    fn transform(coord: Coord, size: u8, symmetry: usize) -> Coord {
+        let (a, b, c, d) = SYMMETRIES[symmetry];
+        let n = size as i16 + 1;
+        let dx = 2 * coord.col as i16 - n;
+        let dy = 2 * coord.row as i16 - n;
+        let x = a * dx + b * dy;
+        let y = c * dx + d * dy;
+        Coord::new(((x + n) / 2) as u8, ((y + n) / 2) as u8)
+    }
+
+    // The hash has to be the same in every run of the program (unlike
+    // the one of `Game`), so the keys are derived from the points
+    // with SplitMix64.
+    fn key(board: &Board, symmetry: usize) -> u64 {
+        let size = board.size();
+        let mut key = Self::mix(size as u64);
+        for coord in Coord::for_board_size(size) {
+            let color = board.color(&coord);
+            if color != Empty {
+                let index = Self::transform(coord, size, symmetry).to_index(size) as u64;
+                let offset = if color == White { 1 } else { 0 };
+                key ^= Self::mix(1024 + 2 * index + offset);
+            }
+        }
+        if board.next_player() == White {
+            key ^= Self::mix(512);
+        }
+        key
+    }
+
+    fn mix(value: u64) -> u64 {
+        let mut z = value.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+}
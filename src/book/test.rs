@@ -0,0 +1,119 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Play;
+use board::White;
+use game::Game;
+use ruleset::KgsChinese;
+use sgf::Parser;
+use super::Book;
+
+use rand::SeedableRng;
+use rand::XorShiftRng;
+use std::path::Path;
+
+fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([1, 2, 3, 4])
+}
+
+fn book(max_depth: usize) -> Book {
+    let mut book = Book::new();
+    book.load(Path::new("fixtures/sgf/book/opening.sgf"), max_depth).unwrap();
+    book
+}
+
+#[test]
+fn plays_the_first_move_from_the_book() {
+    let game = Game::new(9, 7.0, KgsChinese);
+    assert_eq!(Some(Play(Black, 5, 5)), book(10).pick(&game, Black, &mut rng()));
+}
+
+#[test]
+fn finds_rotated_and_mirrored_positions() {
+    let game = Game::new(9, 7.0, KgsChinese)
+        .play(Play(Black, 5, 5)).unwrap()
+        .play(Play(White, 3, 3)).unwrap();
+    assert_eq!(Some(Play(Black, 7, 7)), book(10).pick(&game, Black, &mut rng()));
+}
+
+#[test]
+fn picks_among_the_moves_by_weight() {
+    let game = Game::new(9, 7.0, KgsChinese).play(Play(Black, 5, 5)).unwrap();
+    let book = book(10);
+    let mut rng = rng();
+    let mut approaches = 0;
+    for _ in 0..200 {
+        let m = book.pick(&game, White, &mut rng).unwrap();
+        if m.coord().col == 5 || m.coord().row == 5 {
+            approaches += 1;
+        }
+    }
+    // Both variations have the same weight.
+    assert!(approaches > 70 && approaches < 130);
+}
+
+#[test]
+fn uses_the_visits_in_the_comments_as_weights() {
+    let sgf = "(;SZ[9](;B[ee]C[visits 1 wins 0])(;B[cc]C[visits 99 wins 50]))";
+    let mut book = Book::new();
+    book.add_tree(&Parser::new(sgf.to_string()).tree().unwrap(), 10).unwrap();
+    let game = Game::new(9, 7.0, KgsChinese);
+    let mut rng = rng();
+    let center = (0..100)
+        .filter(|_| book.pick(&game, Black, &mut rng) == Some(Play(Black, 5, 5)))
+        .count();
+    assert!(center < 10);
+}
+
+#[test]
+fn returns_nothing_for_positions_not_in_the_book() {
+    let game = Game::new(9, 7.0, KgsChinese).play(Play(Black, 1, 1)).unwrap();
+    assert_eq!(None, book(10).pick(&game, White, &mut rng()));
+}
+
+#[test]
+fn returns_nothing_if_the_color_is_not_to_move() {
+    let game = Game::new(9, 7.0, KgsChinese);
+    assert_eq!(None, book(10).pick(&game, White, &mut rng()));
+}
+
+#[test]
+fn only_loads_the_moves_up_to_the_maximum_depth() {
+    let book = book(1);
+    let game = Game::new(9, 7.0, KgsChinese);
+    assert_eq!(Some(Play(Black, 5, 5)), book.pick(&game, Black, &mut rng()));
+    let game = game.play(Play(Black, 5, 5)).unwrap();
+    assert_eq!(None, book.pick(&game, White, &mut rng()));
+}
+
+#[test]
+fn is_empty_without_a_file() {
+    assert!(Book::new().is_empty());
+}
+
+#[test]
+fn reports_missing_files() {
+    let result = Book::new().load(Path::new("fixtures/sgf/book/missing.sgf"), 10);
+    assert!(result.unwrap_err().starts_with("fixtures/sgf/book/missing.sgf"));
+}
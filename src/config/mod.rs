@@ -25,12 +25,82 @@
 use ruleset::Ruleset;
 
 use num_cpus;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::stderr;
-use std::process::exit;
 use toml;
 
+mod test;
+
+/// A problem with a configuration file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigError {
+    /// The line of the file the problem is on (if it's known).
+    pub line: Option<usize>,
+    /// The full name of the setting (e.g. `tree.rave_equiv`) if the
+    /// problem is with a single setting.
+    pub key: Option<String>,
+    /// What's wrong.
+    pub kind: ConfigErrorKind,
+}
+
+/// The kinds of problems a configuration file can have.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigErrorKind {
+    /// The file couldn't be read.
+    Read(String),
+    /// The file isn't valid TOML.
+    Syntax(String),
+    /// There is no such setting. Holds the most similar setting if
+    /// there is one that's close enough to be a typo.
+    UnknownKey(Option<String>),
+    /// The value has the wrong type. Holds the expected and the
+    /// actual type.
+    WrongType(String, String),
+    /// The value has the right type, but isn't allowed. Holds a
+    /// description of the allowed values and the actual value.
+    OutOfRange(String, String),
+}
+
+impl ConfigError {
+
+    fn new(line: Option<usize>, key: Option<String>, kind: ConfigErrorKind) -> ConfigError {
+        ConfigError { line: line, key: key, kind: kind }
+    }
+
+}
+
+impl fmt::Display for ConfigError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            try!(write!(f, "line {}: ", line));
+        }
+        let key = self.key.clone().unwrap_or(String::new());
+        match self.kind {
+            ConfigErrorKind::Read(ref message) => write!(f, "{}", message),
+            ConfigErrorKind::Syntax(ref message) => write!(f, "{}", message),
+            ConfigErrorKind::UnknownKey(Some(ref similar)) =>
+                write!(f, "unknown setting {:?} (did you mean {:?}?)", key, similar),
+            ConfigErrorKind::UnknownKey(None) => write!(f, "unknown setting {:?}", key),
+            ConfigErrorKind::WrongType(ref expected, ref found) |
+            ConfigErrorKind::OutOfRange(ref expected, ref found) =>
+                write!(f, "expected {} for {:?} but found {}", expected, key, found),
+        }
+    }
+
+}
+
+/// Turns the errors of a configuration file into a message with one
+/// line per error.
+pub fn describe_errors(filename: &str, errors: &[ConfigError]) -> String {
+    let lines: Vec<String> = errors.iter()
+        .map(|error| format!("{}: {}", filename, error))
+        .collect();
+    lines.join("\n")
+}
+
 trait FromToml {
 
     fn as_float(table: &toml::Table, field: &'static str) -> f32 {
@@ -59,25 +129,174 @@ trait FromToml {
         }
     }
 
+    fn as_string(table: &toml::Table, field: &'static str) -> String {
+        let value = &table[field];
+        match value.as_str() {
+            Some(v) => String::from(v),
+            None => Self::fail(field, value, "string")
+        }
+    }
+
+    // `Config::new()` replaces invalid values with the defaults
+    // before the sections are built, so this only happens if the
+    // defaults themselves are broken.
     fn fail(field: &'static str, value: &toml::Value, expected: &'static str) -> ! {
         let long_name = match Self::name() {
             Some(name) => format!("{}.{}", name, field),
             None => format!("{}", field)
         };
-        println!("Expected {} for {:?} but found {}", expected, long_name, value.type_str());
-        exit(1)
+        panic!("Expected {} for {:?} but found {}", expected, long_name, value.type_str())
     }
 
-
     fn name() -> Option<&'static str>;
 
 }
 
+/// Holds the settings related to the opening book.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookConfig {
+    /// If `false` the book isn't loaded and all moves are searched.
+    pub enabled: bool,
+    /// The path to an SGF file with the opening book (see
+    /// `book::Book` for the format). If it's empty no book is used.
+    pub file: String,
+    /// The book is only used for the first `max_depth` moves of a
+    /// game. Deeper positions aren't loaded from the file either.
+    pub max_depth: usize,
+}
+
+impl BookConfig {
+
+    fn new(value: toml::Value, default: toml::Value) -> BookConfig {
+        let opts = value.as_table().unwrap().clone();
+        let default_table = default.as_table().unwrap().clone();
+        let mut table = toml::Table::new();
+        table.extend(default_table);
+        table.extend(opts);
+        BookConfig {
+            enabled: Self::as_bool(&table, "enabled"),
+            file: Self::as_string(&table, "file"),
+            max_depth: Self::as_integer(&table, "max_depth"),
+        }
+    }
+
+}
+
+impl FromToml for BookConfig {
+    fn name() -> Option<&'static str> { Some("book") }
+}
+
+/// Holds all settings related to adjusting the komi used in the
+/// playouts. In handicap games (and other lopsided games) almost all
+/// playouts are either won or lost, which makes it impossible for
+/// the search to distinguish between good and bad moves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicKomiConfig {
+    /// Only used by the adaptive mode. If the win rate of the engine
+    /// at the end of a search is above this value the komi is
+    /// changed by `adaptive_step` to the disadvantage of the engine.
+    pub adaptive_high: f32,
+    /// Only used by the adaptive mode. If the win rate of the engine
+    /// at the end of a search is below this value the komi is
+    /// changed by `adaptive_step` to the advantage of the engine.
+    pub adaptive_low: f32,
+    /// The maximum number of points the adaptive mode may change the
+    /// komi by (in either direction).
+    pub adaptive_max: f32,
+    /// The number of points the komi is changed by after each move
+    /// in the adaptive mode.
+    pub adaptive_step: f32,
+    /// Only used by the linear mode. The number of moves after which
+    /// the komi is back to the real komi.
+    pub handicap_moves: usize,
+    /// Only used by the linear mode. The number of points each
+    /// handicap stone is worth at the start of the game.
+    pub handicap_stone_value: f32,
+    /// How to adjust the komi. See `DynamicKomiMode` for the
+    /// possible values.
+    pub mode: DynamicKomiMode,
+}
+
+/// The ways the komi can be adjusted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DynamicKomiMode {
+    /// Adjust the komi based on the win rate at the root of the tree
+    /// after each move. Set as "adaptive" in the configuration file.
+    Adaptive,
+    /// Gives white extra komi for the handicap stones that decreases
+    /// linearly over the course of the game. Set as "linear" in the
+    /// configuration file.
+    Linear,
+    /// Always use the real komi. Set as "off" in the configuration
+    /// file. This is the default.
+    Off,
+}
+
+impl DynamicKomiConfig {
+
+    fn new(value: toml::Value, default: toml::Value) -> DynamicKomiConfig {
+        let opts = value.as_table().unwrap().clone();
+        let default_table = default.as_table().unwrap().clone();
+        let mut table = toml::Table::new();
+        table.extend(default_table);
+        table.extend(opts);
+        DynamicKomiConfig {
+            adaptive_high: Self::as_float(&table, "adaptive_high"),
+            adaptive_low: Self::as_float(&table, "adaptive_low"),
+            adaptive_max: Self::as_float(&table, "adaptive_max"),
+            adaptive_step: Self::as_float(&table, "adaptive_step"),
+            handicap_moves: Self::as_integer(&table, "handicap_moves"),
+            handicap_stone_value: Self::as_float(&table, "handicap_stone_value"),
+            mode: Self::as_mode(&table, "mode"),
+        }
+    }
+
+    fn as_mode(table: &toml::Table, field: &'static str) -> DynamicKomiMode {
+        match Self::as_string(table, field).as_ref() {
+            "adaptive" => DynamicKomiMode::Adaptive,
+            "linear" => DynamicKomiMode::Linear,
+            "off" => DynamicKomiMode::Off,
+            _ => Self::fail(field, &table[field], "\"adaptive\", \"linear\" or \"off\"")
+        }
+    }
+
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let mode = match self.mode {
+            DynamicKomiMode::Adaptive => "adaptive",
+            DynamicKomiMode::Linear => "linear",
+            DynamicKomiMode::Off => "off",
+        };
+        let mut table = toml::Table::new();
+        table.insert("adaptive_high".to_string(), float(self.adaptive_high));
+        table.insert("adaptive_low".to_string(), float(self.adaptive_low));
+        table.insert("adaptive_max".to_string(), float(self.adaptive_max));
+        table.insert("adaptive_step".to_string(), float(self.adaptive_step));
+        table.insert("handicap_moves".to_string(), integer(self.handicap_moves));
+        table.insert("handicap_stone_value".to_string(), float(self.handicap_stone_value));
+        table.insert("mode".to_string(), toml::Value::String(mode.to_string()));
+        table
+    }
+
+}
+
+impl FromToml for DynamicKomiConfig {
+    fn name() -> Option<&'static str> { Some("dynamic_komi") }
+}
+
 /// Contains all settings that are related to the search tree.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TreeConfig {
     /// The number of plays before a leaf will be expanded.
     pub expand_after: usize,
+    /// The maximum number of nodes in the tree. Once it's reached
+    /// the leaves aren't expanded anymore until the tree is pruned
+    /// to the subtree of the next move. 0 means no limit.
+    pub max_nodes: usize,
+    /// Same as `max_nodes`, but given as the memory (in megabytes)
+    /// the nodes may use. 0 means no limit. If both are set the
+    /// smaller limit applies.
+    pub max_memory_mb: usize,
     /// Configuration factor for the RAVE part of the node selection
     /// algorithm. There's no clear way to set this value. It's best
     /// to use parameter optimization to find the best value.
@@ -85,6 +304,27 @@ pub struct TreeConfig {
     /// A float between 0.0 and 1.0 that is the part of a win recorded
     /// in the tree nodes to signify the score of the playout.
     pub score_weight: f32,
+    /// The memory (in megabytes) to use for the transposition table
+    /// that shares the statistics of nodes that represent the same
+    /// position (and player to move). Setting it to 0 turns the
+    /// transposition table off.
+    pub transposition_table_mb: usize,
+    /// What to do when a new position needs to be stored in the
+    /// transposition table but both slots it can go into are already
+    /// taken. See `Replacement` for the possible values.
+    pub transposition_replacement: Replacement,
+}
+
+/// The replacement policy of the transposition table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Replacement {
+    /// Always replace the second of the two entries. Set as
+    /// "always" in the configuration file.
+    Always,
+    /// Replace the entry with fewer plays. This keeps the well
+    /// explored positions around. Set as "visits" in the
+    /// configuration file.
+    Visits,
 }
 
 impl TreeConfig {
@@ -97,11 +337,40 @@ impl TreeConfig {
         table.extend(opts);
         TreeConfig {
             expand_after: Self::as_integer(&table, "expand_after"),
+            max_nodes: Self::as_integer(&table, "max_nodes"),
+            max_memory_mb: Self::as_integer(&table, "max_memory_mb"),
             rave_equiv: Self::as_float(&table, "rave_equiv"),
             score_weight: Self::as_float(&table, "score_weight"),
+            transposition_table_mb: Self::as_integer(&table, "transposition_table_mb"),
+            transposition_replacement: Self::as_replacement(&table, "transposition_replacement"),
         }
     }
 
+    fn as_replacement(table: &toml::Table, field: &'static str) -> Replacement {
+        match Self::as_string(table, field).as_ref() {
+            "always" => Replacement::Always,
+            "visits" => Replacement::Visits,
+            _ => Self::fail(field, &table[field], "\"always\" or \"visits\"")
+        }
+    }
+
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let replacement = match self.transposition_replacement {
+            Replacement::Always => "always",
+            Replacement::Visits => "visits",
+        };
+        let mut table = toml::Table::new();
+        table.insert("expand_after".to_string(), integer(self.expand_after));
+        table.insert("max_memory_mb".to_string(), integer(self.max_memory_mb));
+        table.insert("max_nodes".to_string(), integer(self.max_nodes));
+        table.insert("rave_equiv".to_string(), float(self.rave_equiv));
+        table.insert("score_weight".to_string(), float(self.score_weight));
+        table.insert("transposition_replacement".to_string(), toml::Value::String(replacement.to_string()));
+        table.insert("transposition_table_mb".to_string(), integer(self.transposition_table_mb));
+        table
+    }
+
 }
 
 impl FromToml for TreeConfig {
@@ -110,7 +379,7 @@ impl FromToml for TreeConfig {
 
 /// Holds all settings related to initializing the leaves of the
 /// search tree with prior values for plays and wins.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PriorsConfig {
     /// When calculating the number of wins and plays a node has (e.g.
     /// when calculating the win rate) this is the weight the priors
@@ -133,6 +402,12 @@ pub struct PriorsConfig {
     /// moves on the third line. This is only applied if the area
     /// around the move of a Manhattan distance of three is empty.
     pub empty: usize,
+    /// The number of prior plays to assign when one of the large
+    /// patterns (see `PatternsConfig`) matches. The prior wins are
+    /// set so that the prior win rate is `gamma / (gamma + 1)`, i.e.
+    /// a pattern with a gamma above 1.0 is a positive prior and one
+    /// below 1.0 a negative prior.
+    pub large_patterns: usize,
     /// The number of prior plays to start with. This is useful to
     /// simplify the calculations as we can avoid 0 values.
     pub neutral_plays: usize,
@@ -161,6 +436,7 @@ impl PriorsConfig {
             capture_many: Self::as_integer(&table, "capture_many"),
             capture_one: Self::as_integer(&table, "capture_one"),
             empty: Self::as_integer(&table, "empty"),
+            large_patterns: Self::as_integer(&table, "large_patterns"),
             neutral_plays: Self::as_integer(&table, "neutral_plays"),
             neutral_wins: Self::as_integer(&table, "neutral_wins"),
             patterns: Self::as_integer(&table, "patterns"),
@@ -168,6 +444,21 @@ impl PriorsConfig {
         }
     }
 
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let mut table = toml::Table::new();
+        table.insert("best_move_factor".to_string(), float(self.best_move_factor));
+        table.insert("capture_many".to_string(), integer(self.capture_many));
+        table.insert("capture_one".to_string(), integer(self.capture_one));
+        table.insert("empty".to_string(), integer(self.empty));
+        table.insert("large_patterns".to_string(), integer(self.large_patterns));
+        table.insert("neutral_plays".to_string(), integer(self.neutral_plays));
+        table.insert("neutral_wins".to_string(), integer(self.neutral_wins));
+        table.insert("patterns".to_string(), integer(self.patterns));
+        table.insert("self_atari".to_string(), integer(self.self_atari));
+        table
+    }
+
 }
 
 impl FromToml for PriorsConfig {
@@ -175,7 +466,7 @@ impl FromToml for PriorsConfig {
 }
 
 /// Holds all settings related to time control.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TimeControlConfig {
     /// Scaling factor for allocating the time for the next move. We
     /// devide the remaining time by `c * <EMPTY INTERSECTION COUNT>`.
@@ -194,6 +485,11 @@ pub struct TimeControlConfig {
     /// Minimum number of stones to use when calculating the budget
     /// for the next move.
     pub min_stones: usize,
+    /// If `true` the engine keeps searching on the opponent's time
+    /// after a `genmove`, i.e. it uses all threads while the
+    /// opponent is thinking. Can be changed at runtime with the
+    /// `imrscl-ponder` GTP command.
+    pub ponder: bool,
 }
 
 impl TimeControlConfig {
@@ -209,8 +505,21 @@ impl TimeControlConfig {
             fastplay_budget: Self::as_float(&table, "fastplay_budget"),
             fastplay_threshold: Self::as_float(&table, "fastplay_threshold"),
             min_stones: Self::as_integer(&table, "min_stones"),
+            ponder: Self::as_bool(&table, "ponder"),
         }
     }
+
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let mut table = toml::Table::new();
+        table.insert("c".to_string(), float(self.c));
+        table.insert("fastplay_budget".to_string(), float(self.fastplay_budget));
+        table.insert("fastplay_threshold".to_string(), float(self.fastplay_threshold));
+        table.insert("min_stones".to_string(), integer(self.min_stones));
+        table.insert("ponder".to_string(), toml::Value::Boolean(self.ponder));
+        table
+    }
+
 }
 
 impl FromToml for TimeControlConfig {
@@ -218,11 +527,15 @@ impl FromToml for TimeControlConfig {
 }
 
 /// Holds settings related to the playout policy
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PlayoutConfig {
     /// The probability of checking for atari moves (and playing one
     /// if there are any). Set to 1.0 to always check.
     pub atari_check: f32,
+    /// The probability of checking for opponent chains in atari (and
+    /// capturing one of them if there are any).
+    /// Off (0.0) by default.
+    pub capture_probability: f32,
     /// The probability of using the ladder checker (which is
     /// expensive) during atari resolution. Set to 1.0 to always use
     /// it.
@@ -230,13 +543,64 @@ pub struct PlayoutConfig {
     /// The number of most recently played moves to consider when
     /// selecting moves based on heuristics.
     pub last_moves_for_heuristics: usize,
+    /// The probability of checking for an eye space next to the last
+    /// move that has a vital point (e.g. a bent three) and playing
+    /// it.
+    /// Off (0.0) by default.
+    pub nakade_probability: f32,
     /// The probability of playing a move that was found by trying to
     /// match patterns on the current board. We don't want to always
     /// play those moves as this would reduce the random element of
     /// the playouts too much.
     pub pattern_probability: f32,
+    /// If `true` and large patterns are loaded (see
+    /// `PatternsConfig`) the pattern move is picked at random, with
+    /// the gammas of the patterns as weights. Otherwise the first
+    /// move that matches one of the 3x3 patterns is played.
+    pub pattern_weights: bool,
     /// ???
     pub play_in_middle_of_eye: f32,
+    /// How the moves of the playouts are selected. See
+    /// `PlayoutPolicy` for the possible values.
+    pub policy: PlayoutPolicy,
+    /// The probability of checking the chains with two liberties
+    /// next to the last move and attacking (or defending) one of
+    /// them.
+    /// Off (0.0) by default.
+    pub two_liberties_probability: f32,
+    /// The gamma of moves that put an opponent chain into atari
+    /// (only used by the weighted policy). Gammas learned with
+    /// `learn-patterns` take precedence over all of the
+    /// `weighted_*` settings.
+    pub weighted_atari: f32,
+    /// The gamma of moves that capture opponent stones.
+    pub weighted_capture: f32,
+    /// The gamma of moves close to the last move (neighbours,
+    /// diagonals and two points away on a line).
+    pub weighted_distance: f32,
+    /// The gamma of moves that extend one of our own chains in
+    /// atari.
+    pub weighted_escape: f32,
+    /// The gamma of moves that match one of the 3x3 patterns (used
+    /// if no large pattern matches).
+    pub weighted_pattern: f32,
+    /// The gamma of moves that put one of our own chains into atari.
+    pub weighted_self_atari: f32,
+}
+
+/// The policy used to select the moves of the playouts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PlayoutPolicy {
+    /// Try to save chains in atari, then play a pattern move close
+    /// to the last moves and finally a random move (see
+    /// `atari_check` and `pattern_probability`). Set as "cascade" in
+    /// the configuration file.
+    Cascade,
+    /// Score every legal point with the gammas of its pattern and
+    /// its tactical features and pick a move at random with the
+    /// scores as weights. The scores are updated incrementally after
+    /// each move. Set as "weighted" in the configuration file.
+    Weighted,
 }
 
 impl PlayoutConfig {
@@ -249,21 +613,94 @@ impl PlayoutConfig {
         table.extend(opts);
         PlayoutConfig {
             atari_check: Self::as_float(&table, "atari_check"),
+            capture_probability: Self::as_float(&table, "capture_probability"),
             ladder_check: Self::as_float(&table, "ladder_check"),
             last_moves_for_heuristics: Self::as_integer(&table, "last_moves_for_heuristics"),
+            nakade_probability: Self::as_float(&table, "nakade_probability"),
             pattern_probability: Self::as_float(&table, "pattern_probability"),
+            pattern_weights: Self::as_bool(&table, "pattern_weights"),
             play_in_middle_of_eye: Self::as_float(&table, "play_in_middle_of_eye"),
+            policy: Self::as_policy(&table, "policy"),
+            two_liberties_probability: Self::as_float(&table, "two_liberties_probability"),
+            weighted_atari: Self::as_float(&table, "weighted_atari"),
+            weighted_capture: Self::as_float(&table, "weighted_capture"),
+            weighted_distance: Self::as_float(&table, "weighted_distance"),
+            weighted_escape: Self::as_float(&table, "weighted_escape"),
+            weighted_pattern: Self::as_float(&table, "weighted_pattern"),
+            weighted_self_atari: Self::as_float(&table, "weighted_self_atari"),
         }
     }
 
+    fn as_policy(table: &toml::Table, field: &'static str) -> PlayoutPolicy {
+        match Self::as_string(table, field).as_ref() {
+            "cascade" => PlayoutPolicy::Cascade,
+            "weighted" => PlayoutPolicy::Weighted,
+            _ => Self::fail(field, &table[field], "\"cascade\" or \"weighted\"")
+        }
+    }
+
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let policy = match self.policy {
+            PlayoutPolicy::Cascade => "cascade",
+            PlayoutPolicy::Weighted => "weighted",
+        };
+        let mut table = toml::Table::new();
+        table.insert("atari_check".to_string(), float(self.atari_check));
+        table.insert("capture_probability".to_string(), float(self.capture_probability));
+        table.insert("ladder_check".to_string(), float(self.ladder_check));
+        table.insert("last_moves_for_heuristics".to_string(), integer(self.last_moves_for_heuristics));
+        table.insert("nakade_probability".to_string(), float(self.nakade_probability));
+        table.insert("pattern_probability".to_string(), float(self.pattern_probability));
+        table.insert("pattern_weights".to_string(), toml::Value::Boolean(self.pattern_weights));
+        table.insert("play_in_middle_of_eye".to_string(), float(self.play_in_middle_of_eye));
+        table.insert("policy".to_string(), toml::Value::String(policy.to_string()));
+        table.insert("two_liberties_probability".to_string(), float(self.two_liberties_probability));
+        table.insert("weighted_atari".to_string(), float(self.weighted_atari));
+        table.insert("weighted_capture".to_string(), float(self.weighted_capture));
+        table.insert("weighted_distance".to_string(), float(self.weighted_distance));
+        table.insert("weighted_escape".to_string(), float(self.weighted_escape));
+        table.insert("weighted_pattern".to_string(), float(self.weighted_pattern));
+        table.insert("weighted_self_atari".to_string(), float(self.weighted_self_atari));
+        table
+    }
+
 }
 
 impl FromToml for PlayoutConfig {
     fn name() -> Option<&'static str> { Some("playout") }
 }
 
+/// Holds the settings related to the large patterns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternsConfig {
+    /// The path to a file with large patterns and their gamma values
+    /// (see `patterns::Dictionary` for the format). If it's empty
+    /// only the built-in 3x3 patterns are used.
+    pub file: String,
+}
+
+impl PatternsConfig {
+
+    fn new(value: toml::Value, default: toml::Value) -> PatternsConfig {
+        let opts = value.as_table().unwrap().clone();
+        let default_table = default.as_table().unwrap().clone();
+        let mut table = toml::Table::new();
+        table.extend(default_table);
+        table.extend(opts);
+        PatternsConfig {
+            file: Self::as_string(&table, "file"),
+        }
+    }
+
+}
+
+impl FromToml for PatternsConfig {
+    fn name() -> Option<&'static str> { Some("patterns") }
+}
+
 /// Hold settings related to estimating the score of a board
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ScoringConfig {
     /// Prior for the value of neutral owners (i.e. dame points). This
     /// increases the number of playouts necessary to generate an
@@ -289,6 +726,14 @@ impl ScoringConfig {
         }
     }
 
+    /// The current values (for `Config::params()`).
+    fn table(&self) -> toml::Table {
+        let mut table = toml::Table::new();
+        table.insert("ownership_cutoff".to_string(), float(self.ownership_cutoff));
+        table.insert("ownership_prior".to_string(), integer(self.ownership_prior));
+        table
+    }
+
 }
 
 impl FromToml for ScoringConfig {
@@ -299,8 +744,14 @@ impl FromToml for ScoringConfig {
 /// (inside an `Arc`) most of the app and contains all possible
 /// settings and variables that can be tuned. Everything in here can
 /// be set in a configuration file in TOML format.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Config {
+    /// Holds a configuration object that contains everything related
+    /// to the opening book.
+    pub book: BookConfig,
+    /// Holds a configuration object that contains everything related
+    /// to adjusting the komi in handicap and other lopsided games.
+    pub dynamic_komi: DynamicKomiConfig,
     /// If `true` output GoGui live graphics commands on stderr so
     /// that you can see what the engine is "thinking" when playing or
     /// observing a game via GoGui
@@ -310,6 +761,9 @@ pub struct Config {
     /// running.
     pub log: bool,
     /// Holds a configuration object that contains everything related
+    /// to the large patterns.
+    pub patterns: PatternsConfig,
+    /// Holds a configuration object that contains everything related
     /// to the playout policy.
     pub playout: PlayoutConfig,
     /// Holds a configuration object that contains everything related
@@ -344,7 +798,7 @@ impl Config {
     /// Uses the TOML returned by `Config::toml()` and returns a
     /// `Config` object that encodes this data.
     pub fn default(log: bool, gfx: bool, ruleset: Ruleset) -> Config {
-        Self::new(String::from(""), Self::toml(), log, gfx, ruleset)
+        Self::new(String::from(""), Self::toml(), log, gfx, ruleset).unwrap()
     }
 
     /// Returns a string representation of the default configuration
@@ -358,24 +812,43 @@ impl Config {
     /// object from the data. The file doesn't need to contain all
     /// possible fields of `Config` or the various structs it
     /// contains. What's missing is taken from `Config::toml()`.
-    pub fn from_file(filename: String, log: bool, gfx: bool, ruleset: Ruleset) -> Config {
-        let mut file = File::open(filename).unwrap();
+    ///
+    /// Returns all problems with the file (see
+    /// `Config::from_toml()`) if there are any.
+    pub fn from_file(filename: String, log: bool, gfx: bool, ruleset: Ruleset) -> Result<Config, Vec<ConfigError>> {
         let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
-        Self::new(contents, Self::toml(), log, gfx, ruleset)
+        try!(File::open(filename)
+             .and_then(|mut file| file.read_to_string(&mut contents))
+             .map_err(|e| vec!(ConfigError::new(None, None, ConfigErrorKind::Read(format!("{}", e))))));
+        Self::from_toml(contents, log, gfx, ruleset)
+    }
+
+    /// Like `Config::from_file()`, but takes the TOML itself instead
+    /// of the name of a file containing it.
+    ///
+    /// Unknown settings (e.g. typos), values of the wrong type and
+    /// values outside of the allowed range (e.g. probabilities above
+    /// 1.0) are all errors. The errors are sorted by line.
+    pub fn from_toml(toml_str: String, log: bool, gfx: bool, ruleset: Ruleset) -> Result<Config, Vec<ConfigError>> {
+        Self::new(toml_str, Self::toml(), log, gfx, ruleset)
     }
 
-    fn new(toml_str: String, default_toml_str: String, log: bool, gfx: bool, ruleset: Ruleset) -> Config {
-        let opts = toml::Parser::new(&toml_str).parse().unwrap();
-        let default_table = toml::Parser::new(&default_toml_str).parse().unwrap();
+    fn new(toml_str: String, default_toml_str: String, log: bool, gfx: bool, ruleset: Ruleset) -> Result<Config, Vec<ConfigError>> {
+        let mut default_table = toml::Parser::new(&default_toml_str).parse().unwrap();
         let threads = toml::Parser::new(&format!("threads = {}", num_cpus::get())).parse().unwrap();
+        default_table.extend(threads);
+        let mut errors = vec!();
+        let opts = Self::parse(&toml_str, &mut errors);
+        let opts = Self::validate(&toml_str, opts, &default_table, &mut errors);
         let mut table = toml::Table::new();
         table.extend(default_table.clone());
-        table.extend(threads.clone());
-        table.extend(opts.clone());
-        Config {
+        table.extend(opts);
+        let config = Config {
+            book: BookConfig::new(table["book"].clone(), default_table["book"].clone()),
+            dynamic_komi: DynamicKomiConfig::new(table["dynamic_komi"].clone(), default_table["dynamic_komi"].clone()),
             gfx: gfx,
             log: log,
+            patterns: PatternsConfig::new(table["patterns"].clone(), default_table["patterns"].clone()),
             playout: PlayoutConfig::new(table["playout"].clone(), default_table["playout"].clone()),
             priors: PriorsConfig::new(table["priors"].clone(), default_table["priors"].clone()),
             ruleset: ruleset,
@@ -383,7 +856,122 @@ impl Config {
             threads: Self::as_integer(&table, "threads"),
             time_control: TimeControlConfig::new(table["time_control"].clone(), default_table["time_control"].clone()),
             tree: TreeConfig::new(table["tree"].clone(), default_table["tree"].clone()),
+        };
+        errors.extend(config.range_errors(&toml_str));
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            // Errors without a line go last.
+            errors.sort_by_key(|error| (error.line.is_none(), error.line));
+            Err(errors)
+        }
+    }
+
+    fn parse(toml_str: &str, errors: &mut Vec<ConfigError>) -> toml::Table {
+        let mut parser = toml::Parser::new(toml_str);
+        match parser.parse() {
+            Some(table) => table,
+            None => {
+                for error in parser.errors.iter() {
+                    let (line, _) = parser.to_linecol(error.lo);
+                    let kind = ConfigErrorKind::Syntax(error.desc.clone());
+                    errors.push(ConfigError::new(Some(line + 1), None, kind));
+                }
+                toml::Table::new()
+            }
+        }
+    }
+
+    // Checks the names and types of the settings against the
+    // defaults. Only the valid settings are returned.
+    fn validate(toml_str: &str, opts: toml::Table, defaults: &toml::Table, errors: &mut Vec<ConfigError>) -> toml::Table {
+        let mut known = vec!();
+        for (name, value) in defaults.iter() {
+            match value.as_table() {
+                Some(section) => known.extend(section.keys().map(|field| format!("{}.{}", name, field))),
+                None => known.push(name.clone())
+            }
+        }
+        let mut valid = toml::Table::new();
+        for (name, value) in opts {
+            let default = match defaults.get(&name) {
+                Some(default) => default,
+                None => {
+                    let kind = ConfigErrorKind::UnknownKey(similar(&name, &known));
+                    errors.push(ConfigError::new(line_of(toml_str, &name), Some(name), kind));
+                    continue;
+                }
+            };
+            match (default, value) {
+                (&toml::Value::Table(ref default_section), toml::Value::Table(section)) => {
+                    let mut valid_section = toml::Table::new();
+                    for (field, value) in section {
+                        let key = format!("{}.{}", name, field);
+                        let line = line_of(toml_str, &key);
+                        let kind = match default_section.get(&field) {
+                            Some(default) => check_value(&key, &value, default),
+                            None => Some(ConfigErrorKind::UnknownKey(similar(&key, &known)))
+                        };
+                        match kind {
+                            Some(kind) => errors.push(ConfigError::new(line, Some(key), kind)),
+                            None => { valid_section.insert(field, value); }
+                        }
+                    }
+                    valid.insert(name, toml::Value::Table(valid_section));
+                },
+                (default, value) => {
+                    match check_value(&name, &value, default) {
+                        Some(kind) => errors.push(ConfigError::new(line_of(toml_str, &name), Some(name), kind)),
+                        None => { valid.insert(name, value); }
+                    }
+                }
+            }
         }
+        valid
+    }
+
+    // The checks that need the values of the settings (or of more
+    // than one setting).
+    fn range_errors(&self, toml_str: &str) -> Vec<ConfigError> {
+        let mut problems = vec!();
+        let probabilities = [
+            ("dynamic_komi.adaptive_high", self.dynamic_komi.adaptive_high),
+            ("dynamic_komi.adaptive_low", self.dynamic_komi.adaptive_low),
+            ("playout.atari_check", self.playout.atari_check),
+            ("playout.capture_probability", self.playout.capture_probability),
+            ("playout.ladder_check", self.playout.ladder_check),
+            ("playout.nakade_probability", self.playout.nakade_probability),
+            ("playout.pattern_probability", self.playout.pattern_probability),
+            ("playout.play_in_middle_of_eye", self.playout.play_in_middle_of_eye),
+            ("playout.two_liberties_probability", self.playout.two_liberties_probability),
+            ("scoring.ownership_cutoff", self.scoring.ownership_cutoff),
+            ("time_control.fastplay_threshold", self.time_control.fastplay_threshold),
+            ];
+        for &(key, value) in probabilities.iter() {
+            if value < 0.0 || value > 1.0 {
+                problems.push((key, "a value between 0 and 1".to_string(), format!("{}", value)));
+            }
+        }
+        // The timer divides by the budget.
+        let budget = self.time_control.fastplay_budget;
+        if budget <= 0.0 || budget > 1.0 {
+            problems.push(("time_control.fastplay_budget", "a value above 0 and at most 1".to_string(), format!("{}", budget)));
+        }
+        if self.dynamic_komi.adaptive_low > self.dynamic_komi.adaptive_high {
+            let expected = format!("at most dynamic_komi.adaptive_high ({})", self.dynamic_komi.adaptive_high);
+            problems.push(("dynamic_komi.adaptive_low", expected, format!("{}", self.dynamic_komi.adaptive_low)));
+        }
+        if self.priors.neutral_wins > self.priors.neutral_plays {
+            let expected = format!("at most priors.neutral_plays ({})", self.priors.neutral_plays);
+            problems.push(("priors.neutral_wins", expected, format!("{}", self.priors.neutral_wins)));
+        }
+        if self.threads == 0 {
+            problems.push(("threads", "at least 1".to_string(), "0".to_string()));
+        }
+        problems.into_iter().map(|(key, expected, found)| {
+            let kind = ConfigErrorKind::OutOfRange(expected, found);
+            ConfigError::new(line_of(toml_str, key), Some(key.to_string()), kind)
+        }).collect()
     }
 
     /// If logging is turned on then the string passed will be printed
@@ -409,8 +997,200 @@ impl Config {
         }
     }
 
+
+    /// The current values of all settings that can be changed while
+    /// the engine is running (see `Config::set()`), sorted by their
+    /// names (e.g. `tree.rave_equiv`).
+    pub fn params(&self) -> Vec<(String, toml::Value)> {
+        let mut params = vec!();
+        for &section in PARAM_SECTIONS.iter() {
+            for (field, value) in self.section_table(section).unwrap() {
+                params.push((format!("{}.{}", section, field), value));
+            }
+        }
+        params
+    }
+
+    /// The current value of a setting that can be changed while the
+    /// engine is running.
+    pub fn get(&self, name: &str) -> Option<toml::Value> {
+        let mut parts = name.splitn(2, '.');
+        match (parts.next(), parts.next()) {
+            (Some(section), Some(field)) => self.section_table(section)
+                .and_then(|table| table.get(field).cloned()),
+            _ => None
+        }
+    }
+
+    /// The possible values of a setting that takes one of a few
+    /// strings.
+    pub fn choices(name: &str) -> Option<&'static [&'static str]> {
+        match name {
+            "dynamic_komi.mode" => Some(&["adaptive", "linear", "off"]),
+            "playout.policy" => Some(&["cascade", "weighted"]),
+            "tree.transposition_replacement" => Some(&["always", "visits"]),
+            _ => None
+        }
+    }
+
+    /// Returns a copy of the configuration with the setting changed.
+    /// The value needs to have the same type as the current one
+    /// (although an integer is fine for a float). Booleans can be
+    /// given as `0` and `1` as well and strings don't need quotes.
+    pub fn set(&self, name: &str, value: &str) -> Result<Config, String> {
+        let current = match self.get(name) {
+            Some(current) => current,
+            None => return Err(format!("unknown parameter {}", name))
+        };
+        let expected = match current.type_str() {
+            "integer" => "non-negative integer",
+            other => other,
+        };
+        let new_value = match current {
+            toml::Value::Boolean(_) => match value {
+                "true" | "1" => Some(toml::Value::Boolean(true)),
+                "false" | "0" => Some(toml::Value::Boolean(false)),
+                _ => None
+            },
+            toml::Value::Integer(_) => value.parse::<u32>().ok().map(|v| toml::Value::Integer(v as i64)),
+            toml::Value::Float(_) => value.parse::<f32>().ok().map(float),
+            _ => {
+                let string = value.trim_matches('"');
+                match Self::choices(name) {
+                    Some(choices) if !choices.contains(&string) => {
+                        return Err(format!("expected one of {} for {:?}", choices.join(", "), name));
+                    },
+                    _ => Some(toml::Value::String(string.to_string()))
+                }
+            }
+        };
+        let new_value = match new_value {
+            Some(new_value) => new_value,
+            None => return Err(format!("expected {} for {:?} but found {:?}", expected, name, value))
+        };
+        let (section, field) = name.split_at(name.find('.').unwrap());
+        let mut table = toml::Table::new();
+        table.insert(field[1..].to_string(), new_value);
+        let opts = toml::Value::Table(table);
+        let current_table = toml::Value::Table(self.section_table(section).unwrap());
+        let mut config = self.clone();
+        match section {
+            "dynamic_komi" => config.dynamic_komi = DynamicKomiConfig::new(opts, current_table),
+            "playout" => config.playout = PlayoutConfig::new(opts, current_table),
+            "priors" => config.priors = PriorsConfig::new(opts, current_table),
+            "scoring" => config.scoring = ScoringConfig::new(opts, current_table),
+            "time_control" => config.time_control = TimeControlConfig::new(opts, current_table),
+            _ => config.tree = TreeConfig::new(opts, current_table),
+        }
+        match config.range_errors("").first() {
+            Some(error) => Err(format!("{}", error)),
+            None => Ok(config)
+        }
+    }
+
+    fn section_table(&self, section: &str) -> Option<toml::Table> {
+        match section {
+            "dynamic_komi" => Some(self.dynamic_komi.table()),
+            "playout" => Some(self.playout.table()),
+            "priors" => Some(self.priors.table()),
+            "scoring" => Some(self.scoring.table()),
+            "time_control" => Some(self.time_control.table()),
+            "tree" => Some(self.tree.table()),
+            _ => None
+        }
+    }
 }
 
 impl FromToml for Config {
     fn name() -> Option<&'static str> { None }
 }
+
+// The sections of the configuration that can be changed while the
+// engine is running.
+const PARAM_SECTIONS: [&'static str; 6] = ["dynamic_komi", "playout", "priors", "scoring", "time_control", "tree"];
+
+fn float(value: f32) -> toml::Value {
+    // Going through the string representation keeps e.g. 0.1 from
+    // turning into 0.10000000149011612.
+    toml::Value::Float(format!("{}", value).parse().unwrap())
+}
+
+fn integer(value: usize) -> toml::Value {
+    toml::Value::Integer(value as i64)
+}
+
+// Returns a description of the problem if the value doesn't fit the
+// default value of the setting.
+fn check_value(key: &str, value: &toml::Value, default: &toml::Value) -> Option<ConfigErrorKind> {
+    let wrong_type = |expected: &str| Some(ConfigErrorKind::WrongType(expected.to_string(), value.type_str().to_string()));
+    match (default, value) {
+        (&toml::Value::Float(_), &toml::Value::Float(_)) => None,
+        (&toml::Value::Float(_), &toml::Value::Integer(_)) => None,
+        (&toml::Value::Float(_), _) => wrong_type("float"),
+        (&toml::Value::Integer(_), &toml::Value::Integer(i)) if i < 0 =>
+            Some(ConfigErrorKind::OutOfRange("a non-negative integer".to_string(), format!("{}", i))),
+        (&toml::Value::Integer(_), &toml::Value::Integer(_)) => None,
+        (&toml::Value::Integer(_), _) => wrong_type("integer"),
+        (&toml::Value::Boolean(_), &toml::Value::Boolean(_)) => None,
+        (&toml::Value::Boolean(_), _) => wrong_type("boolean"),
+        (&toml::Value::String(_), &toml::Value::String(ref s)) => match Config::choices(key) {
+            Some(choices) if !choices.contains(&s.as_ref()) =>
+                Some(ConfigErrorKind::OutOfRange(format!("one of {}", choices.join(", ")), format!("{:?}", s))),
+            _ => None
+        },
+        (&toml::Value::String(_), _) => wrong_type("string"),
+        (&toml::Value::Table(_), _) => wrong_type("table"),
+        (_, _) => wrong_type(default.type_str()),
+    }
+}
+
+// Finds the line a setting (e.g. `tree.rave_equiv`) is set on.
+fn line_of(toml_str: &str, key: &str) -> Option<usize> {
+    let (section, field) = match key.find('.') {
+        Some(index) => (&key[..index], &key[index+1..]),
+        None => ("", key)
+    };
+    let mut current = "";
+    for (index, line) in toml_str.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            current = line.trim_matches(|c| c == '[' || c == ']').trim();
+            if current == key {
+                return Some(index + 1);
+            }
+        } else if current == section && line.split('=').next().unwrap().trim() == field {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+// The known setting that's closest to the unknown one, if it's close
+// enough to be a typo. A setting in the wrong section counts as a
+// single typo.
+fn similar(name: &str, known: &[String]) -> Option<String> {
+    let field = |key: &str| key.split('.').last().unwrap().to_string();
+    known.iter()
+        .map(|candidate| {
+            let d = if name.contains('.') && field(name) == field(candidate) { 1 } else { distance(name, candidate) };
+            (d, candidate)
+        })
+        .filter(|&(d, _)| d * 4 <= name.len())
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+// The Levenshtein distance between the two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..b.len() + 1).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec!(i + 1);
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current.push(*[substitution, previous[j + 1] + 1, current[j] + 1].iter().min().unwrap());
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
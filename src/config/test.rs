@@ -0,0 +1,147 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+
+#![cfg(test)]
+
+use ruleset::KgsChinese;
+use super::Config;
+use super::ConfigError;
+use super::ConfigErrorKind;
+
+fn errors(toml: &str) -> Vec<ConfigError> {
+    Config::from_toml(toml.to_string(), false, false, KgsChinese).unwrap_err()
+}
+
+fn messages(toml: &str) -> Vec<String> {
+    errors(toml).iter().map(|error| format!("{}", error)).collect()
+}
+
+#[test]
+fn takes_missing_settings_from_the_defaults() {
+    let config = Config::from_toml("[tree]\nrave_equiv = 30\n".to_string(), false, false, KgsChinese).unwrap();
+    assert_eq!(30.0, config.tree.rave_equiv);
+    assert_eq!(1, config.tree.expand_after);
+    assert_eq!(Config::test_config().priors, config.priors);
+}
+
+#[test]
+fn reports_the_line_and_the_key_of_values_with_the_wrong_type() {
+    let errors = errors("[book]\n\n[tree]\nexpand_after = 1\nrave_equiv = \"many\"\n");
+    assert_eq!(1, errors.len());
+    assert_eq!(Some(5), errors[0].line);
+    assert_eq!(Some("tree.rave_equiv".to_string()), errors[0].key);
+    assert_eq!(ConfigErrorKind::WrongType("float".to_string(), "string".to_string()), errors[0].kind);
+    assert_eq!("line 5: expected float for \"tree.rave_equiv\" but found string", format!("{}", errors[0]));
+}
+
+#[test]
+fn accepts_integers_for_floats_but_not_the_other_way_around() {
+    assert_eq!(
+        vec!("line 2: expected integer for \"priors.empty\" but found float"),
+        messages("[priors]\nempty = 2.5\nbest_move_factor = 2\n"));
+}
+
+#[test]
+fn rejects_negative_integers() {
+    assert_eq!(
+        vec!("line 2: expected a non-negative integer for \"tree.max_nodes\" but found -1"),
+        messages("[tree]\nmax_nodes = -1\n"));
+}
+
+#[test]
+fn suggests_known_settings_for_typos() {
+    assert_eq!(
+        vec!("line 2: unknown setting \"tree.rave_equv\" (did you mean \"tree.rave_equiv\"?)"),
+        messages("[tree]\nrave_equv = 20\n"));
+}
+
+#[test]
+fn suggests_the_right_section_for_misplaced_settings() {
+    assert_eq!(
+        vec!("line 2: unknown setting \"priors.rave_equiv\" (did you mean \"tree.rave_equiv\"?)"),
+        messages("[priors]\nrave_equiv = 20\n"));
+}
+
+#[test]
+fn reports_unknown_sections() {
+    assert_eq!(
+        vec!("line 1: unknown setting \"treads\" (did you mean \"threads\"?)", "line 3: unknown setting \"search\""),
+        messages("treads = 2\n\n[search]\nwidth = 3\n"));
+}
+
+#[test]
+fn rejects_values_that_are_not_one_of_the_choices() {
+    assert_eq!(
+        vec!("line 2: expected one of cascade, weighted for \"playout.policy\" but found \"random\""),
+        messages("[playout]\npolicy = \"random\"\n"));
+}
+
+#[test]
+fn checks_that_probabilities_are_between_zero_and_one() {
+    assert_eq!(
+        vec!("line 3: expected a value between 0 and 1 for \"playout.capture_probability\" but found 1.5"),
+        messages("[playout]\natari_check = 1\ncapture_probability = 1.5\n"));
+}
+
+#[test]
+fn checks_that_there_are_no_more_neutral_wins_than_plays() {
+    assert_eq!(
+        vec!("line 2: expected at most priors.neutral_plays (4) for \"priors.neutral_wins\" but found 5"),
+        messages("[priors]\nneutral_wins = 5\nneutral_plays = 4\n"));
+}
+
+#[test]
+fn reports_all_problems_sorted_by_line() {
+    let toml = "threads = 0\n\n[playout]\npolicy = 3\nladder_check = 2.0\n\n[tree]\nrave = 1\n";
+    let lines: Vec<Option<usize>> = errors(toml).iter().map(|error| error.line).collect();
+    assert_eq!(vec!(Some(1), Some(4), Some(5), Some(8)), lines);
+}
+
+#[test]
+fn reports_the_line_of_syntax_errors() {
+    let errors = errors("[tree]\nrave_equiv = = 3\n");
+    assert!(errors.len() > 0);
+    assert!(errors.iter().all(|error| error.line == Some(2)));
+}
+
+#[test]
+fn reports_missing_files() {
+    let errors = Config::from_file("fixtures/config/missing.toml".to_string(), false, false, KgsChinese).unwrap_err();
+    assert_eq!(1, errors.len());
+    assert_eq!(None, errors[0].line);
+}
+
+#[test]
+fn set_checks_the_range_of_the_new_value() {
+    let config = Config::test_config();
+    assert_eq!(
+        Err("expected a value between 0 and 1 for \"time_control.fastplay_threshold\" but found 2".to_string()),
+        config.set("time_control.fastplay_threshold", "2"));
+    assert!(config.set("time_control.fastplay_threshold", "0.5").is_ok());
+}
+
+#[test]
+fn rejects_a_fastplay_budget_of_zero() {
+    assert_eq!(
+        vec!("line 2: expected a value above 0 and at most 1 for \"time_control.fastplay_budget\" but found 0"),
+        messages("[time_control]\nfastplay_budget = 0\n"));
+}
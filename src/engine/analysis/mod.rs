@@ -0,0 +1,77 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Move;
+
+mod test;
+
+/// The output flavour of the analysis commands. Leela Zero reports
+/// win rates and priors as integers in the range 0 to 10000 and
+/// KataGo as floats between 0 and 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Kata,
+    Lz,
+}
+
+/// What the search currently knows about one of the moves at the
+/// root of the tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    pub amaf_visits: usize,
+    pub amaf_winrate: f32,
+    pub m: Move,
+    pub prior: f32,
+    pub pv: Vec<Move>,
+    pub visits: usize,
+    pub winrate: f32,
+}
+
+/// Formats the candidates as a single line of `info` entries, in
+/// the order they are given.
+pub fn info_line(format: Format, candidates: &[Candidate]) -> String {
+    let entries: Vec<String> = candidates.iter()
+        .enumerate()
+        .map(|(order, candidate)| info(format, order, candidate))
+        .collect();
+    entries.join(" ")
+}
+
+fn info(format: Format, order: usize, candidate: &Candidate) -> String {
+    let pv: Vec<String> = candidate.pv.iter().map(|m| m.to_gtp()).collect();
+    format!(
+        "info move {} visits {} winrate {} prior {} amafVisits {} amafWinrate {} order {} pv {}",
+        candidate.m.to_gtp(),
+        candidate.visits,
+        ratio(format, candidate.winrate),
+        ratio(format, candidate.prior),
+        candidate.amaf_visits,
+        ratio(format, candidate.amaf_winrate),
+        order,
+        pv.join(" "))
+}
+
+fn ratio(format: Format, value: f32) -> String {
+    match format {
+        Format::Kata => format!("{:.4}", value),
+        Format::Lz => format!("{}", (value * 10000.0).round() as u32),
+    }
+}
@@ -0,0 +1,68 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Play;
+use board::White;
+use super::Candidate;
+use super::Format;
+use super::info_line;
+
+fn candidate() -> Candidate {
+    Candidate {
+        amaf_visits: 20,
+        amaf_winrate: 0.6,
+        m: Play(Black, 4, 4),
+        prior: 0.125,
+        pv: vec!(Play(Black, 4, 4), Play(White, 16, 16)),
+        visits: 10,
+        winrate: 0.55,
+    }
+}
+
+#[test]
+fn lz_format_uses_integers() {
+    let line = info_line(Format::Lz, &[candidate()]);
+    assert_eq!(line, "info move D4 visits 10 winrate 5500 prior 1250 amafVisits 20 amafWinrate 6000 order 0 pv D4 Q16");
+}
+
+#[test]
+fn kata_format_uses_floats() {
+    let line = info_line(Format::Kata, &[candidate()]);
+    assert_eq!(line, "info move D4 visits 10 winrate 0.5500 prior 0.1250 amafVisits 20 amafWinrate 0.6000 order 0 pv D4 Q16");
+}
+
+#[test]
+fn candidates_are_numbered_in_order() {
+    let mut second = candidate();
+    second.m = Play(Black, 3, 3);
+    second.pv = vec!(Play(Black, 3, 3));
+    let line = info_line(Format::Lz, &[candidate(), second]);
+    assert!(line.contains("order 0 pv D4 Q16 info move C3"));
+    assert!(line.ends_with("order 1 pv C3"));
+}
+
+#[test]
+fn no_candidates_give_an_empty_line() {
+    assert_eq!(info_line(Format::Lz, &[]), "");
+}
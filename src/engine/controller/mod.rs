@@ -23,23 +23,23 @@ use board::Color;
 use board::Move;
 use config::Config;
 use engine::Engine;
+use engine::analysis::Candidate;
 use game::Game;
 use ownership::OwnershipStatistics;
 use score::FinalScore;
 use timer::Timer;
 
 use std::sync::Arc;
+use time::Duration;
 
 pub struct EngineController<'a> {
-    config: Arc<Config>,
     engine: Box<Engine + 'a>,
 }
 
 impl<'a> EngineController<'a> {
 
-    pub fn new<'b>(config: Arc<Config>, engine: Box<Engine + 'b>) -> EngineController<'b> {
+    pub fn new<'b>(engine: Box<Engine + 'b>) -> EngineController<'b> {
         EngineController {
-            config: config,
             engine: engine,
         }
     }
@@ -48,16 +48,20 @@ impl<'a> EngineController<'a> {
         self.engine.reset(size, komi);
     }
 
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.engine.set_config(config);
+    }
+
     pub fn ownership_statistics(&self) -> String {
         format!("{}", self.ownership())
     }
 
     pub fn final_score(&self, game: &Game) -> String {
-        FinalScore::new(self.config.clone(), game, self.ownership()).score()
+        FinalScore::new(game, self.ownership()).score()
     }
 
     pub fn final_status_list(&self, game: &Game, kind: &str) -> Result<String, String> {
-        FinalScore::new(self.config.clone(), game, self.ownership()).status_list(kind)
+        FinalScore::new(game, self.ownership()).status_list(kind)
 
     }
 
@@ -65,6 +69,14 @@ impl<'a> EngineController<'a> {
         self.engine.genmove(color, game, timer)
     }
 
+    pub fn analyze(&mut self, color: Color, game: &Game, interval: Duration, report: &mut FnMut(Vec<Candidate>), stop: &Fn() -> bool) {
+        self.engine.analyze(color, game, interval, report, stop);
+    }
+
+    pub fn ponder(&mut self, game: &Game, stop: &Fn() -> bool) {
+        self.engine.ponder(game, stop);
+    }
+
     fn ownership(&self) -> &OwnershipStatistics {
         &self.engine.ownership()
     }
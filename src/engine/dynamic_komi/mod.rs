@@ -0,0 +1,99 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Color;
+use board::White;
+use config::Config;
+use config::DynamicKomiMode;
+use game::Game;
+
+use std::sync::Arc;
+
+mod test;
+
+/// Calculates the komi used in the playouts. All adjustments are
+/// given in points for white, i.e. a positive offset means more komi
+/// for white.
+pub struct DynamicKomi {
+    config: Arc<Config>,
+    offset: f32,
+}
+
+impl DynamicKomi {
+
+    pub fn new(config: Arc<Config>) -> DynamicKomi {
+        DynamicKomi {
+            config: config,
+            offset: 0.0,
+        }
+    }
+
+    /// The komi to use in the playouts of the next search.
+    pub fn komi(&self, game: &Game) -> f32 {
+        game.komi() + self.offset(game)
+    }
+
+    /// The difference between the komi used in the playouts and the
+    /// real komi.
+    pub fn offset(&self, game: &Game) -> f32 {
+        match self.config.dynamic_komi.mode {
+            DynamicKomiMode::Adaptive => self.offset,
+            DynamicKomiMode::Linear => self.handicap_offset(game),
+            DynamicKomiMode::Off => 0.0,
+        }
+    }
+
+    // The value of the handicap stones decreases linearly until it
+    // reaches 0 after `handicap_moves` moves. The handicap stones
+    // themselves don't count as moves.
+    fn handicap_offset(&self, game: &Game) -> f32 {
+        let config = &self.config.dynamic_komi;
+        let moves = game.move_count().saturating_sub(game.handicap() as usize);
+        if moves >= config.handicap_moves {
+            0.0
+        } else {
+            let remaining = 1.0 - moves as f32 / config.handicap_moves as f32;
+            game.handicap() as f32 * config.handicap_stone_value * remaining
+        }
+    }
+
+    /// Adapts the komi to the win rate `color` had at the end of the
+    /// search. Only has an effect in the adaptive mode.
+    pub fn record_win_ratio(&mut self, color: Color, win_ratio: f32) {
+        let config = &self.config.dynamic_komi;
+        if config.mode != DynamicKomiMode::Adaptive {
+            return;
+        }
+        // More komi makes it harder for black and easier for white.
+        let harder = if color == White { -config.adaptive_step } else { config.adaptive_step };
+        if win_ratio > config.adaptive_high {
+            self.offset += harder;
+        } else if win_ratio < config.adaptive_low {
+            self.offset -= harder;
+        }
+        self.offset = self.offset.max(-config.adaptive_max).min(config.adaptive_max);
+    }
+
+    pub fn reset(&mut self) {
+        self.offset = 0.0;
+    }
+
+}
@@ -0,0 +1,128 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Coord;
+use board::Play;
+use board::White;
+use config::Config;
+use config::DynamicKomiMode;
+use game::Game;
+use ruleset::KgsChinese;
+use super::DynamicKomi;
+
+use std::sync::Arc;
+
+fn dynamic_komi(mode: DynamicKomiMode) -> DynamicKomi {
+    let mut config = Config::test_config();
+    config.dynamic_komi.mode = mode;
+    config.dynamic_komi.handicap_moves = 100;
+    config.dynamic_komi.handicap_stone_value = 7.0;
+    config.dynamic_komi.adaptive_high = 0.8;
+    config.dynamic_komi.adaptive_low = 0.4;
+    config.dynamic_komi.adaptive_max = 2.0;
+    config.dynamic_komi.adaptive_step = 1.0;
+    DynamicKomi::new(Arc::new(config))
+}
+
+fn handicap_game() -> Game {
+    let game = Game::new(19, 0.5, KgsChinese);
+    game.place_handicap(&[Coord::new(4, 4), Coord::new(16, 16)]).unwrap()
+}
+
+#[test]
+fn off_uses_the_real_komi() {
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Off);
+    dynamic_komi.record_win_ratio(Black, 0.99);
+    assert_eq!(0.5, dynamic_komi.komi(&handicap_game()));
+}
+
+#[test]
+fn linear_gives_white_komi_for_the_handicap_stones() {
+    let dynamic_komi = dynamic_komi(DynamicKomiMode::Linear);
+    assert_eq!(14.5, dynamic_komi.komi(&handicap_game()));
+}
+
+#[test]
+fn linear_decreases_the_komi_over_the_game() {
+    let dynamic_komi = dynamic_komi(DynamicKomiMode::Linear);
+    let mut game = handicap_game();
+    // Vertical stripes of stones, so that nothing gets captured.
+    for i in 0..50 {
+        let color = if i % 2 == 0 { White } else { Black };
+        game = game.play(Play(color, i % 10 + 1, i / 10 + 10)).unwrap();
+    }
+    assert_eq!(7.0, dynamic_komi.offset(&game));
+}
+
+#[test]
+fn linear_doesnt_change_even_games() {
+    let dynamic_komi = dynamic_komi(DynamicKomiMode::Linear);
+    assert_eq!(0.0, dynamic_komi.offset(&Game::new(19, 6.5, KgsChinese)));
+}
+
+#[test]
+fn adaptive_makes_it_harder_when_winning() {
+    let game = Game::new(19, 6.5, KgsChinese);
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Adaptive);
+    dynamic_komi.record_win_ratio(Black, 0.9);
+    assert_eq!(7.5, dynamic_komi.komi(&game));
+    let mut dynamic_komi = self::dynamic_komi(DynamicKomiMode::Adaptive);
+    dynamic_komi.record_win_ratio(White, 0.9);
+    assert_eq!(5.5, dynamic_komi.komi(&game));
+}
+
+#[test]
+fn adaptive_makes_it_easier_when_losing() {
+    let game = Game::new(19, 6.5, KgsChinese);
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Adaptive);
+    dynamic_komi.record_win_ratio(Black, 0.1);
+    assert_eq!(5.5, dynamic_komi.komi(&game));
+}
+
+#[test]
+fn adaptive_keeps_the_komi_in_the_middle_zone() {
+    let game = Game::new(19, 6.5, KgsChinese);
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Adaptive);
+    dynamic_komi.record_win_ratio(Black, 0.6);
+    assert_eq!(6.5, dynamic_komi.komi(&game));
+}
+
+#[test]
+fn adaptive_is_limited_by_the_maximum() {
+    let game = Game::new(19, 6.5, KgsChinese);
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Adaptive);
+    for _ in 0..5 {
+        dynamic_komi.record_win_ratio(Black, 0.9);
+    }
+    assert_eq!(2.0, dynamic_komi.offset(&game));
+}
+
+#[test]
+fn reset_goes_back_to_the_real_komi() {
+    let game = Game::new(19, 6.5, KgsChinese);
+    let mut dynamic_komi = dynamic_komi(DynamicKomiMode::Adaptive);
+    dynamic_komi.record_win_ratio(Black, 0.9);
+    dynamic_komi.reset();
+    assert_eq!(6.5, dynamic_komi.komi(&game));
+}
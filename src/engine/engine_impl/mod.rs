@@ -19,106 +19,167 @@
  *                                                                      *
  ************************************************************************/
 
-pub use self::node::Node;
-use board::Board;
+
 use board::Color;
 use board::Move;
-use board::NoMove;
 use board::Pass;
 use board::Resign;
+use book::Book;
 use config::Config;
+use config::DynamicKomiMode;
 use engine::Engine;
+use engine::analysis::Candidate;
+use engine::dynamic_komi::DynamicKomi;
 use game::Game;
 use ownership::OwnershipStatistics;
 use patterns::Matcher;
 use playout::Playout;
-use playout::PlayoutResult;
 use score::FinalScore;
+use self::tree::Tree;
 use timer::Timer;
 
 use rand::weak_rng;
 use std::sync::Arc;
-use std::sync::mpsc::Receiver;
-use std::sync::mpsc::Sender;
-use std::sync::mpsc::channel;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration as StdDuration;
 use thread_scoped::JoinGuard;
 use thread_scoped::scoped;
+use time::Duration;
 use time::PreciseTime;
 
 mod node;
-
-macro_rules! check {
-    ($config:expr, $r:expr) => {
-        check!($config, _unused_result = $r => {})
-    };
-    ($config:expr, $res:pat = $r:expr => $body:expr) => {
-        match $r {
-            Ok(res) => {
-                let $res = res;
-                $body
-            },
-            Err(e) => {
-                $config.log(format!("[DEBUG] unwrap failed with {:?} at {}:{}", e, file!(), line!()));
-            }
-        }
-    };
-
-}
+mod test;
+mod tree;
 
 pub struct EngineImpl {
+    book: Arc<Book>,
     config: Arc<Config>,
+    dynamic_komi: DynamicKomi,
     matcher: Arc<Matcher>,
     ownership: OwnershipStatistics,
     playout: Arc<Playout>,
     previous_node_count: usize,
-    root: Node,
+    root_position: Option<(usize, u64)>,
     start: PreciseTime,
+    tree: Tree,
 }
 
 impl EngineImpl {
 
-    pub fn new(config: Arc<Config>, matcher: Arc<Matcher>) -> EngineImpl {
+    pub fn new(config: Arc<Config>, matcher: Arc<Matcher>, book: Arc<Book>) -> EngineImpl {
         EngineImpl {
+            book: book,
             config: config.clone(),
+            dynamic_komi: DynamicKomi::new(config.clone()),
             matcher: matcher.clone(),
             ownership: OwnershipStatistics::new(config.clone(), 0, 0.0),
             playout: Arc::new(Playout::new(config.clone(), matcher.clone())),
             previous_node_count: 0,
-            root: Node::new(NoMove, config),
+            root_position: None,
             start: PreciseTime::now(),
+            tree: Tree::new(config),
         }
     }
 
     fn set_new_root(&mut self, game: &Game, color: Color) {
-        self.root = self.root.find_new_root(game, color);
+        if self.root_position == Some(Self::position(game)) {
+            // The tree was built for this very position already
+            // (e.g. by analyzing or pondering).
+            if self.tree.color() != color {
+                self.tree.make_root(color);
+            }
+            return;
+        }
+        self.tree = if self.continues_from_root(game) {
+            self.tree.find_new_root(game, color)
+        } else {
+            Tree::root(game, color, self.config.clone())
+        };
+        self.root_position = Some(Self::position(game));
+    }
+
+    // The tree can only be reused if the game is exactly one move
+    // ahead of the root. Otherwise moves were taken back or the game
+    // was set up in some other way.
+    fn continues_from_root(&self, game: &Game) -> bool {
+        match (game.undo(), self.root_position) {
+            (Some(previous), Some(position)) => Self::position(&previous) == position,
+            _ => false
+        }
+    }
+
+    fn position(game: &Game) -> (usize, u64) {
+        (game.move_count(), game.hash())
     }
 
     fn genmove_setup(&mut self, color: Color, game: &Game) {
         self.start = PreciseTime::now();
         self.config.gfx(self.ownership.gfx());
-        self.ownership = OwnershipStatistics::new(self.config.clone(), game.size(), game.komi());
-        self.previous_node_count = self.root.descendants();
+        self.ownership = OwnershipStatistics::new(self.config.clone(), game.size(), game.adjusted_komi());
+        self.ownership.set_pass_alive(&game.board());
+        self.previous_node_count = self.tree.descendants();
         self.set_new_root(game, color);
-        let reused_node_count = self.root.descendants();
+        let reused_node_count = self.tree.descendants();
         if self.previous_node_count > 0 {
             let percentage = reused_node_count as f32 / self.previous_node_count as f32;
-            let msg = format!("Reusing {} nodes ({}%)", reused_node_count, percentage*100.0);
+            let memory = self.tree.memory() as f32 / (1024.0 * 1024.0);
+            let msg = format!("Reusing {} nodes ({}%, {:.1}MB)", reused_node_count, percentage*100.0, memory);
             self.config.log(msg);
         }
+        if self.config.dynamic_komi.mode != DynamicKomiMode::Off {
+            let msg = format!("Dynamic komi: {} ({:+} to the komi of {})", self.dynamic_komi.komi(game), self.dynamic_komi.offset(game), game.komi());
+            self.config.log(msg);
+        }
+    }
+
+    /// Runs simulations on the worker threads until `done` returns
+    /// true. The workers share the tree, so the main thread only
+    /// checks `done` periodically. The playouts are scored with the
+    /// dynamic komi.
+    fn search<F>(&mut self, game: &Game, mut done: F) where F: FnMut(&EngineImpl) -> bool {
+        let halt = AtomicBool::new(false);
+        let mut game = game.clone();
+        game.set_komi(self.dynamic_komi.komi(&game));
+        let statistics: Vec<OwnershipStatistics> = {
+            let engine: &EngineImpl = self;
+            let game = &game;
+            let guards: Vec<JoinGuard<OwnershipStatistics>> = (0..engine.config.threads)
+                .map(|_| spin_up_worker(engine, game, &halt))
+                .collect();
+            while !done(engine) {
+                thread::sleep(StdDuration::from_millis(5));
+            }
+            halt.store(true, Ordering::SeqCst);
+            guards.into_iter().map(|guard| guard.join()).collect()
+        };
+        for worker_statistics in statistics.iter() {
+            self.ownership.add(worker_statistics);
+        }
     }
 
-    fn finish(&mut self, game: &Game, color: Color, halt_senders: Vec<Sender<()>>) -> (Move,usize) {
-        for halt_sender in halt_senders.iter() {
-            check!(self.config, halt_sender.send(()));
+    fn book_move(&self, color: Color, game: &Game) -> Option<Move> {
+        if game.move_count() < self.config.book.max_depth {
+            self.book.pick(game, color, &mut weak_rng())
+        } else {
+            None
         }
-        let msg = format!("{} simulations ({}% wins on average, {} nodes)", self.root.playouts(), self.root.win_ratio()*100.0, self.root.descendants());
+    }
+
+    fn finish(&mut self, game: &Game, color: Color) -> (Move,usize) {
+        let msg = format!("{} simulations ({}% wins on average, {} nodes)", self.tree.playouts(), self.tree.win_ratio()*100.0, self.tree.descendants());
         self.config.log(msg);
-        let final_score = FinalScore::new(self.config.clone(), game, self.ownership());
+        if self.tree.is_full() {
+            self.config.log(format!("The tree is full. Stopped expanding the leaves."));
+        }
+        self.dynamic_komi.record_win_ratio(color, self.tree.win_ratio());
+        let final_score = FinalScore::without_solver(game, self.ownership());
         let m = if final_score.decided() {
             self.config.log(format!("Board decided. Passing."));
             Pass(color)
         } else {
-            let best_node = self.root.best();
+            let best_node = self.tree.best();
             let win_ratio = best_node.win_ratio();
             if win_ratio == 0.0 {
                 if game.winner() == color {
@@ -133,7 +194,7 @@ impl EngineImpl {
                 best_node.m()
             }
         };
-        let playouts = self.root.playouts();
+        let playouts = self.tree.playouts();
         self.set_new_root(&game.play(m).unwrap(), color);
         (m,playouts)
     }
@@ -147,84 +208,93 @@ impl Engine for EngineImpl {
     }
 
     fn genmove(&mut self, color: Color, game: &Game, timer: &Timer) -> (Move,usize) {
+        if let Some(m) = self.book_move(color, game) {
+            self.config.log(format!("Playing {} from the opening book", m.to_gtp()));
+            return (m, 0);
+        }
         self.genmove_setup(color, game);
-        if self.root.has_no_children() {
+        if self.tree.has_no_children() {
             self.config.log(format!("No moves to simulate!"));
-            return (Pass(color), self.root.playouts());
+            return (Pass(color), self.tree.playouts());
+        }
+        self.search(game, |engine| timer.ran_out_of_time(engine.tree.best().win_ratio()));
+        self.finish(game, color)
+    }
+
+    fn analyze(&mut self, color: Color, game: &Game, interval: Duration, report: &mut FnMut(Vec<Candidate>), stop: &Fn() -> bool) {
+        self.genmove_setup(color, game);
+        if self.tree.has_no_children() {
+            return;
         }
-        let (send_result_to_main, receive_result_from_threads) = channel::<((Vec<usize>, usize, PlayoutResult), Sender<(Vec<usize>, Vec<Move>, bool, usize)>)>();
-        let (_guards, halt_senders) = spin_up(self.config.clone(), self.playout.clone(), game, send_result_to_main);
-        loop {
-            if timer.ran_out_of_time(self.root.best().win_ratio()) {
-                return self.finish(game, color, halt_senders);
+        let mut last_report = PreciseTime::now();
+        self.search(game, |engine| {
+            if last_report.to(PreciseTime::now()) >= interval {
+                report(engine.tree.candidates());
+                last_report = PreciseTime::now();
             }
-            select!(
-                r = receive_result_from_threads.recv() => {
-                    check!(self.config, res = r => {
-                        let ((path, nodes_added, playout_result), send_to_thread) = res;
-                        self.ownership.merge(playout_result.score());
-                        self.root.record_on_path(
-                            &path,
-                            nodes_added,
-                            &playout_result);
-                        let data = self.root.find_leaf_and_expand(game, self.matcher.clone());
-                        check!(self.config, send_to_thread.send(data));
-                    });
-                }
-                )
+            stop()
+        });
+        report(self.tree.candidates());
+    }
+
+    fn ponder(&mut self, game: &Game, stop: &Fn() -> bool) {
+        let color = game.board().next_player();
+        self.genmove_setup(color, game);
+        if self.tree.has_no_children() {
+            return;
         }
+        let start = PreciseTime::now();
+        let playouts = self.tree.playouts();
+        self.config.log(format!("Pondering on {} threads until the opponent moves", self.config.threads));
+        self.search(game, |_| stop());
+        let msg = format!(
+            "Pondered for {}ms on {} threads ({} simulations)",
+            start.to(PreciseTime::now()).num_milliseconds(),
+            self.config.threads,
+            self.tree.playouts() - playouts);
+        self.config.log(msg);
     }
 
     fn reset(&mut self, size: u8, komi: f32) {
         self.previous_node_count = 0;
-        self.root = Node::new(NoMove, self.config.clone());
+        self.tree = Tree::new(self.config.clone());
+        self.root_position = None;
         self.ownership = OwnershipStatistics::new(self.config.clone(), size, komi);
+        self.dynamic_komi.reset();
     }
 
-}
-
-fn spin_up<'a>(config: Arc<Config>, playout: Arc<Playout>, game: &Game, send_to_main: Sender<((Vec<usize>, usize, PlayoutResult), Sender<(Vec<usize>, Vec<Move>, bool, usize)>)>) -> (Vec<JoinGuard<'a, ()>>, Vec<Sender<()>>) {
-    let mut guards = Vec::new();
-    let mut halt_senders = Vec::new();
-    for _ in 0..config.threads {
-        let (send_halt, receive_halt) = channel::<()>();
-        halt_senders.push(send_halt);
-        let send_to_main = send_to_main.clone();
-        let guard = spin_up_worker(config.clone(), playout.clone(), game.board(), send_to_main, receive_halt);
-        guards.push(guard);
+    fn set_config(&mut self, config: Arc<Config>) {
+        // The tree, the ownership statistics and the dynamic komi are
+        // thrown away as they were collected with the old settings.
+        self.playout = Arc::new(Playout::new(config.clone(), self.matcher.clone()));
+        self.tree = Tree::new(config.clone());
+        self.root_position = None;
+        self.ownership = OwnershipStatistics::new(config.clone(), self.ownership.size(), self.ownership.komi());
+        self.dynamic_komi = DynamicKomi::new(config.clone());
+        self.config = config;
     }
-    (guards, halt_senders)
+
 }
 
-fn spin_up_worker<'a>(config: Arc<Config>, playout: Arc<Playout>, board: Board, send_to_main: Sender<((Vec<usize>, usize, PlayoutResult),Sender<(Vec<usize>, Vec<Move>, bool, usize)>)>, receive_halt: Receiver<()>) -> JoinGuard<'a, ()> {
+// Each worker descends the shared tree, runs the playout and records
+// the result on its own. The ownership statistics are collected per
+// worker and merged once the search is over.
+fn spin_up_worker<'a>(engine: &'a EngineImpl, game: &'a Game, halt: &'a AtomicBool) -> JoinGuard<'a, OwnershipStatistics> {
+    let tree = &engine.tree;
+    let config = engine.config.clone();
+    let matcher = engine.matcher.clone();
+    let playout = engine.playout.clone();
     unsafe { scoped(move || {
         let mut rng = weak_rng();
-        let (send_to_self, receive_from_main) = channel::<(Vec<usize>, Vec<Move>, bool, usize)>();
-        // Send this empty message to get everything started
-        check!(
-            config,
-            send_to_main.send(((vec!(), 0, PlayoutResult::empty()), send_to_self.clone())));
-        loop {
-            select!(
-                _ = receive_halt.recv() => { break; },
-                task = receive_from_main.recv() => {
-                    check!(
-                        config,
-                        (path, moves, _unused, nodes_added) = task => {
-                            let mut b = board.clone();
-                            for &m in moves.iter() {
-                                b.play_legal_move(m);
-                            }
-                            // Playout is smart enough to correctly handle the
-                            // case where the game is already over.
-                            let playout_result = playout.run(&mut b, None, &mut rng);
-                            let send_to_self = send_to_self.clone();
-                            check!(
-                                config,
-                                send_to_main.send(((path, nodes_added, playout_result), send_to_self)));
-                        })
-                }
-                )
+        let mut ownership = OwnershipStatistics::new(config, game.size(), game.adjusted_komi());
+        while !halt.load(Ordering::Relaxed) {
+            let (path, mut board) = tree.find_leaf_and_expand(game, matcher.clone());
+            // Playout is smart enough to correctly handle the
+            // case where the game is already over.
+            let playout_result = playout.run(&mut board, None, &mut rng);
+            ownership.merge(playout_result.score());
+            tree.record_on_path(&path, &playout_result);
         }
+        ownership
     })}
 }
@@ -19,180 +19,171 @@
  *                                                                      *
  ************************************************************************/
 
+
 use board::Board;
 use board::Color;
 use board::Empty;
 use board::Move;
-use board::NoMove;
 use board::Pass;
-use board::Play;
 use config::Config;
-use game::Game;
 use patterns::Matcher;
-use playout::PlayoutResult;
 use score::Score;
 
 use std::f32;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 mod test;
 
-#[derive(Clone, Debug, PartialEq)]
+/// The wins are fractional (see `weighted_win()`), but they are
+/// stored in an `AtomicUsize`. So they are multiplied by this factor
+/// first.
+const WIN_SCALE: f32 = 10000.0;
+
+const NOT_TERMINAL: usize = 0;
+const TERMINAL_WIN: usize = 1;
+const TERMINAL_LOSS: usize = 2;
+
+/// A node of the search tree. All statistics are atomic counters so
+/// that the worker threads can update them without locking. The
+/// children live in a contiguous block of the arena of the `Tree`
+/// and are published by storing the index of the first one.
+#[derive(Debug)]
 pub struct Node {
-    amaf_plays: f32,
-    amaf_wins: f32,
-    children: Vec<Node>,
-    config: Arc<Config>,
-    descendants: usize,
+    amaf_plays: AtomicUsize,
+    amaf_wins: AtomicUsize,
+    child_count: AtomicUsize,
+    children: AtomicUsize,
+    expanding: AtomicBool,
+    key: AtomicUsize,
     m: Move,
-    playouts: usize,
-    plays: f32,
+    plays: AtomicUsize,
     prior_plays: usize,
     prior_wins: usize,
-    wins: f32,
+    terminal: AtomicUsize,
+    wins: AtomicUsize,
 }
 
 impl Node {
 
-    pub fn new(m: Move, config: Arc<Config>) -> Node {
+    pub fn new(m: Move, config: &Config) -> Node {
+        Self::with_priors(m, config.priors.neutral_plays, config.priors.neutral_wins)
+    }
+
+    /// The root doesn't have any priors as it's never selected.
+    pub fn root(color: Color) -> Node {
+        Self::with_priors(Pass(color), 0, 0)
+    }
+
+    fn with_priors(m: Move, prior_plays: usize, prior_wins: usize) -> Node {
         Node {
-            amaf_plays: 0.0,
-            amaf_wins: 0.0,
-            children: vec!(),
-            config: config.clone(),
-            descendants: 0,
+            amaf_plays: AtomicUsize::new(0),
+            amaf_wins: AtomicUsize::new(0),
+            child_count: AtomicUsize::new(0),
+            children: AtomicUsize::new(0),
+            expanding: AtomicBool::new(false),
+            key: AtomicUsize::new(0),
             m: m,
-            playouts: 0,
-            plays: 0.0,
-            prior_plays: config.priors.neutral_plays,
-            prior_wins: config.priors.neutral_wins,
-            wins: 0.0,
+            plays: AtomicUsize::new(0),
+            prior_plays: prior_plays,
+            prior_wins: prior_wins,
+            terminal: AtomicUsize::new(NOT_TERMINAL),
+            wins: AtomicUsize::new(0),
         }
     }
 
-    pub fn root(game: &Game, color: Color, config: Arc<Config>) -> Node {
-        let mut root = Node::new(Pass(color), config);
-        root.reset();
-        // So that we don't get NaN on the first UCT calculation
-        root.plays = 1.0;
-        // Now that plays is 1, this needs to be one too to keep the
-        // win ratio calculations correct.
-        root.wins = 1.0;
-        root.expand_root(&game);
-        root
-    }
-
-    pub fn find_new_root(&self, game: &Game, color: Color) -> Node {
-        let mut new_root = self.find_child(game.last_move());
-        new_root.make_root(color);
-        new_root.remove_illegal_children(game);
-        // We don't currently include pass moves in the tree, so
-        // we need to handle the case where the opponent plays a
-        // pass move separately. This branch also handles the
-        // first move where we don't have a tree, yet.
-        if new_root.has_no_children() {
-            Node::root(game, color, self.config.clone())
-        } else {
-            new_root
+    /// Returns a node with the same statistics, but without any
+    /// children (so it can be expanded again). Used when copying a
+    /// subtree into a new tree.
+    pub fn copy(&self) -> Node {
+        Node {
+            amaf_plays: AtomicUsize::new(self.amaf_plays.load(Ordering::Relaxed)),
+            amaf_wins: AtomicUsize::new(self.amaf_wins.load(Ordering::Relaxed)),
+            child_count: AtomicUsize::new(0),
+            children: AtomicUsize::new(0),
+            expanding: AtomicBool::new(false),
+            key: AtomicUsize::new(self.key()),
+            m: self.m,
+            plays: AtomicUsize::new(self.plays.load(Ordering::Relaxed)),
+            prior_plays: self.prior_plays,
+            prior_wins: self.prior_wins,
+            terminal: AtomicUsize::new(self.terminal.load(Ordering::Relaxed)),
+            wins: AtomicUsize::new(self.wins.load(Ordering::Relaxed)),
         }
     }
 
-    pub fn make_root(&mut self, color: Color) {
-        // Set these values to zero, as the new root is actually a
-        // node of the opponent. Otherwise the win ratio would
-        // approach 0% as we win the game. And then we would resign!
-        self.reset();
-        // The root has to have the color of the player we want to
-        // simulate. Otherwise the win statistics are for the wrong
-        // player!
-        self.m = Pass(color);
-    }
-
-    fn reset(&mut self) {
-        self.amaf_plays = 0.0;
-        self.amaf_wins = 0.0;
-        self.playouts = 0;
-        self.plays = 0.0;
-        self.prior_plays = 0;
-        self.prior_wins = 0;
-        self.wins = 0.0;
-    }
-
-    pub fn remove_illegal_children(&mut self, game: &Game) {
-        let mut to_remove = vec!();
-
-        for (index, node) in self.children.iter().enumerate() {
-            match node.m() {
-                Play(..) => if game.play(node.m()).is_err() {
-                    to_remove.push(index);
-                },
-                _ => unreachable!()
-            }
-        }
-        to_remove.reverse();
-        for &index in to_remove.iter() {
-            self.descendants -= self.children[index].descendants;
-            self.children.remove(index);
+    /// The arena index of the first child and the number of
+    /// children, if the node was expanded.
+    pub fn children(&self) -> Option<(usize, usize)> {
+        match self.children.load(Ordering::Acquire) {
+            0 => None,
+            first => Some((first, self.child_count.load(Ordering::Relaxed)))
         }
     }
 
-    pub fn find_leaf_and_expand(&mut self, game: &Game, matcher: Arc<Matcher>) -> (Vec<usize>, Vec<Move>, bool, usize) {
-        let (path, moves, leaf) = self.find_leaf_and_mark(vec!(), vec!());
-        let mut board = game.board();
-        for &m in moves.iter() {
-            board.play_legal_move(m);
-        }
-        let previous_desc = leaf.descendants;
-        let not_terminal = leaf.expand(&board, matcher);
-        if !not_terminal {
-            let is_win = board.winner() == leaf.color();
-            leaf.mark_as_terminal(is_win);
-        }
-        let new_desc = leaf.descendants - previous_desc;
-        (path, moves, not_terminal, new_desc)
-    }
-
-    /// Finds the next leave to simulate. To make sure that different
-    /// paths are taken through the tree (as we execute the
-    /// simulations in parallel) we already increase the play count
-    /// here instead of when recording the wins in the tree.
-    pub fn find_leaf_and_mark(&mut self, mut path: Vec<usize>, mut moves: Vec<Move>) -> (Vec<usize>, Vec<Move>, &mut Node) {
-        self.record_play();
-        if self.is_leaf() {
-            (path, moves, self)
-        } else {
-            let index = self.next_child_index();
-            path.push(index);
-            moves.push(self.children[index].m());
-            self.children[index].find_leaf_and_mark(path, moves)
-        }
+    /// Publishes the children. They need to be fully written to the
+    /// arena before calling this.
+    pub fn set_children(&self, first: usize, count: usize) {
+        self.child_count.store(count, Ordering::Relaxed);
+        self.children.store(first, Ordering::Release);
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children().is_none()
+    }
+
+    /// Returns true for exactly one of the threads that try to
+    /// expand this node.
+    pub fn claim_expansion(&self) -> bool {
+        !self.expanding.compare_and_swap(false, true, Ordering::SeqCst)
+    }
+
+    /// The key of the position in the transposition table. It's
+    /// only known after the node was selected once (0 until then).
+    pub fn key(&self) -> usize {
+        self.key.load(Ordering::Relaxed)
     }
 
-    fn expand_root(&mut self, game: &Game) {
-        if !game.is_over() {
-            self.children = game.legal_moves_without_eyes()
-                .iter()
-                .map(|&m| Node::new(m, self.config.clone()))
-                .collect();
-            self.descendants = self.children.len();
+    pub fn set_key(&self, key: usize) {
+        self.key.store(key, Ordering::Relaxed);
+    }
+
+    pub fn new_leaf(board: &Board, m: &Move, matcher: Arc<Matcher>, config: &Config) -> Node {
+        let mut node = Node::new(*m, config);
+
+        if !board.is_not_self_atari(m) {
+            // That's a negative prior
+            node.record_priors(config.priors.self_atari, 0);
+        }
+        if config.priors.empty > 0 {
+            let distance = m.coord().distance_to_border(board.size());
+            if distance <= 2 && Self::in_empty_area(board, m) {
+                if distance <= 1 {
+                    // That's a negative prior
+                    node.record_priors(config.priors.empty, 0);
+                } else {
+                    node.record_even_prior(config.priors.empty);
+                }
+            }
         }
- }
-
-    pub fn expand(&mut self, board: &Board, matcher: Arc<Matcher>) -> bool {
-        let not_terminal = !board.is_game_over();
-        if not_terminal && self.playouts >= self.config.tree.expand_after {
-            let mut children = board.legal_moves_without_eyes()
-                .iter()
-                .map(|m| self.new_leaf(board, m, matcher.clone()))
-                .collect();
-            self.priors(&mut children, board);
-            self.children = children;
+        if config.priors.patterns > 0 {
+            let count = matcher.pattern_count(board, &m.coord());
+            let prior = count * config.priors.patterns;
+            node.record_even_prior(prior);
+        }
+        if config.priors.large_patterns > 0 {
+            if let Some(gamma) = matcher.gamma(board, m) {
+                let plays = config.priors.large_patterns;
+                let wins = (plays as f32 * gamma / (gamma + 1.0)).round() as usize;
+                node.record_priors(plays, wins);
+            }
         }
-        self.descendants = self.children.len();
-        not_terminal
+        node
     }
 
-    pub fn priors(&self, children: &mut Vec<Node>, board: &Board) {
+    pub fn priors(children: &mut Vec<Node>, board: &Board, config: &Config) {
         let color = board.next_player().opposite();
 
         let in_danger = board.chains().iter()
@@ -201,7 +192,7 @@ impl Node {
         for one_stone in in_danger {
             if let Some(solution) = board.capture_ladder(one_stone) {
                 if let Some(node) = children.iter_mut().find(|c| c.m() == solution) {
-                    node.record_even_prior(self.config.priors.capture_one);
+                    node.record_even_prior(config.priors.capture_one);
                 }
             }
         }
@@ -212,144 +203,90 @@ impl Node {
         for many_stones in in_danger {
             if let Some(solution) = board.capture_ladder(many_stones) {
                 if let Some(node) = children.iter_mut().find(|c| c.m() == solution) {
-                    node.record_even_prior(self.config.priors.capture_many);
+                    node.record_even_prior(config.priors.capture_many);
                 }
             }
         }
     }
 
-    pub fn new_leaf(&self, board: &Board, m: &Move, matcher: Arc<Matcher>) -> Node {
-        let mut node = Node::new(*m, self.config.clone());
-
-        if !board.is_not_self_atari(m) {
-            // That's a negative prior
-            node.record_priors(self.config.priors.self_atari, 0);
-        }
-        if self.use_empty() {
-            let distance = m.coord().distance_to_border(board.size());
-            if distance <= 2 && self.in_empty_area(board, m) {
-                if distance <= 1 {
-                    // That's a negative prior
-                    node.record_priors(self.config.priors.empty, 0);
-                } else {
-                    node.record_even_prior(self.config.priors.empty);
-                }
-            }
-        }
-        if self.use_patterns() {
-            let count = self.matching_patterns_count(board, m, matcher);
-            let prior = count * self.config.priors.patterns;
-            node.record_even_prior(prior);
-        }
-        node
-    }
-
-    fn use_patterns(&self) -> bool {
-        self.config.priors.patterns > 0
-    }
-
-    fn matching_patterns_count(&self, board: &Board, m: &Move, matcher: Arc<Matcher>) -> usize {
-        matcher.pattern_count(board, &m.coord())
-    }
-
-    fn in_empty_area(&self, board: &Board, m: &Move) -> bool {
+    fn in_empty_area(board: &Board, m: &Move) -> bool {
         m.coord().manhattan_distance_three_neighbours(board.size())
             .iter()
             .all(|c| board.color(c) == Empty)
     }
 
-    pub fn has_no_children(&self) -> bool {
-        self.children.len() == 0
+    pub fn mark_as_terminal(&self, is_win: bool) {
+        let terminal = if is_win { TERMINAL_WIN } else { TERMINAL_LOSS };
+        self.terminal.store(terminal, Ordering::Relaxed);
     }
 
-    pub fn is_leaf(&self) -> bool {
-        self.children.len() == 0
+    pub fn weighted_win(config: &Config, score: &Score) -> usize {
+        let weight = config.tree.score_weight;
+        (((weight * score.adjusted()) + (1.0 - weight)) * WIN_SCALE).round() as usize
     }
 
-    pub fn mark_as_terminal(&mut self, is_win: bool) {
-        self.plays = f32::MAX;
-        if is_win {
-            self.wins = f32::MAX;
-        } else {
-            self.wins = 0.0;
-        }
+    pub fn record_win(&self, config: &Config, score: &Score) {
+        self.wins.fetch_add(Self::weighted_win(config, score), Ordering::Relaxed);
     }
 
-    pub fn record_on_path(&mut self, path: &[usize], new_nodes: usize, playout_result: &PlayoutResult) {
-        let winner = playout_result.winner();
-        let amaf = playout_result.amaf();
-        if self.color() == winner {
-            self.record_win(playout_result.score());
-        }
-        // We need to switch the color as we see things from the
-        // opponent's point of view now.
-        let color = self.color().opposite();
-        for child in self.children.iter_mut() {
-            if !child.m.is_pass() {
-                match amaf.get(&child.m.coord()) {
-                    Some(&c) if c == color => {
-                        child.record_amaf_play();
-                        if color == winner {
-                            child.record_amaf_win(playout_result.score());
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-        if path.len() > 0 {
-            self.descendants += new_nodes;
-            self.children[path[0]].record_on_path(&path[1..], new_nodes, playout_result);
-        }
+    pub fn record_amaf_win(&self, config: &Config, score: &Score) {
+        self.amaf_wins.fetch_add(Self::weighted_win(config, score), Ordering::Relaxed);
     }
 
-    pub fn best(&self) -> &Node {
-        let mut best = &self.children[0];
-        for n in self.children.iter() {
-            if n.plays_with_prior_factor() > best.plays_with_prior_factor() {
-                best = n;
-            }
-        }
-        best
+    /// Called when descending the tree. As the win is only recorded
+    /// after the playout finished this acts as a virtual loss. This
+    /// makes sure the threads take different paths through the tree.
+    pub fn record_play(&self) {
+        self.plays.fetch_add(1, Ordering::Relaxed);
     }
 
-    fn weighted_win(&self, score: &Score) -> f32 {
-        let weight = self.config.tree.score_weight;
-        (weight * score.adjusted()) + (1.0 - weight)
+    pub fn record_amaf_play(&self) {
+        self.amaf_plays.fetch_add(1, Ordering::Relaxed);
     }
 
-    fn record_win(&mut self, score: &Score) {
-        self.wins += self.weighted_win(score);
+    fn record_priors(&mut self, prior_plays: usize, prior_wins: usize) {
+        self.prior_plays += prior_plays;
+        self.prior_wins += prior_wins;
     }
 
-    fn record_amaf_win(&mut self, score: &Score) {
-        self.amaf_wins += self.weighted_win(score);
+    fn record_even_prior(&mut self, prior: usize) {
+        self.record_priors(prior, prior);
     }
 
-    fn record_play(&mut self) {
-        self.playouts += 1;
-        self.plays += 1.0;
+    fn plays(&self) -> f32 {
+        match self.terminal.load(Ordering::Relaxed) {
+            NOT_TERMINAL => self.plays.load(Ordering::Relaxed) as f32,
+            _ => f32::MAX
+        }
     }
 
-    fn record_amaf_play(&mut self) {
-        self.amaf_plays += 1.0;
+    fn wins(&self) -> f32 {
+        match self.terminal.load(Ordering::Relaxed) {
+            NOT_TERMINAL => self.wins.load(Ordering::Relaxed) as f32 / WIN_SCALE,
+            TERMINAL_WIN => f32::MAX,
+            _ => 0.0
+        }
     }
 
-    fn record_priors(&mut self, prior_plays: usize, prior_wins: usize) {
-        self.prior_plays += prior_plays;
-        self.prior_wins += prior_wins;
+    pub fn amaf_plays(&self) -> usize {
+        self.amaf_plays.load(Ordering::Relaxed)
     }
 
-    fn record_even_prior(&mut self, prior: usize) {
-        self.record_priors(prior, prior);
+    pub fn amaf_win_ratio(&self) -> f32 {
+        let plays = self.amaf_plays();
+        if plays == 0 {
+            0.0
+        } else {
+            self.amaf_wins.load(Ordering::Relaxed) as f32 / WIN_SCALE / plays as f32
+        }
     }
 
-    fn plays_with_prior_factor(&self) -> f32 {
-        self.plays as f32 + (self.prior_plays as f32 * self.config.priors.best_move_factor)
+    pub fn prior_plays(&self) -> usize {
+        self.prior_plays
     }
 
-    fn wins_with_prior_factor(&self) -> f32 {
-        self.wins as f32 + (self.prior_wins as f32 * self.config.priors.best_move_factor)
+    pub fn plays_with_prior_factor(&self, config: &Config) -> f32 {
+        self.plays() + (self.prior_plays as f32 * config.priors.best_move_factor)
     }
 
     pub fn m(&self) -> Move {
@@ -357,71 +294,56 @@ impl Node {
     }
 
     pub fn playouts(&self) -> usize {
-        self.playouts
-    }
-
-    pub fn descendants(&self) -> usize {
-        self.descendants
+        self.plays.load(Ordering::Relaxed)
     }
 
-    pub fn find_child(&self, m: Move) -> Node {
-        match self.children.iter().find(|c| c.m() == m) {
-            Some(node) => node.clone(),
-            None => Node::new(NoMove, self.config.clone()),
-        }
-    }
-
-    fn uct_tuned_value(&self, parent_plays: f32) -> f32 {
+    fn uct_tuned_value(&self, config: &Config, parent_plays: f32, plays: f32, wins: f32) -> f32 {
         const MAX_BERNOULLI_VARIANCE: f32 = 0.25;
-        let p = self.win_ratio_with_priors(); //bernoulli distribution parameter
+        let factor = config.priors.best_move_factor;
+        let plays = plays + self.prior_plays as f32 * factor;
+        let wins = wins + self.prior_wins as f32 * factor;
+        let p = if plays == 0.0 { 0.0 } else { wins / plays }; //bernoulli distribution parameter
         let variance = p * (1.0 - p);
-        let variance_upper_bound = variance + ((2.0 * parent_plays.ln())/self.plays_with_prior_factor()).sqrt();
+        let variance_upper_bound = variance + ((2.0 * parent_plays.ln())/plays).sqrt();
         let smaller_upper_bound = MAX_BERNOULLI_VARIANCE.min(variance_upper_bound); //can't be greater than the theoretical variance
 
-        p + ((parent_plays.ln()) * smaller_upper_bound / self.plays_with_prior_factor()).sqrt()
+        p + ((parent_plays.ln()) * smaller_upper_bound / plays).sqrt()
     }
 
-    fn next_child_index(&self) -> usize {
-        let mut best = 0;
-        let plays = self.plays_with_prior_factor();
-        for i in 1..self.children.len() {
-            if self.children[i].child_value(plays) > self.children[best].child_value(plays) {
-                best = i;
-            }
+    // The plays and wins used when selecting the node. The
+    // statistics of the transposition table are used instead of the
+    // ones of the node when they include more plays, i.e. when the
+    // position was also reached through other move orders.
+    fn selection_stats(&self, transposed: Option<(usize, usize)>) -> (f32, f32) {
+        match transposed {
+            Some((plays, wins)) if plays > self.playouts() && self.terminal.load(Ordering::Relaxed) == NOT_TERMINAL =>
+                (plays as f32, wins as f32 / WIN_SCALE),
+            _ => (self.plays(), self.wins())
         }
-        best
     }
 
-    fn child_value(&self, parent_plays: f32) -> f32 {
-        let uct = self.uct_tuned_value(parent_plays);
-        if self.amaf_plays == 0.0 {
+    pub fn child_value(&self, config: &Config, parent_plays: f32, transposed: Option<(usize, usize)>) -> f32 {
+        let (plays, wins) = self.selection_stats(transposed);
+        let uct = self.uct_tuned_value(config, parent_plays, plays, wins);
+        let ap = self.amaf_plays() as f32;
+        if ap == 0.0 {
             uct
         } else {
-            let aw = self.amaf_wins;
-            let ap = self.amaf_plays;
-            let p = self.plays_with_prior_factor();
-            let rave_equiv = self.config.tree.rave_equiv;
+            let aw = self.amaf_wins.load(Ordering::Relaxed) as f32 / WIN_SCALE;
+            let p = plays + (self.prior_plays as f32 * config.priors.best_move_factor);
+            let rave_equiv = config.tree.rave_equiv;
             let rave_winrate = aw / ap;
             let beta = ap / (ap + p + p * ap / rave_equiv);
             beta * rave_winrate + (1.0 - beta) * uct
         }
     }
 
-    fn win_ratio_with_priors(&self) -> f32 {
-        let p = self.plays_with_prior_factor();
-        if p == 0.0 {
-            0.0
-        } else {
-            let w = self.wins_with_prior_factor();
-            w / p
-        }
-    }
-
     pub fn win_ratio(&self) -> f32 {
-        if self.plays == 0.0 {
+        let plays = self.plays();
+        if plays == 0.0 {
             0f32
         } else {
-            self.wins / self.plays
+            self.wins() / plays
         }
     }
 
@@ -429,9 +351,4 @@ impl Node {
         *self.m().color()
     }
 
-    fn use_empty(&self) -> bool {
-        self.config.priors.empty > 0
-    }
-
-
 }
@@ -27,267 +27,126 @@ pub use board::Pass;
 pub use board::Play;
 pub use board::White;
 pub use config::Config;
-pub use game::Game;
-pub use patterns::Matcher;
-pub use playout::Playout;
-pub use playout::PlayoutResult;
 pub use ruleset::KgsChinese;
-pub use score::Score;
-pub use sgf::Parser;
 pub use super::Node;
 
-pub use rand::weak_rng;
-pub use std::collections::HashMap;
-pub use std::path::Path;
+pub use std::f32;
 pub use std::sync::Arc;
-pub use test::Bencher;
 
 pub fn config() -> Arc<Config> {
     let mut config = Config::test_config();
-    config.tree.expand_after = 0;
+    config.tree.score_weight = 0.0;
     Arc::new(config)
 }
 
-fn matcher() -> Arc<Matcher> {
-    Arc::new(Matcher::new())
-}
-
-fn expand_after(expand_after: usize) -> Arc<Config> {
-    let mut config = Arc::try_unwrap(config()).unwrap();
-    config.tree.expand_after = expand_after;
-    Arc::new(config)
-}
-
-
-#[test]
-fn root_expands_the_children() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let root = Node::root(&game, Black, config());
-    assert_eq!(4, root.children.len());
-}
-
-// expand()
-#[test]
-fn expand_doesnt_add_children_to_terminal_nodes() {
-    let mut game = Game::new(5, 6.5, KgsChinese);
-    game = game.play(Pass(Black)).unwrap();
-    game = game.play(Pass(White)).unwrap();
-    let mut node = Node::new(Pass(Black), config());
-    node.expand(&game.board(), matcher());
-    assert_eq!(0, node.children.len());
-}
-
-#[test]
-fn expand_doesnt_add_children_if_threshold_not_met() {
-    let config = expand_after(1);
-    let game = Game::new(2, 0.5, KgsChinese);
-    let mut node = Node::new(Pass(Black), config);
-    node.plays = 0.0;
-    node.expand(&game.board(), matcher());
-    assert_eq!(0, node.children.len());
-}
-
-#[test]
-fn expand_adds_children_if_threshold_is_met() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let mut node = Node::new(Pass(Black), config());
-    node.plays = 2.0;
-    node.expand(&game.board(), matcher());
-    assert_eq!(4, node.children.len());
-}
-
 #[test]
-fn expand_sets_the_descendant_count_if_the_node_was_expanded() {
-    let game = Game::new(5, 6.5, KgsChinese);
-    let board = game.board();
-    let mut node = Node::new(Pass(Black), config());
-    node.expand(&board,matcher());
-    assert_eq!(25, node.descendants);
+fn new_uses_the_neutral_priors() {
+    let config = config();
+    let node = Node::new(Play(Black, 1, 1), &config);
+    assert_eq!(config.priors.neutral_plays, node.prior_plays);
+    assert_eq!(config.priors.neutral_wins, node.prior_wins);
 }
 
 #[test]
-fn expand_doesnt_add_pass_before_the_endgame() {
-    let game = Game::new(5, 6.5, KgsChinese);
-    let board = game.board();
-    let mut node = Node::new(Pass(Black), config());
-    node.expand(&board, matcher());
-    let found_pass = node.children.iter().any(|node| node.m().is_pass());
-    assert!(!found_pass);
+fn root_has_no_priors() {
+    let node = Node::root(Black);
+    assert_eq!(0, node.prior_plays);
+    assert_eq!(Pass(Black), node.m());
 }
 
-// find_leaf_and_expand()
 #[test]
-fn find_leaf_and_expand_expands_the_leaves() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let mut root = Node::root(&game, Black, config());
-    for _ in 0..4 {
-        root.find_leaf_and_expand(&game, matcher());
-    }
-    assert_eq!(4, root.children.len());
-    assert!(root.children.iter().all(|n| n.children.len() == 3));
+fn only_one_thread_can_claim_the_expansion() {
+    let node = Node::root(Black);
+    assert!(node.claim_expansion());
+    assert!(!node.claim_expansion());
 }
 
 #[test]
-fn find_leaf_and_expand_sets_play_on_the_root() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let mut root = Node::root(&game, Black, config());
-    root.find_leaf_and_expand(&game, matcher());
-    assert_eq!(2.0, root.plays);
+fn children_are_none_until_set() {
+    let node = Node::root(Black);
+    assert_eq!(None, node.children());
+    assert!(node.is_leaf());
+    node.set_children(5, 3);
+    assert_eq!(Some((5, 3)), node.children());
+    assert!(!node.is_leaf());
 }
 
 #[test]
-fn find_leaf_and_expand_returns_the_number_of_nodes_added() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let mut root = Node::root(&game, Black, config());
-    let (_,_,_,count) = root.find_leaf_and_expand(&game, matcher());
-    assert_eq!(3, count);
+fn record_win_scales_the_wins() {
+    let config = config();
+    let node = Node::root(Black);
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 1, 1)).unwrap();
+    node.record_play();
+    node.record_play();
+    node.record_win(&config, &board.score());
+    assert_eq!(2, node.playouts());
+    assert_eq!(0.5, node.win_ratio());
 }
 
 #[test]
-fn the_root_needs_to_be_initialized_with_1_plays_for_correct_uct_calculations() {
-    let game = Game::new(2, 0.5, KgsChinese);
-    let root = Node::root(&game, Black, config());
-    assert_eq!(1.0, root.plays);
-    assert_eq!(1.0, root.wins);
- }
-
-#[test]
-fn no_super_ko_violations_in_the_children_of_the_root() {
-    let parser = Parser::from_path(Path::new("fixtures/sgf/positional-superko.sgf")).unwrap();
-    let game = parser.game().unwrap();
-    let root = Node::root(&game, White, config());
-    // Play(White, 2, 9) is a super ko violation
-    assert!(root.children.iter().all(|n| n.m() != Play(White, 2, 9)));
-}
-
-describe! record_on_path {
-
-    before_each {
-        let mut c = Config::test_config();
-        c.tree.score_weight = 0.0;
-        let config = Arc::new(c);
-    }
-
-    it "only records wins for the correct color" {
-        let grandchild = Node::new(Pass(Black), config.clone());
-        let mut child = Node::new(Pass(White), config.clone());
-        child.children = vec!(grandchild);
-        let mut root = Node::new(Pass(Black), config.clone());
-        root.children = vec!(child);
-
-        let mut board = Board::new(9, 6.5, KgsChinese);
-        board.play(Play(Black, 1, 1)).unwrap();
-        let score = board.score();
-        let playout_result = PlayoutResult::new(score, HashMap::new());
-        root.record_on_path(&vec!(0, 0), 0, &playout_result);
-        assert_eq!(1.0, root.wins);
-        assert_eq!(0.0, root.children[0].wins);
-        assert_eq!(1.0, root.children[0].children[0].wins);
-
-        let board = Board::new(9, 6.5, KgsChinese);
-        let score = board.score();
-        let playout_result = PlayoutResult::new(score, HashMap::new());
-        root.record_on_path(&vec!(0, 0), 0, &playout_result);
-        assert_eq!(1.0, root.wins);
-        assert_eq!(1.0, root.children[0].wins);
-        assert_eq!(1.0, root.children[0].children[0].wins);
-    }
-
-    it "updates the descendant counts" {
-        let mut grandchild = Node::new(Pass(Black), config.clone());
-        // The leaf already has the correct value set
-        grandchild.descendants = 5;
-        let mut child = Node::new(Pass(White), config.clone());
-        child.children = vec!(grandchild);
-        child.descendants = 1;
-        let mut root = Node::new(Pass(Black), config.clone());
-        root.children = vec!(child);
-        root.descendants = 2;
-
-        let mut board = Board::new(9, 6.5, KgsChinese);
-        board.play(Play(Black, 1, 1)).unwrap();
-        let score = board.score();
-        let playout_result = PlayoutResult::new(score, HashMap::new());
-        root.record_on_path(&vec!(0, 0), 5, &playout_result);
-        assert_eq!(7, root.descendants);
-        assert_eq!(6, root.children[0].descendants);
-        assert_eq!(5, root.children[0].children[0].descendants);
-    }
+fn win_ratio_is_zero_without_plays() {
+    let node = Node::root(Black);
+    assert_eq!(0.0, node.win_ratio());
 }
 
 #[test]
-fn find_child_returns_the_correct_child() {
-    let mut root = Node::new(Pass(Black), config().clone());
-    let child = Node::new(Play(White, 1, 1), config().clone());
-    root.children = vec!(Node::new(Play(Black, 5, 5), config().clone()), child.clone(), Node::new(Play(Black, 3, 7), config().clone()));
-    assert_eq!(child, root.find_child(Play(White, 1, 1)));
+fn terminal_wins_are_always_chosen() {
+    let config = config();
+    let node = Node::new(Play(Black, 1, 1), &config);
+    node.mark_as_terminal(true);
+    assert_eq!(1.0, node.win_ratio());
+    assert_eq!(f32::MAX, node.plays_with_prior_factor(&config));
 }
 
 #[test]
-fn new_sets_the_descendats_to_zero() {
-    let node = Node::new(Pass(Black), config());
-    assert_eq!(0, node.descendants);
+fn terminal_losses_have_a_win_ratio_of_zero() {
+    let node = Node::root(Black);
+    node.record_play();
+    node.mark_as_terminal(false);
+    assert_eq!(0.0, node.win_ratio());
 }
 
-// expand_root()
 #[test]
-fn expand_root_sets_the_correct_descendant_count_on_the_root() {
-    let game = Game::new(5, 6.5, KgsChinese);
-    let mut root = Node::new(Pass(Black), config());
-    root.expand_root(&game);
-    assert_eq!(25, root.descendants);
+fn copy_keeps_the_statistics_but_not_the_children() {
+    let config = config();
+    let node = Node::new(Play(White, 2, 2), &config);
+    node.record_play();
+    node.record_amaf_play();
+    node.set_children(1, 4);
+    let copy = node.copy();
+    assert_eq!(Play(White, 2, 2), copy.m());
+    assert_eq!(1, copy.playouts());
+    assert_eq!(1, copy.amaf_plays());
+    assert_eq!(node.prior_plays, copy.prior_plays);
+    assert!(copy.is_leaf());
 }
 
 #[test]
-fn expand_root_doesnt_add_pass() {
-    let game = Game::new(5, 6.5, KgsChinese);
-    let mut node = Node::new(Pass(Black), config());
-    node.expand_root(&game);
-    let found_pass = node.children.iter().any(|node| node.m().is_pass());
-    assert!(!found_pass);
+fn self_atari_gets_a_negative_prior() {
+    let config = config();
+    let mut board = Board::new(3, 6.5, KgsChinese);
+    board.play(Play(Black, 3, 3)).unwrap();
+    board.play(Play(White, 1, 2)).unwrap();
+    let matcher = Arc::new(::patterns::Matcher::new());
+    let node = Node::new_leaf(&board, &Play(Black, 1, 1), matcher, &config);
+    let neutral = Node::new(Play(Black, 1, 1), &config);
+    assert!(node.prior_plays - node.prior_wins > neutral.prior_plays - neutral.prior_wins);
 }
 
-// remove_illegal_children()
 #[test]
-fn remove_illegal_children_removes_superko_violations() {
-    let parser = Parser::from_path(Path::new("fixtures/sgf/positional-superko.sgf")).unwrap();
-    let game = parser.game().unwrap();
-    let mut node = Node::new(Pass(White), config());
-    // Play(White, 2, 9) is a super ko violation
-    node.children.push(Node::new(Play(White, 2, 9), config()));
-    node.remove_illegal_children(&game);
-    assert!(node.children.iter().all(|n| n.m() != Play(White, 2, 9)));
-}
-
-#[bench]
-fn full_uct_cycle_09x09(b: &mut Bencher) {
-    full_uct_cycle(9, b);
-}
-
-#[bench]
-fn full_uct_cycle_13x13(b: &mut Bencher) {
-    full_uct_cycle(13, b);
-}
-
-#[bench]
-fn full_uct_cycle_19x19(b: &mut Bencher) {
-    full_uct_cycle(19, b);
-}
-
-fn full_uct_cycle(size: u8, b: &mut Bencher) {
-    let game = Game::new(size, 6.5, KgsChinese);
-    let matcher = matcher();
-    let config = Arc::new(Config::test_config());
-    let mut root = Node::root(&game, Black, config.clone());
-    let playout = Playout::new(config.clone(), matcher.clone());
-    let mut rng = weak_rng();
-    b.iter(|| {
-        let (path, moves, _, nodes_added) = root.find_leaf_and_expand(&game, matcher.clone());
-        let mut b = game.board();
-        for &m in moves.iter() {
-            b.play_legal_move(m);
-        }
-        let playout_result = playout.run(&mut b, None, &mut rng);
-        root.record_on_path(&path, nodes_added, &playout_result);
-    });
+fn large_patterns_set_the_prior_from_the_gamma() {
+    let mut c = Config::test_config();
+    c.patterns.file = String::from("fixtures/patterns/example.pat");
+    c.priors.patterns = 0;
+    c.priors.empty = 0;
+    let config = Arc::new(c);
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 6, 5)).unwrap();
+    board.play(Play(White, 4, 5)).unwrap();
+    let matcher = Arc::new(::patterns::Matcher::from_config(&config).unwrap());
+    let node = Node::new_leaf(&board, &Play(Black, 5, 5), matcher, &config);
+    // gamma = 2.5, i.e. a win rate of 2.5 / 3.5
+    assert_eq!(config.priors.neutral_plays + 20, node.prior_plays);
+    assert_eq!(config.priors.neutral_wins + 14, node.prior_wins);
 }
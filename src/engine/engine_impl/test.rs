@@ -0,0 +1,102 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+pub use board::Black;
+pub use board::Play;
+pub use book::Book;
+pub use engine::Engine;
+pub use config::Config;
+pub use game::Game;
+pub use patterns::Matcher;
+pub use ruleset::KgsChinese;
+pub use super::EngineImpl;
+pub use super::tree::Tree;
+pub use timer::Timer;
+
+pub use std::path::Path;
+pub use std::sync::Arc;
+pub use test::Bencher;
+
+fn engine(threads: usize) -> EngineImpl {
+    let mut config = Config::test_config();
+    config.threads = threads;
+    EngineImpl::new(Arc::new(config), Arc::new(Matcher::new()), Arc::new(Book::new()))
+}
+
+#[test]
+fn search_runs_until_done() {
+    let game = Game::new(9, 6.5, KgsChinese);
+    let mut engine = engine(2);
+    engine.tree = Tree::root(&game, Black, engine.config.clone());
+    engine.search(&game, |e| e.tree.playouts() >= 100);
+    assert!(engine.tree.playouts() >= 100);
+}
+
+#[test]
+fn search_uses_all_threads_on_the_same_tree() {
+    let game = Game::new(9, 6.5, KgsChinese);
+    let mut engine = engine(4);
+    engine.tree = Tree::root(&game, Black, engine.config.clone());
+    engine.search(&game, |e| e.tree.playouts() >= 400);
+    let best = engine.tree.best();
+    assert!(best.playouts() > 0);
+    assert!(engine.tree.descendants() > 81);
+}
+
+#[test]
+fn genmove_plays_from_the_book_without_searching() {
+    let config = Arc::new(Config::test_config());
+    let mut book = Book::new();
+    book.load(Path::new("fixtures/sgf/book/opening.sgf"), config.book.max_depth).unwrap();
+    let mut engine = EngineImpl::new(config.clone(), Arc::new(Matcher::new()), Arc::new(book));
+    let game = Game::new(9, 7.0, KgsChinese);
+    let (m, playouts) = engine.genmove(Black, &game, &Timer::new(config));
+    assert_eq!(Play(Black, 5, 5), m);
+    assert_eq!(0, playouts);
+}
+
+#[bench]
+fn search_09x09_1_thread(b: &mut Bencher) {
+    search(9, 1, b);
+}
+
+#[bench]
+fn search_09x09_2_threads(b: &mut Bencher) {
+    search(9, 2, b);
+}
+
+#[bench]
+fn search_09x09_4_threads(b: &mut Bencher) {
+    search(9, 4, b);
+}
+
+// Runs 1000 simulations per iteration, so the time per iteration
+// should go down as the number of threads goes up.
+fn search(size: u8, threads: usize, b: &mut Bencher) {
+    let game = Game::new(size, 6.5, KgsChinese);
+    let mut engine = engine(threads);
+    b.iter(|| {
+        engine.tree = Tree::root(&game, Black, engine.config.clone());
+        engine.search(&game, |e| e.tree.playouts() >= 1000);
+    });
+}
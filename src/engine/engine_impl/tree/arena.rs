@@ -0,0 +1,157 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use super::super::node::Node;
+
+use std::cmp;
+use std::mem;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+const CHUNK_SIZE: usize = 4096;
+const MAX_CHUNKS: usize = 16384;
+
+/// Allocates the nodes of the tree. The memory is reserved in chunks
+/// that never move, so references to the nodes stay valid until the
+/// arena is dropped. Nodes are allocated in blocks (all the children
+/// of a node) that never span two chunks. The arena never holds more
+/// nodes than its capacity.
+pub struct Arena {
+    capacity: usize,
+    chunks: Vec<AtomicPtr<Node>>,
+    full: AtomicBool,
+    len: AtomicUsize,
+    lock: Mutex<()>,
+    next: AtomicUsize,
+}
+
+impl Arena {
+
+    /// A capacity of 0 means that the arena holds as many nodes as
+    /// possible.
+    pub fn new(capacity: usize) -> Arena {
+        let max = CHUNK_SIZE * MAX_CHUNKS;
+        Arena {
+            capacity: if capacity == 0 { max } else { cmp::min(capacity, max) },
+            chunks: (0..MAX_CHUNKS).map(|_| AtomicPtr::new(ptr::null_mut())).collect(),
+            full: AtomicBool::new(false),
+            len: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of nodes allocated so far.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    /// The memory (in bytes) reserved for the nodes so far.
+    pub fn memory(&self) -> usize {
+        let chunks = self.chunks.iter()
+            .filter(|chunk| !chunk.load(Ordering::Relaxed).is_null())
+            .count();
+        chunks * CHUNK_SIZE * mem::size_of::<Node>()
+    }
+
+    /// True once an allocation failed because the capacity was
+    /// reached.
+    pub fn is_full(&self) -> bool {
+        self.full.load(Ordering::Relaxed)
+    }
+
+    /// Moves the nodes into a contiguous block of the arena and
+    /// returns the index of the first one. Returns `None` if the
+    /// arena is full.
+    pub fn allocate(&self, nodes: Vec<Node>) -> Option<usize> {
+        let count = nodes.len();
+        assert!(count <= CHUNK_SIZE);
+        loop {
+            let next = self.next.load(Ordering::SeqCst);
+            let offset = next % CHUNK_SIZE;
+            let first = if offset + count > CHUNK_SIZE {
+                next - offset + CHUNK_SIZE
+            } else {
+                next
+            };
+            if first + count > self.capacity {
+                self.full.store(true, Ordering::Relaxed);
+                return None;
+            }
+            if self.next.compare_and_swap(next, first + count, Ordering::SeqCst) == next {
+                let chunk = self.chunk(first / CHUNK_SIZE);
+                for (i, node) in nodes.into_iter().enumerate() {
+                    unsafe { ptr::write(chunk.offset((first % CHUNK_SIZE + i) as isize), node); }
+                }
+                self.len.fetch_add(count, Ordering::Relaxed);
+                return Some(first);
+            }
+        }
+    }
+
+    /// Only valid for indexes returned by `allocate()`.
+    pub fn get(&self, index: usize) -> &Node {
+        let chunk = self.chunks[index / CHUNK_SIZE].load(Ordering::Acquire);
+        unsafe { &*chunk.offset((index % CHUNK_SIZE) as isize) }
+    }
+
+    /// Overwrites an allocated node. Requires exclusive access to the
+    /// arena, i.e. no search can be running.
+    pub fn replace(&mut self, index: usize, node: Node) {
+        let chunk = self.chunks[index / CHUNK_SIZE].load(Ordering::Acquire);
+        unsafe { ptr::write(chunk.offset((index % CHUNK_SIZE) as isize), node); }
+    }
+
+    fn chunk(&self, index: usize) -> *mut Node {
+        let chunk = self.chunks[index].load(Ordering::Acquire);
+        if !chunk.is_null() {
+            return chunk;
+        }
+        let _guard = self.lock.lock().unwrap();
+        let chunk = self.chunks[index].load(Ordering::Acquire);
+        if !chunk.is_null() {
+            return chunk;
+        }
+        let mut nodes: Vec<Node> = Vec::with_capacity(CHUNK_SIZE);
+        let chunk = nodes.as_mut_ptr();
+        mem::forget(nodes);
+        self.chunks[index].store(chunk, Ordering::Release);
+        chunk
+    }
+}
+
+impl Drop for Arena {
+
+    fn drop(&mut self) {
+        for chunk in self.chunks.iter() {
+            let chunk = chunk.load(Ordering::Acquire);
+            if !chunk.is_null() {
+                // Nodes don't own any resources so there's no need
+                // to drop them individually.
+                unsafe { Vec::from_raw_parts(chunk, 0, CHUNK_SIZE); }
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Board;
+use board::Color;
+use board::Coord;
+use board::Empty;
+use board::Move;
+use board::NoMove;
+use config::Config;
+use engine::analysis::Candidate;
+use game::Game;
+use patterns::Matcher;
+use playout::PlayoutResult;
+use self::arena::Arena;
+use self::transposition_table::TranspositionTable;
+use super::node::Node;
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Arc;
+
+mod arena;
+mod test;
+mod transposition_table;
+
+const ROOT: usize = 0;
+
+/// The search tree. It's shared by all worker threads: each of them
+/// descends the tree, expands the leaf and records the result of the
+/// playout on its own. All methods used during the search therefore
+/// only need a shared reference.
+pub struct Tree {
+    arena: Arena,
+    config: Arc<Config>,
+    // The pass-alive areas of the root position (see
+    // `Tree::expand()`), indexed like the points of the board.
+    pass_alive: Vec<Color>,
+    table: Option<Arc<TranspositionTable>>,
+}
+
+impl Tree {
+
+    /// An empty tree that needs to be replaced by `root()` before
+    /// searching.
+    pub fn new(config: Arc<Config>) -> Tree {
+        Self::with_root(Node::new(NoMove, &config), config, None, vec!())
+    }
+
+    pub fn root(game: &Game, color: Color, config: Arc<Config>) -> Tree {
+        let table = TranspositionTable::new(&config).map(Arc::new);
+        let tree = Self::with_root(Node::root(color), config, table, Self::pass_alive(&game.board()));
+        tree.expand_root(game);
+        tree
+    }
+
+    fn with_root(root: Node, config: Arc<Config>, table: Option<Arc<TranspositionTable>>, pass_alive: Vec<Color>) -> Tree {
+        let arena = Arena::new(Self::max_nodes(&config));
+        arena.allocate(vec!(root));
+        Tree {
+            arena: arena,
+            config: config,
+            pass_alive: pass_alive,
+            table: table,
+        }
+    }
+
+    fn max_nodes(config: &Config) -> usize {
+        let by_memory = config.tree.max_memory_mb * 1024 * 1024 / mem::size_of::<Node>();
+        match (config.tree.max_nodes, by_memory) {
+            (0, by_memory) => by_memory,
+            (max_nodes, 0) => max_nodes,
+            (max_nodes, by_memory) => max_nodes.min(by_memory)
+        }
+    }
+
+    fn expand_root(&self, game: &Game) {
+        if !game.is_over() {
+            let children = game.legal_moves_without_eyes()
+                .iter()
+                .filter(|m| self.outside_of_pass_alive_areas(m, game.size()))
+                .map(|&m| Node::new(m, &self.config))
+                .collect();
+            self.add_children(self.root_node(), children);
+        }
+    }
+
+    /// Returns a new tree for the game, which needs to be the game
+    /// of the current root plus one move. The subtree of that move
+    /// is copied over, so that the rest of the old tree can be freed.
+    /// The transposition table is kept as well. If the subtree
+    /// doesn't fit into the new tree the deepest nodes are pruned.
+    pub fn find_new_root(&self, game: &Game, color: Color) -> Tree {
+        let child = self.children(self.root_node())
+            .iter()
+            .position(|c| c.m() == game.last_move());
+        let tree = Self::with_root(Node::root(color), self.config.clone(), self.table.clone(), Self::pass_alive(&game.board()));
+        if let Some(offset) = child {
+            let (first, _) = self.root_node().children().unwrap();
+            self.copy_subtree(first + offset, &tree, game);
+        }
+        // We don't currently include pass moves in the tree, so
+        // we need to handle the case where the opponent plays a
+        // pass move separately. This branch also handles the
+        // first move where we don't have a tree, yet.
+        if tree.has_no_children() {
+            Self::root(game, color, self.config.clone())
+        } else {
+            tree
+        }
+    }
+
+    // Copies the node at `from` (without its statistics) and all its
+    // descendants into the root of `tree`. The children of the new
+    // root that are illegal in the game (e.g. because of super ko)
+    // are skipped. The copy is breadth first, so that the nodes
+    // closest to the root are kept when `tree` runs full.
+    fn copy_subtree(&self, from: usize, tree: &Tree, game: &Game) {
+        let mut queue = VecDeque::new();
+        queue.push_back((from, ROOT));
+        while let Some((old, new)) = queue.pop_front() {
+            let children: Vec<usize> = match self.arena.get(old).children() {
+                Some((first, count)) => (first..first+count)
+                    .filter(|&i| new != ROOT || game.play(self.arena.get(i).m()).is_ok())
+                    .collect(),
+                None => vec!()
+            };
+            let copies = children.iter().map(|&i| self.arena.get(i).copy()).collect();
+            if let Some(first) = tree.add_children(tree.arena.get(new), copies) {
+                for (offset, &child) in children.iter().enumerate() {
+                    queue.push_back((child, first + offset));
+                }
+            }
+        }
+    }
+
+    /// Changes the color of the root, but keeps the children.
+    pub fn make_root(&mut self, color: Color) {
+        let root = Node::root(color);
+        if let Some((first, count)) = self.root_node().children() {
+            root.set_children(first, count);
+        }
+        self.arena.replace(ROOT, root);
+    }
+
+    fn add_children(&self, parent: &Node, children: Vec<Node>) -> Option<usize> {
+        let count = children.len();
+        if count == 0 {
+            return None;
+        }
+        let first = self.arena.allocate(children);
+        if let Some(first) = first {
+            parent.set_children(first, count);
+        }
+        first
+    }
+
+    /// Descends the tree to a leaf, marking the plays on the way
+    /// (see `Node::record_play()`), and expands it. Returns the
+    /// arena indexes of the nodes on the path and the board at the
+    /// leaf.
+    pub fn find_leaf_and_expand(&self, game: &Game, matcher: Arc<Matcher>) -> (Vec<usize>, Board) {
+        let mut board = game.board();
+        let mut hash = game.hash();
+        let mut path = vec!(ROOT);
+        let mut node = self.root_node();
+        node.record_play();
+        while let Some((first, count)) = node.children() {
+            let index = first + self.next_child_index(node, first, count);
+            node = self.arena.get(index);
+            node.record_play();
+            board.play_legal_move(node.m());
+            if let Some(ref table) = self.table {
+                hash = game.hash_after(hash, &node.m(), &board);
+                let key = TranspositionTable::key(hash, board.next_player());
+                node.set_key(key);
+                table.record_play(key);
+            }
+            path.push(index);
+        }
+        if board.is_game_over() {
+            let is_win = board.winner() == node.color();
+            node.mark_as_terminal(is_win);
+        } else if node.playouts() >= self.config.tree.expand_after && !self.is_full() && node.claim_expansion() {
+            self.expand(node, &board, matcher);
+        }
+        (path, board)
+    }
+
+    fn expand(&self, node: &Node, board: &Board, matcher: Arc<Matcher>) {
+        let mut children = board.legal_moves_without_eyes()
+            .iter()
+            .filter(|m| self.outside_of_pass_alive_areas(m, board.size()))
+            .map(|m| Node::new_leaf(board, m, matcher.clone(), &self.config))
+            .collect();
+        Node::priors(&mut children, board, &self.config);
+        self.add_children(node, children);
+    }
+
+    // Moves inside of pass-alive territory (of either color) can't
+    // change the outcome of the game. As neither color plays there
+    // the areas of the root stay pass-alive in the whole tree, so
+    // they only need to be computed once (the areas that become
+    // pass-alive deeper in the tree aren't pruned).
+    fn outside_of_pass_alive_areas(&self, m: &Move, size: u8) -> bool {
+        let index = m.coord().to_index(size);
+        self.pass_alive.get(index).map_or(true, |&color| color == Empty)
+    }
+
+    // The pass-alive areas of the board (see `Board::pass_alive`).
+    // Under the rulesets that don't remove dead stones at the end of
+    // the game the areas with dead stones in them are left out, as
+    // their owner has to capture the stones to get the points.
+    fn pass_alive(board: &Board) -> Vec<Color> {
+        let mut owner = board.pass_alive();
+        if board.ruleset().removes_dead_stones() {
+            return owner;
+        }
+        let size = board.size();
+        for coord in Coord::for_board_size(size) {
+            let color = owner[coord.to_index(size)];
+            if color == Empty || board.color(&coord) != color.opposite() {
+                continue;
+            }
+            let mut area = vec!(coord);
+            owner[coord.to_index(size)] = Empty;
+            while let Some(current) = area.pop() {
+                for &n in board.neighbours(current).iter() {
+                    if owner[n.to_index(size)] == color && board.color(&n) != color {
+                        owner[n.to_index(size)] = Empty;
+                        area.push(n);
+                    }
+                }
+            }
+        }
+        owner
+    }
+
+    fn next_child_index(&self, node: &Node, first: usize, count: usize) -> usize {
+        let plays = node.plays_with_prior_factor(&self.config);
+        let mut best = 0;
+        let mut best_value = self.child_value(self.arena.get(first), plays);
+        for i in 1..count {
+            let value = self.child_value(self.arena.get(first + i), plays);
+            if value > best_value {
+                best = i;
+                best_value = value;
+            }
+        }
+        best
+    }
+
+    fn child_value(&self, child: &Node, parent_plays: f32) -> f32 {
+        let transposed = self.table.as_ref().and_then(|table| table.get(child.key()));
+        child.child_value(&self.config, parent_plays, transposed)
+    }
+
+    pub fn record_on_path(&self, path: &[usize], playout_result: &PlayoutResult) {
+        let winner = playout_result.winner();
+        let amaf = playout_result.amaf();
+        for &index in path {
+            let node = self.arena.get(index);
+            if node.color() == winner {
+                node.record_win(&self.config, playout_result.score());
+                if let Some(ref table) = self.table {
+                    table.record_win(node.key(), Node::weighted_win(&self.config, playout_result.score()));
+                }
+            }
+            // We need to switch the color as we see things from the
+            // opponent's point of view now.
+            let color = node.color().opposite();
+            let (first, count) = node.children().unwrap_or((0, 0));
+            for child in (first..first+count).map(|i| self.arena.get(i)) {
+                if !child.m().is_pass() {
+                    match amaf.get(&child.m().coord()) {
+                        Some(&c) if c == color => {
+                            child.record_amaf_play();
+                            if color == winner {
+                                child.record_amaf_win(&self.config, playout_result.score());
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn root_node(&self) -> &Node {
+        self.arena.get(ROOT)
+    }
+
+    pub fn children(&self, node: &Node) -> Vec<&Node> {
+        match node.children() {
+            Some((first, count)) => (first..first+count).map(|i| self.arena.get(i)).collect(),
+            None => vec!()
+        }
+    }
+
+    /// The best move at the root.
+    pub fn best(&self) -> &Node {
+        self.best_child(self.root_node())
+    }
+
+    fn best_child(&self, node: &Node) -> &Node {
+        let children = self.children(node);
+        let mut best = children[0];
+        for &n in children.iter() {
+            if n.plays_with_prior_factor(&self.config) > best.plays_with_prior_factor(&self.config) {
+                best = n;
+            }
+        }
+        best
+    }
+
+    /// The children of the root that were simulated at least once,
+    /// ordered by the number of simulations (best first).
+    pub fn candidates(&self) -> Vec<Candidate> {
+        let children = self.children(self.root_node());
+        let prior_plays = children.iter().fold(0, |acc, c| acc + c.prior_plays());
+        let mut visited: Vec<&Node> = children.into_iter().filter(|c| c.playouts() > 0).collect();
+        let config = &self.config;
+        visited.sort_by(|a, b| b.plays_with_prior_factor(config).partial_cmp(&a.plays_with_prior_factor(config)).unwrap());
+        visited.iter()
+            .map(|child| Candidate {
+                amaf_visits: child.amaf_plays(),
+                amaf_winrate: child.amaf_win_ratio(),
+                m: child.m(),
+                prior: if prior_plays == 0 { 0.0 } else { child.prior_plays() as f32 / prior_plays as f32 },
+                pv: self.principal_variation(child),
+                visits: child.playouts(),
+                winrate: child.win_ratio(),
+            })
+            .collect()
+    }
+
+    /// The move of the node followed by the best replies as long as
+    /// they were simulated.
+    fn principal_variation(&self, node: &Node) -> Vec<Move> {
+        let mut pv = vec!(node.m());
+        let mut node = node;
+        while !node.is_leaf() && self.best_child(node).playouts() > 0 {
+            node = self.best_child(node);
+            pv.push(node.m());
+        }
+        pv
+    }
+
+    pub fn has_no_children(&self) -> bool {
+        self.root_node().is_leaf()
+    }
+
+    pub fn color(&self) -> Color {
+        self.root_node().color()
+    }
+
+    pub fn playouts(&self) -> usize {
+        self.root_node().playouts()
+    }
+
+    pub fn win_ratio(&self) -> f32 {
+        self.root_node().win_ratio()
+    }
+
+    /// The number of nodes in the tree (without the root).
+    pub fn descendants(&self) -> usize {
+        self.arena.len() - 1
+    }
+
+    /// True if the tree reached the configured maximum size and the
+    /// leaves aren't expanded anymore.
+    pub fn is_full(&self) -> bool {
+        self.arena.is_full()
+    }
+
+    /// The memory (in bytes) used by the nodes of the tree.
+    pub fn memory(&self) -> usize {
+        self.arena.memory()
+    }
+
+}
@@ -0,0 +1,537 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+pub use board::Black;
+pub use board::Board;
+pub use board::Coord;
+pub use board::Empty;
+pub use board::Pass;
+pub use board::Play;
+pub use board::White;
+pub use config::Config;
+pub use config::Replacement;
+pub use game::Game;
+pub use patterns::Matcher;
+pub use playout::Playout;
+pub use playout::PlayoutResult;
+pub use ruleset::CGOS;
+pub use ruleset::KgsChinese;
+pub use ruleset::Ruleset;
+pub use sgf::Parser;
+use super::ROOT;
+pub use super::Tree;
+pub use super::transposition_table::TranspositionTable;
+
+pub use rand::weak_rng;
+pub use std::collections::HashMap;
+pub use std::path::Path;
+pub use std::sync::Arc;
+pub use test::Bencher;
+
+pub fn config() -> Arc<Config> {
+    let mut config = Config::test_config();
+    config.tree.expand_after = 0;
+    Arc::new(config)
+}
+
+fn matcher() -> Arc<Matcher> {
+    Arc::new(Matcher::new())
+}
+
+fn expand_after(expand_after: usize) -> Arc<Config> {
+    let mut config = Arc::try_unwrap(config()).unwrap();
+    config.tree.expand_after = expand_after;
+    Arc::new(config)
+}
+
+fn root_children(tree: &Tree) -> usize {
+    tree.children(tree.root_node()).len()
+}
+
+#[test]
+fn root_expands_the_children() {
+    let game = Game::new(2, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    assert_eq!(4, root_children(&tree));
+    assert_eq!(4, tree.descendants());
+}
+
+#[test]
+fn root_doesnt_add_pass() {
+    let game = Game::new(5, 6.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    assert_eq!(25, root_children(&tree));
+    assert!(tree.children(tree.root_node()).iter().all(|n| !n.m().is_pass()));
+}
+
+#[test]
+fn root_doesnt_add_children_when_the_game_is_over() {
+    let mut game = Game::new(5, 6.5, KgsChinese);
+    game = game.play(Pass(Black)).unwrap();
+    game = game.play(Pass(White)).unwrap();
+    let tree = Tree::root(&game, Black, config());
+    assert!(tree.has_no_children());
+}
+
+#[test]
+fn no_super_ko_violations_in_the_children_of_the_root() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/positional-superko.sgf")).unwrap();
+    let game = parser.game().unwrap();
+    let tree = Tree::root(&game, White, config());
+    // Play(White, 2, 9) is a super ko violation
+    assert!(tree.children(tree.root_node()).iter().all(|n| n.m() != Play(White, 2, 9)));
+}
+
+// A 5x5 board where both colors are pass-alive and a dead white
+// stone at A5 sits in the area of black.
+fn dead_stone_in_pass_alive_area(ruleset: Ruleset) -> Game {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/safety/dead-stone.sgf")).unwrap();
+    let board = parser.game().unwrap().board();
+    let mut game = Game::new(board.size(), board.komi(), ruleset);
+    for coord in Coord::for_board_size(board.size()) {
+        let color = board.color(&coord);
+        if color != Empty {
+            game.set_next_player(color);
+            game = game.play(Play(color, coord.col, coord.row)).unwrap();
+        }
+    }
+    game.set_next_player(Black);
+    game
+}
+
+#[test]
+fn root_prunes_the_moves_in_pass_alive_areas() {
+    let game = dead_stone_in_pass_alive_area(KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    assert!(tree.has_no_children());
+}
+
+#[test]
+fn root_keeps_the_moves_capturing_dead_stones_if_they_arent_removed() {
+    let game = dead_stone_in_pass_alive_area(CGOS);
+    let tree = Tree::root(&game, Black, config());
+    let a4 = Coord::from_gtp("A4");
+    let moves: Vec<_> = tree.children(tree.root_node()).iter().map(|n| n.m()).collect();
+    assert_eq!(vec!(Play(Black, a4.col, a4.row)), moves);
+}
+
+// find_leaf_and_expand()
+#[test]
+fn find_leaf_and_expand_expands_the_leaves() {
+    let game = Game::new(2, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    for _ in 0..4 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    let children = tree.children(tree.root_node());
+    assert_eq!(4, children.len());
+    assert!(children.iter().all(|&n| tree.children(n).len() == 3));
+    assert!(children.iter().all(|&n| tree.children(n).iter().all(|c| !c.m().is_pass())));
+    assert_eq!(16, tree.descendants());
+}
+
+#[test]
+fn find_leaf_and_expand_doesnt_expand_if_threshold_not_met() {
+    let game = Game::new(2, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, expand_after(2));
+    tree.find_leaf_and_expand(&game, matcher());
+    assert_eq!(4, tree.descendants());
+}
+
+#[test]
+fn find_leaf_and_expand_expands_once_the_threshold_is_met() {
+    let game = Game::new(2, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, expand_after(2));
+    let matcher = matcher();
+    for _ in 0..4 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    assert_eq!(4, tree.descendants());
+    tree.find_leaf_and_expand(&game, matcher.clone());
+    assert_eq!(7, tree.descendants());
+}
+
+#[test]
+fn find_leaf_and_expand_sets_play_on_the_path() {
+    let game = Game::new(2, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let (path, _) = tree.find_leaf_and_expand(&game, matcher());
+    assert_eq!(2, path.len());
+    assert_eq!(ROOT, path[0]);
+    assert_eq!(1, tree.playouts());
+    assert_eq!(1, tree.arena.get(path[1]).playouts());
+}
+
+#[test]
+fn find_leaf_and_expand_returns_the_board_at_the_leaf() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let (path, board) = tree.find_leaf_and_expand(&game, matcher());
+    let m = tree.arena.get(path[1]).m();
+    assert_eq!(Black, board.color(&m.coord()));
+    assert_eq!(8, board.vacant_point_count());
+}
+
+describe! record_on_path {
+
+    before_each {
+        let mut c = Config::test_config();
+        c.tree.score_weight = 0.0;
+        c.tree.expand_after = 0;
+        let config = Arc::new(c);
+        let game = Game::new(2, 0.5, KgsChinese);
+        let tree = Tree::root(&game, Black, config.clone());
+        let matcher = Arc::new(Matcher::new());
+        tree.find_leaf_and_expand(&game, matcher.clone());
+        let (path, _) = tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+
+    it "only records wins for the correct color" {
+        let mut board = Board::new(9, 6.5, KgsChinese);
+        board.play(Play(Black, 1, 1)).unwrap();
+        let score = board.score();
+        let playout_result = PlayoutResult::new(score, HashMap::new());
+        tree.record_on_path(&path, &playout_result);
+        assert_eq!(Black, playout_result.winner());
+        // The root is a Pass(Black) node and the child a black move
+        assert!(tree.win_ratio() > 0.0);
+        assert!(tree.arena.get(path[1]).win_ratio() > 0.0);
+    }
+
+    it "doesn't record wins for the other color" {
+        let board = Board::new(9, 6.5, KgsChinese);
+        let score = board.score();
+        let playout_result = PlayoutResult::new(score, HashMap::new());
+        tree.record_on_path(&path, &playout_result);
+        assert_eq!(White, playout_result.winner());
+        assert_eq!(0.0, tree.win_ratio());
+        assert_eq!(0.0, tree.arena.get(path[1]).win_ratio());
+    }
+}
+
+// find_new_root()
+#[test]
+fn find_new_root_keeps_the_subtree_of_the_move() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    for _ in 0..50 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    let best = tree.best();
+    let best_descendants = tree.children(best).len();
+    let new_game = game.play(best.m()).unwrap();
+    let new_tree = tree.find_new_root(&new_game, White);
+    assert_eq!(White, new_tree.color());
+    assert_eq!(best_descendants, root_children(&new_tree));
+    assert!(new_tree.descendants() < tree.descendants());
+    assert_eq!(0, new_tree.playouts());
+}
+
+#[test]
+fn find_new_root_starts_from_scratch_for_unknown_moves() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let new_game = game.play(Pass(Black)).unwrap();
+    let new_tree = tree.find_new_root(&new_game, White);
+    assert_eq!(9, root_children(&new_tree));
+}
+
+#[test]
+fn find_new_root_removes_superko_violations() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/positional-superko.sgf")).unwrap();
+    let game = parser.game().unwrap();
+    let previous = game.undo().unwrap();
+    let tree = Tree::root(&previous, *game.last_move().color(), config());
+    let matcher = matcher();
+    for _ in 0..400 {
+        tree.find_leaf_and_expand(&previous, matcher.clone());
+    }
+    let new_tree = tree.find_new_root(&game, White);
+    // Play(White, 2, 9) is a super ko violation
+    assert!(new_tree.children(new_tree.root_node()).iter().all(|n| n.m() != Play(White, 2, 9)));
+}
+
+#[test]
+fn make_root_keeps_the_children() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let mut tree = Tree::root(&game, Black, config());
+    tree.make_root(White);
+    assert_eq!(White, tree.color());
+    assert_eq!(9, root_children(&tree));
+}
+
+#[test]
+fn candidates_are_ordered_by_simulations() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    let playout = Playout::new(config(), matcher.clone());
+    let mut rng = weak_rng();
+    for _ in 0..100 {
+        let (path, mut board) = tree.find_leaf_and_expand(&game, matcher.clone());
+        let playout_result = playout.run(&mut board, None, &mut rng);
+        tree.record_on_path(&path, &playout_result);
+    }
+    let candidates = tree.candidates();
+    assert!(candidates.len() > 0);
+    assert_eq!(tree.best().m(), candidates[0].m);
+    assert_eq!(candidates[0].m, candidates[0].pv[0]);
+    assert!(candidates.windows(2).all(|w| w[0].visits >= w[1].visits));
+}
+
+#[test]
+fn the_tree_can_be_searched_from_several_threads() {
+    let game = Game::new(5, 6.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    let guards: Vec<_> = (0..4).map(|_| {
+        let tree = &tree;
+        let game = &game;
+        let matcher = matcher.clone();
+        unsafe { ::thread_scoped::scoped(move || {
+            for _ in 0..250 {
+                tree.find_leaf_and_expand(game, matcher.clone());
+            }
+        })}
+    }).collect();
+    for guard in guards {
+        guard.join();
+    }
+    assert_eq!(1000, tree.playouts());
+    let plays = tree.children(tree.root_node()).iter().fold(0, |acc, n| acc + n.playouts());
+    assert_eq!(1000, plays);
+}
+
+// max_nodes / max_memory_mb
+fn max_nodes(max_nodes: usize) -> Arc<Config> {
+    let mut config = Arc::try_unwrap(config()).unwrap();
+    config.tree.max_nodes = max_nodes;
+    Arc::new(config)
+}
+
+#[test]
+fn the_tree_stops_expanding_at_max_nodes() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, max_nodes(10));
+    let matcher = matcher();
+    for _ in 0..50 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    assert!(tree.is_full());
+    assert_eq!(9, tree.descendants());
+    assert_eq!(50, tree.playouts());
+}
+
+#[test]
+fn the_smaller_limit_applies() {
+    let mut config = Config::test_config();
+    config.tree.max_nodes = 0;
+    config.tree.max_memory_mb = 1;
+    let by_memory = Tree::max_nodes(&config);
+    assert!(by_memory > 0);
+    config.tree.max_nodes = by_memory + 1;
+    assert_eq!(by_memory, Tree::max_nodes(&config));
+    config.tree.max_nodes = by_memory - 1;
+    assert_eq!(by_memory - 1, Tree::max_nodes(&config));
+    config.tree.max_memory_mb = 0;
+    config.tree.max_nodes = 0;
+    assert_eq!(0, Tree::max_nodes(&config));
+}
+
+#[test]
+fn find_new_root_prunes_the_subtree_if_it_doesnt_fit() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let mut tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    for _ in 0..200 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    // The limit could also be lowered between two moves.
+    tree.config = max_nodes(20);
+    let best = tree.best();
+    let new_game = game.play(best.m()).unwrap();
+    let new_tree = tree.find_new_root(&new_game, White);
+    assert!(new_tree.descendants() < 20);
+    assert_eq!(tree.children(best).len(), root_children(&new_tree));
+    assert!(new_tree.is_full());
+}
+
+#[test]
+fn the_memory_of_the_nodes_is_reported() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    assert!(tree.memory() > 0);
+}
+
+// transposition table
+#[test]
+fn transposed_nodes_share_their_statistics() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let matcher = matcher();
+    for _ in 0..300 {
+        tree.find_leaf_and_expand(&game, matcher.clone());
+    }
+    let mut plays = HashMap::new();
+    let mut nodes = HashMap::new();
+    let mut stack = tree.children(tree.root_node());
+    while let Some(node) = stack.pop() {
+        if node.playouts() > 0 {
+            *plays.entry(node.key()).or_insert(0) += node.playouts();
+            *nodes.entry(node.key()).or_insert(0) += 1;
+        }
+        stack.extend(tree.children(node));
+    }
+    let table = tree.table.as_ref().unwrap();
+    assert!(nodes.values().any(|&count| count > 1));
+    for (&key, &count) in plays.iter() {
+        assert_eq!(Some(count), table.get(key).map(|(plays, _)| plays));
+    }
+}
+
+#[test]
+fn the_key_of_a_node_is_the_key_of_its_position() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let (path, board) = tree.find_leaf_and_expand(&game, matcher());
+    let node = tree.arena.get(path[1]);
+    let hash = game.play(node.m()).unwrap().hash();
+    assert_eq!(TranspositionTable::key(hash, board.next_player()), node.key());
+}
+
+#[test]
+fn the_transposition_table_can_be_turned_off() {
+    let mut config = Config::test_config();
+    config.tree.transposition_table_mb = 0;
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, Arc::new(config));
+    let (path, _) = tree.find_leaf_and_expand(&game, matcher());
+    assert!(tree.table.is_none());
+    assert_eq!(0, tree.arena.get(path[1]).key());
+}
+
+#[test]
+fn find_new_root_keeps_the_transposition_table() {
+    let game = Game::new(3, 0.5, KgsChinese);
+    let tree = Tree::root(&game, Black, config());
+    let (path, _) = tree.find_leaf_and_expand(&game, matcher());
+    let node = tree.arena.get(path[1]);
+    let new_tree = tree.find_new_root(&game.play(node.m()).unwrap(), White);
+    let table = new_tree.table.as_ref().unwrap();
+    assert_eq!(Some((1, 0)), table.get(node.key()));
+}
+
+describe! transposition_table {
+
+    before_each {
+        let table = TranspositionTable::with_capacity(4, Replacement::Visits);
+    }
+
+    it "rounds the capacity down to a power of two" {
+        let table = TranspositionTable::with_capacity(7, Replacement::Always);
+        table.record_play(4);
+        table.record_play(8);
+        table.record_play(12);
+        assert_eq!(None, table.get(8));
+    }
+
+    it "records plays and wins" {
+        table.record_play(5);
+        table.record_play(5);
+        table.record_win(5, 3);
+        assert_eq!(Some((2, 3)), table.get(5));
+    }
+
+    it "doesn't know positions it hasn't seen" {
+        table.record_play(5);
+        assert_eq!(None, table.get(6));
+        assert_eq!(None, table.get(0));
+    }
+
+    it "ignores wins of positions it hasn't seen" {
+        table.record_win(5, 3);
+        assert_eq!(None, table.get(5));
+    }
+
+    it "uses a different key depending on the player to move" {
+        let key = TranspositionTable::key(42, Black);
+        assert!(key != TranspositionTable::key(42, White));
+    }
+
+    it "never uses the key of an empty entry" {
+        assert!(TranspositionTable::key(0, Black) != 0);
+    }
+
+    it "replaces the entry with fewer plays" {
+        table.record_play(4);
+        table.record_play(4);
+        table.record_play(8);
+        table.record_play(12);
+        assert_eq!(Some((2, 0)), table.get(4));
+        assert_eq!(None, table.get(8));
+        assert_eq!(Some((1, 0)), table.get(12));
+    }
+
+    it "always replaces the second entry" {
+        let table = TranspositionTable::with_capacity(4, Replacement::Always);
+        table.record_play(4);
+        table.record_play(8);
+        table.record_play(8);
+        table.record_play(12);
+        assert_eq!(Some((1, 0)), table.get(4));
+        assert_eq!(None, table.get(8));
+        assert_eq!(Some((1, 0)), table.get(12));
+    }
+
+}
+
+#[bench]
+fn full_uct_cycle_09x09(b: &mut Bencher) {
+    full_uct_cycle(9, b);
+}
+
+#[bench]
+fn full_uct_cycle_13x13(b: &mut Bencher) {
+    full_uct_cycle(13, b);
+}
+
+#[bench]
+fn full_uct_cycle_19x19(b: &mut Bencher) {
+    full_uct_cycle(19, b);
+}
+
+fn full_uct_cycle(size: u8, b: &mut Bencher) {
+    let game = Game::new(size, 6.5, KgsChinese);
+    let matcher = matcher();
+    let config = Arc::new(Config::test_config());
+    let tree = Tree::root(&game, Black, config.clone());
+    let playout = Playout::new(config.clone(), matcher.clone());
+    let mut rng = weak_rng();
+    b.iter(|| {
+        let (path, mut board) = tree.find_leaf_and_expand(&game, matcher.clone());
+        let playout_result = playout.run(&mut board, None, &mut rng);
+        tree.record_on_path(&path, &playout_result);
+    });
+}
@@ -0,0 +1,171 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Color;
+use board::White;
+use config::Config;
+use config::Replacement;
+
+use std::mem;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// The Zobrist hash only encodes the stones on the board, so this is
+/// mixed into the key when white is to move.
+const WHITE_TO_MOVE: u64 = 0x9e3779b97f4a7c15;
+
+const EMPTY: usize = 0;
+
+struct Entry {
+    key: AtomicUsize,
+    plays: AtomicUsize,
+    wins: AtomicUsize,
+}
+
+impl Entry {
+
+    fn new() -> Entry {
+        Entry {
+            key: AtomicUsize::new(EMPTY),
+            plays: AtomicUsize::new(0),
+            wins: AtomicUsize::new(0),
+        }
+    }
+
+    fn key(&self) -> usize {
+        self.key.load(Ordering::Relaxed)
+    }
+
+    fn plays(&self) -> usize {
+        self.plays.load(Ordering::Relaxed)
+    }
+
+}
+
+/// Shares the statistics of the nodes of the tree that represent the
+/// same position with the same player to move. Each key can go into
+/// one of the two entries of its bucket. The entries are updated
+/// without locking, so an entry that gets replaced while another
+/// thread updates it may end up with a play or win of the previous
+/// position. That's harmless for the search.
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    replacement: Replacement,
+}
+
+impl TranspositionTable {
+
+    /// Uses as many entries as fit into the configured memory
+    /// budget. Returns `None` if the table is turned off.
+    pub fn new(config: &Config) -> Option<TranspositionTable> {
+        let bytes = config.tree.transposition_table_mb * 1024 * 1024;
+        let capacity = bytes / mem::size_of::<Entry>();
+        if capacity < 2 {
+            None
+        } else {
+            Some(Self::with_capacity(capacity, config.tree.transposition_replacement))
+        }
+    }
+
+    /// The capacity is rounded down to a power of two (and at least
+    /// one bucket).
+    pub fn with_capacity(capacity: usize, replacement: Replacement) -> TranspositionTable {
+        let mut size = 2;
+        while size * 2 <= capacity {
+            size *= 2;
+        }
+        TranspositionTable {
+            entries: (0..size).map(|_| Entry::new()).collect(),
+            replacement: replacement,
+        }
+    }
+
+    /// The key of the position with the given Zobrist hash and
+    /// player to move. It's never 0 as that marks the empty entries.
+    pub fn key(hash: u64, to_move: Color) -> usize {
+        let hash = if to_move == White { hash ^ WHITE_TO_MOVE } else { hash };
+        match hash as usize {
+            EMPTY => 1,
+            key => key
+        }
+    }
+
+    /// Adds a play to the position, storing it in the table first if
+    /// it isn't in there, yet.
+    pub fn record_play(&self, key: usize) {
+        let entry = match self.find(key) {
+            Some(entry) => entry,
+            None => self.replace(key)
+        };
+        entry.plays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Adds the (scaled) wins to the position, if it's still in the
+    /// table.
+    pub fn record_win(&self, key: usize, wins: usize) {
+        if let Some(entry) = self.find(key) {
+            entry.wins.fetch_add(wins, Ordering::Relaxed);
+        }
+    }
+
+    /// The plays and (scaled) wins of the position.
+    pub fn get(&self, key: usize) -> Option<(usize, usize)> {
+        self.find(key).map(|entry| (entry.plays(), entry.wins.load(Ordering::Relaxed)))
+    }
+
+    fn bucket(&self, key: usize) -> (&Entry, &Entry) {
+        let index = key & (self.entries.len() - 1) & !1;
+        (&self.entries[index], &self.entries[index + 1])
+    }
+
+    fn find(&self, key: usize) -> Option<&Entry> {
+        if key == EMPTY {
+            return None;
+        }
+        let (first, second) = self.bucket(key);
+        if first.key() == key {
+            Some(first)
+        } else if second.key() == key {
+            Some(second)
+        } else {
+            None
+        }
+    }
+
+    fn replace(&self, key: usize) -> &Entry {
+        let (first, second) = self.bucket(key);
+        let entry = if first.key() == EMPTY {
+            first
+        } else if second.key() == EMPTY {
+            second
+        } else {
+            match self.replacement {
+                Replacement::Always => second,
+                Replacement::Visits => if second.plays() <= first.plays() { second } else { first }
+            }
+        };
+        entry.plays.store(0, Ordering::Relaxed);
+        entry.wins.store(0, Ordering::Relaxed);
+        entry.key.store(key, Ordering::Relaxed);
+        entry
+    }
+
+}
@@ -22,7 +22,9 @@
 pub use self::controller::EngineController;
 pub use self::engine_impl::EngineImpl;
 use board::Color;
+use book::Book;
 use board::Move;
+use self::analysis::Candidate;
 use config::Config;
 use game::Game;
 use ownership::OwnershipStatistics;
@@ -30,19 +32,32 @@ use patterns::Matcher;
 use timer::Timer;
 
 use std::sync::Arc;
+use time::Duration;
 
+pub mod analysis;
 mod controller;
+mod dynamic_komi;
 mod engine_impl;
 mod test;
 
-pub fn factory(config: Arc<Config>, matcher: Arc<Matcher>) -> Box<Engine> {
-    Box::new(EngineImpl::new(config, matcher))
+pub fn factory(config: Arc<Config>, matcher: Arc<Matcher>, book: Arc<Book>) -> Box<Engine> {
+    Box::new(EngineImpl::new(config, matcher, book))
 }
 
 pub trait Engine {
 
     fn genmove(&mut self, Color, &Game, &Timer) -> (Move,usize);
+    /// Searches the position without committing to a move. The
+    /// candidates are reported every `interval` until `stop` returns
+    /// true.
+    fn analyze(&mut self, _: Color, _: &Game, _: Duration, _: &mut FnMut(Vec<Candidate>), _: &Fn() -> bool) {}
     fn ownership(&self) -> &OwnershipStatistics;
+    /// Keeps searching the current position (i.e. on the opponent's
+    /// time) until `stop` returns true.
+    fn ponder(&mut self, _: &Game, _: &Fn() -> bool) {}
     fn reset(&mut self, _:u8, _:f32) {}
+    /// Switches to a changed configuration (e.g. through
+    /// `imrscl-set`). Only called between searches.
+    fn set_config(&mut self, _: Arc<Config>) {}
 
 }
@@ -0,0 +1,130 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Coord;
+
+use std::cmp;
+
+mod test;
+
+/// Returns the maximum number of stones that can be placed with
+/// `fixed_handicap` on a board of the given size (as defined by the
+/// GTP specification).
+pub fn max_fixed(size: u8) -> u8 {
+    if size < 7 {
+        0
+    } else if size == 7 || size % 2 == 0 {
+        4
+    } else {
+        9
+    }
+}
+
+/// The standard star point layout for the given number of handicap
+/// stones, in the order the GTP specification defines.
+pub fn fixed(size: u8, stones: u8) -> Result<Vec<Coord>, String> {
+    if stones < 2 || stones > max_fixed(size) {
+        return Err("invalid number of stones".to_string());
+    }
+    let edge = if size >= 13 { 4 } else { 3 };
+    let far = size + 1 - edge;
+    let middle = (size + 1) / 2;
+    let mut coords = vec!(
+        Coord::new(edge, edge),
+        Coord::new(far, far));
+    if stones >= 3 {
+        coords.push(Coord::new(edge, far));
+    }
+    if stones >= 4 {
+        coords.push(Coord::new(far, edge));
+    }
+    if stones >= 6 {
+        coords.push(Coord::new(edge, middle));
+        coords.push(Coord::new(far, middle));
+    }
+    if stones >= 8 {
+        coords.push(Coord::new(middle, edge));
+        coords.push(Coord::new(middle, far));
+    }
+    if stones % 2 == 1 && stones >= 5 {
+        coords.push(Coord::new(middle, middle));
+    }
+    Ok(coords)
+}
+
+/// Chooses the points for `place_free_handicap`. We use the star
+/// points as long as possible and then spread the remaining stones
+/// out as evenly as possible, avoiding the first and second line.
+pub fn free(size: u8, stones: u16) -> Result<Vec<Coord>, String> {
+    let max = size as u16 * size as u16 - 1;
+    if stones < 2 || stones > max {
+        return Err("invalid number of stones".to_string());
+    }
+    let fixed_count = cmp::min(stones, max_fixed(size) as u16) as u8;
+    let mut coords = if fixed_count >= 2 {
+        try!(fixed(size, fixed_count))
+    } else {
+        vec!()
+    };
+    let all = Coord::for_board_size(size);
+    while (coords.len() as u16) < stones {
+        let candidates: Vec<Coord> = all.iter()
+            .filter(|c| !coords.contains(c))
+            .cloned()
+            .collect();
+        let away_from_edge: Vec<Coord> = candidates.iter()
+            .filter(|c| c.distance_to_border(size) >= 2)
+            .cloned()
+            .collect();
+        let candidates = if away_from_edge.len() > 0 { away_from_edge } else { candidates };
+        let mut best: Option<(Coord, usize)> = None;
+        for candidate in candidates {
+            let value = spread(&candidate, &coords, size);
+            if best.map(|(_, v)| value > v).unwrap_or(true) {
+                best = Some((candidate, value));
+            }
+        }
+        match best {
+            Some((coord, _)) => coords.push(coord),
+            None => break
+        }
+    }
+    Ok(coords)
+}
+
+// Higher is better. The distance to the closest stone that's already
+// placed is what counts, the third and fourth line are only used to
+// break ties.
+fn spread(candidate: &Coord, placed: &Vec<Coord>, size: u8) -> usize {
+    let line = candidate.distance_to_border(size);
+    let line_bonus = if line == 2 || line == 3 { 1 } else { 0 };
+    let distance = placed.iter()
+        .map(|c| distance(candidate, c))
+        .min()
+        .unwrap_or(size as usize * 2);
+    2 * distance + line_bonus
+}
+
+fn distance(a: &Coord, b: &Coord) -> usize {
+    let cols = (a.col as isize - b.col as isize).abs();
+    let rows = (a.row as isize - b.row as isize).abs();
+    (cols + rows) as usize
+}
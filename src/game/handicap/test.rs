@@ -0,0 +1,102 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Coord;
+use super::fixed;
+use super::free;
+use super::max_fixed;
+
+fn gtp(coords: Vec<Coord>) -> Vec<String> {
+    coords.iter().map(|c| c.to_gtp()).collect()
+}
+
+#[test]
+fn max_fixed_depends_on_the_board_size() {
+    assert_eq!(0, max_fixed(5));
+    assert_eq!(4, max_fixed(7));
+    assert_eq!(4, max_fixed(10));
+    assert_eq!(9, max_fixed(9));
+    assert_eq!(9, max_fixed(19));
+}
+
+#[test]
+fn fixed_two_stones_on_19x19() {
+    assert_eq!(vec!("D4", "Q16"), gtp(fixed(19, 2).unwrap()));
+}
+
+#[test]
+fn fixed_five_stones_on_19x19_includes_tengen() {
+    assert_eq!(vec!("D4", "Q16", "D16", "Q4", "K10"), gtp(fixed(19, 5).unwrap()));
+}
+
+#[test]
+fn fixed_nine_stones_on_19x19() {
+    let expected = vec!("D4", "Q16", "D16", "Q4", "D10", "Q10", "K4", "K16", "K10");
+    assert_eq!(expected, gtp(fixed(19, 9).unwrap()));
+}
+
+#[test]
+fn fixed_four_stones_on_13x13() {
+    assert_eq!(vec!("D4", "K10", "D10", "K4"), gtp(fixed(13, 4).unwrap()));
+}
+
+#[test]
+fn fixed_six_stones_on_9x9() {
+    assert_eq!(vec!("C3", "G7", "C7", "G3", "C5", "G5"), gtp(fixed(9, 6).unwrap()));
+}
+
+#[test]
+fn fixed_rejects_too_many_stones() {
+    assert!(fixed(19, 10).is_err());
+    assert!(fixed(8, 5).is_err());
+}
+
+#[test]
+fn fixed_rejects_a_single_stone() {
+    assert!(fixed(19, 1).is_err());
+}
+
+#[test]
+fn free_uses_the_star_points_first() {
+    assert_eq!(gtp(fixed(19, 4).unwrap()), gtp(free(19, 4).unwrap()));
+}
+
+#[test]
+fn free_places_more_stones_than_fixed() {
+    let coords = free(9, 12).unwrap();
+    assert_eq!(12, coords.len());
+    let mut deduped = coords.clone();
+    deduped.sort();
+    deduped.dedup();
+    assert_eq!(12, deduped.len());
+}
+
+#[test]
+fn free_works_on_boards_without_star_points() {
+    assert_eq!(3, free(5, 3).unwrap().len());
+}
+
+#[test]
+fn free_rejects_a_full_board() {
+    assert!(free(3, 9).is_err());
+}
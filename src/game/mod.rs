@@ -20,17 +20,22 @@
  *                                                                      *
  ************************************************************************/
 
+use board::Black;
 use board::Board;
 use board::Color;
+use board::Coord;
 use board::IllegalMove;
 use board::Move;
 use board::NoMove;
+use board::Play;
 use ruleset::Ruleset;
 use self::zobrist_hash_table::ZobristHashTable;
 
 use std::fmt;
+use std::sync::Arc;
 use core::fmt::Display;
 
+pub mod handicap;
 mod zobrist_hash_table;
 mod test;
 
@@ -43,9 +48,25 @@ pub trait Info {
 
 #[derive(Clone)]
 pub struct Game {
-    board: Board,
+    board: Arc<Board>,
+    handicap: Arc<Vec<Move>>,
+    hash: u64,
     last_move: Move,
-    zobrist_hash_table: ZobristHashTable,
+    move_count: usize,
+    previous: Option<Arc<Snapshot>>,
+    zobrist_hash_table: Arc<ZobristHashTable>,
+}
+
+// The position before a move, to be able to take it back. The
+// snapshots link back to the start of the game, so they also hold
+// the moves and the super ko history. Games that continue from the
+// same position share all of them.
+struct Snapshot {
+    board: Arc<Board>,
+    hash: u64,
+    last_move: Move,
+    move_count: usize,
+    previous: Option<Arc<Snapshot>>,
 }
 
 impl Game {
@@ -53,46 +74,150 @@ impl Game {
         let new_board = Board::new(size, komi, ruleset);
 
         Game {
-            board: new_board,
+            board: Arc::new(new_board),
+            handicap: Arc::new(vec!()),
+            hash: 0,
             last_move: NoMove,
-            zobrist_hash_table: ZobristHashTable::new(size),
+            move_count: 0,
+            previous: None,
+            zobrist_hash_table: Arc::new(ZobristHashTable::new(size)),
         }
     }
 
-    pub fn with_new_state(board: Board, zobrist_hash_table: ZobristHashTable, last_move: Move) -> Game {
-        Game {
-            board: board,
-            last_move: last_move,
-            zobrist_hash_table: zobrist_hash_table,
-       }
-    }
-
     pub fn play(&self, m: Move) -> Result<Game, IllegalMove> {
-        let mut new_board = self.board.clone();
+        let mut new_board = (*self.board).clone();
 
         match new_board.play(m) {
             Ok(_) => {
-                let mut new_game_state = Game::with_new_state(new_board, self.zobrist_hash_table.clone(), m);
-                if !m.is_pass() && !m.is_resign() {
-                    match new_game_state.check_and_update_super_ko(&m) {
-                        Err(_) => return Err(IllegalMove::SuperKo),
-                        Ok(_) => {}
+                let hash = if m.is_pass() || m.is_resign() {
+                    self.hash
+                } else {
+                    let hash = self.zobrist_hash_table.hash_after(self.hash, &m, &new_board);
+                    if self.is_repetition(hash) {
+                        return Err(IllegalMove::SuperKo);
                     }
-                }
-                Ok(new_game_state)
+                    hash
+                };
+                Ok(Game {
+                    board: Arc::new(new_board),
+                    handicap: self.handicap.clone(),
+                    hash: hash,
+                    last_move: m,
+                    move_count: self.move_count + 1,
+                    previous: Some(Arc::new(self.snapshot())),
+                    zobrist_hash_table: self.zobrist_hash_table.clone(),
+                })
             },
             Err(m) => Err(m)
         }
     }
 
-    fn check_and_update_super_ko(&mut self, m: &Move) -> Result<(),()>{
-        self.zobrist_hash_table.check_and_update_super_ko(m, &self.board)
+    /// Places the handicap stones for black. White is the next
+    /// player after this. The stones are recorded as the first
+    /// moves of the game, but they can't be taken back with `undo()`.
+    pub fn place_handicap(&self, coords: &[Coord]) -> Result<Game, IllegalMove> {
+        let mut board = (*self.board).clone();
+        try!(board.place_handicap(coords));
+        let mut handicap = (*self.handicap).clone();
+        handicap.extend(coords.iter().map(|c| Play(Black, c.col, c.row)));
+        let last_move = match handicap.last() {
+            Some(&m) => m,
+            None => self.last_move
+        };
+        Ok(Game {
+            board: Arc::new(board),
+            handicap: Arc::new(handicap),
+            hash: self.zobrist_hash_table.place_stones(self.hash, Black, coords),
+            last_move: last_move,
+            move_count: self.move_count + coords.len(),
+            previous: self.previous.clone(),
+            zobrist_hash_table: self.zobrist_hash_table.clone(),
+        })
+    }
+
+    /// Returns the game as it was before the last move. The super ko
+    /// history is restored along with the board.
+    pub fn undo(&self) -> Option<Game> {
+        self.previous.as_ref().map(|snapshot| {
+            Game {
+                board: snapshot.board.clone(),
+                handicap: self.handicap.clone(),
+                hash: snapshot.hash,
+                last_move: snapshot.last_move,
+                move_count: snapshot.move_count,
+                previous: snapshot.previous.clone(),
+                zobrist_hash_table: self.zobrist_hash_table.clone(),
+            }
+        })
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            hash: self.hash,
+            last_move: self.last_move,
+            move_count: self.move_count,
+            previous: self.previous.clone(),
+        }
+    }
+
+    // Whether the position with the given hash occurred earlier in
+    // the game (or is the current one).
+    fn is_repetition(&self, hash: u64) -> bool {
+        if self.hash == hash {
+            return true;
+        }
+        let mut previous = self.previous.as_ref();
+        while let Some(snapshot) = previous {
+            if snapshot.hash == hash {
+                return true;
+            }
+            previous = snapshot.previous.as_ref();
+        }
+        false
+    }
+
+    /// The Zobrist hash of the current position.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The Zobrist hash of the position with the given hash after
+    /// playing `m`. `board` is the board after the move was played.
+    /// Used to hash the positions in the search tree.
+    pub fn hash_after(&self, hash: u64, m: &Move, board: &Board) -> u64 {
+        if m.is_pass() {
+            hash
+        } else {
+            self.zobrist_hash_table.hash_after(hash, m, board)
+        }
     }
 
     pub fn last_move(&self) -> Move {
         self.last_move
     }
 
+    /// All the moves played so far, including the handicap stones.
+    pub fn moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(self.move_count);
+        let mut last_move = self.last_move;
+        let mut previous = self.previous.as_ref();
+        while let Some(snapshot) = previous {
+            moves.push(last_move);
+            last_move = snapshot.last_move;
+            previous = snapshot.previous.as_ref();
+        }
+        moves.extend(self.handicap.iter().rev());
+        moves.reverse();
+        moves
+    }
+
+    /// The number of moves played so far, including the handicap
+    /// stones.
+    pub fn move_count(&self) -> usize {
+        self.move_count
+    }
+
     pub fn is_over(&self) -> bool {
         self.board.is_game_over()
     }
@@ -101,6 +226,18 @@ impl Game {
         self.board.komi()
     }
 
+    pub fn adjusted_komi(&self) -> f32 {
+        self.board.adjusted_komi()
+    }
+
+    pub fn handicap(&self) -> u16 {
+        self.board.handicap()
+    }
+
+    pub fn ruleset(&self) -> Ruleset {
+        self.board.ruleset()
+    }
+
     pub fn size(&self) -> u8 {
         self.board.size()
     }
@@ -110,20 +247,34 @@ impl Game {
     }
 
     pub fn set_komi(&mut self, komi: f32) {
-        self.board.set_komi(komi);
+        Arc::make_mut(&mut self.board).set_komi(komi);
+    }
+
+    pub fn set_next_player(&mut self, color: Color) {
+        Arc::make_mut(&mut self.board).set_next_player(color);
     }
 
     pub fn board(&self) -> Board {
-        self.board.clone()
+        (*self.board).clone()
     }
 
+    /// The legal moves (including the super ko check) that don't
+    /// fill one of the player's own eyes.
     pub fn legal_moves_without_eyes(&self) -> Vec<Move> {
         self.board
             .legal_moves_without_eyes()
             .into_iter()
-            .filter(|&m| self.play(m).is_ok())
+            .filter(|&m| m.is_pass() || !self.is_super_ko(m))
             .collect()
     }
+
+    // Whether playing `m` (which needs to be legal on the board)
+    // would repeat an earlier position.
+    fn is_super_ko(&self, m: Move) -> bool {
+        let mut board = (*self.board).clone();
+        board.play_legal_move(m);
+        self.is_repetition(self.zobrist_hash_table.hash_after(self.hash, &m, &board))
+    }
 }
 
 impl Display for Game {
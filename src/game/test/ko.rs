@@ -83,3 +83,12 @@ fn not_a_super_ko2() {
         Ok(_)  => {}
     }
 }
+
+#[test]
+fn legal_moves_without_eyes_leaves_out_super_ko() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/positional-superko.sgf")).unwrap();
+    let game   = parser.game().unwrap();
+    let moves  = game.legal_moves_without_eyes();
+    assert!(!moves.contains(&Play(White, 2, 9)));
+    assert!(moves.iter().all(|&m| game.play(m).is_ok()));
+}
@@ -23,6 +23,7 @@
 #![cfg(test)]
 
 use board::Black;
+use board::Coord;
 use board::IllegalMove;
 use board::Pass;
 use board::Play;
@@ -61,3 +62,70 @@ fn it_should_handle_resign() {
     let res = g.play(Resign(Black));
     assert!(res.is_ok());
 }
+
+#[test]
+fn undo_returns_the_game_before_the_last_move() {
+    let g = Game::new(9, 6.5, KgsChinese);
+    let played = g.play(Play(Black, 3, 3)).unwrap();
+    let undone = played.undo().unwrap();
+    assert_eq!(undone.moves().len(), 0);
+    assert_eq!(undone.hash(), g.hash());
+    assert_eq!(undone.board().vacant_point_count(), 81);
+}
+
+#[test]
+fn undo_can_take_back_several_moves() {
+    let g = Game::new(9, 6.5, KgsChinese);
+    let one = g.play(Play(Black, 3, 3)).unwrap();
+    let two = one.play(Pass(White)).unwrap();
+    let three = two.play(Play(Black, 4, 4)).unwrap();
+    let undone = three.undo().unwrap().undo().unwrap();
+    assert_eq!(undone.moves(), one.moves());
+    assert_eq!(undone.hash(), one.hash());
+    assert_eq!(undone.board().vacant_point_count(), 80);
+    assert_eq!(undone.undo().unwrap().moves().len(), 0);
+}
+
+#[test]
+fn moves_start_with_the_handicap_stones() {
+    let g = Game::new(9, 6.5, KgsChinese);
+    let handicap = g.place_handicap(&[Coord::new(3, 3), Coord::new(7, 7)]).unwrap();
+    let played = handicap.play(Play(White, 5, 5)).unwrap().play(Pass(Black)).unwrap();
+    let expected = vec!(Play(Black, 3, 3), Play(Black, 7, 7), Play(White, 5, 5), Pass(Black));
+    assert_eq!(played.moves(), expected);
+    assert_eq!(played.move_count(), 4);
+    assert_eq!(played.undo().unwrap().moves(), &expected[..3]);
+}
+
+#[test]
+fn changing_the_komi_does_not_change_the_game_before_the_last_move() {
+    let g = Game::new(9, 6.5, KgsChinese);
+    let mut played = g.play(Play(Black, 3, 3)).unwrap();
+    played.set_komi(0.5);
+    assert_eq!(played.undo().unwrap().komi(), 6.5);
+}
+
+#[test]
+fn undo_is_not_possible_on_a_new_game() {
+    let g = Game::new(9, 6.5, KgsChinese);
+    assert!(g.undo().is_none());
+}
+
+#[test]
+fn undo_restores_the_super_ko_history() {
+    let mut g = Game::new(4, 6.5, KgsChinese);
+    g = g.play(Play(Black, 2, 1)).unwrap();
+    g = g.play(Play(White, 3, 1)).unwrap();
+    g = g.play(Play(Black, 1, 2)).unwrap();
+    g = g.play(Play(White, 4, 2)).unwrap();
+    g = g.play(Play(Black, 2, 3)).unwrap();
+    g = g.play(Play(White, 3, 3)).unwrap();
+    g = g.play(Play(Black, 3, 2)).unwrap();
+    let before_capture = g.clone();
+    g = g.play(Play(White, 2, 2)).unwrap();
+    assert!(g.play(Play(Black, 3, 2)).is_err());
+
+    let undone = g.undo().unwrap();
+    assert_eq!(undone.hash(), before_capture.hash());
+    assert!(undone.play(Play(White, 2, 2)).is_ok());
+}
@@ -22,6 +22,7 @@
 
 use board::Black;
 use board::Board;
+use board::Color;
 use board::Coord;
 use board::Empty;
 use board::Move;
@@ -30,10 +31,8 @@ use board::White;
 
 use rand::random;
 
-#[derive(Clone)]
 pub struct ZobristHashTable {
     black: Vec<u64>,
-    hashes: Vec<u64>,
     size: u8,
     white: Vec<u64>,
 }
@@ -49,25 +48,22 @@ impl ZobristHashTable {
         }
         ZobristHashTable {
             black: black,
-            hashes: vec!(0),
             size: size,
             white: white,
         }
     }
 
-    pub fn check_and_update_super_ko(&mut self, m: &Move, b: &Board) -> Result<(),()> {
-        let hash = self.compute_hash(m, b);
-        if self.hashes.contains(&hash) {
-            Err(())
-        } else {
-            self.hashes.push(hash);
-            Ok(())
-        }
+    /// The hash of the position with the given hash after placing
+    /// the given stones on the board in one go (e.g. handicap stones).
+    pub fn place_stones(&self, hash: u64, color: Color, coords: &[Coord]) -> u64 {
+        coords.iter().fold(hash, |hash, coord| self.change_hash(hash, &Play(color, coord.col, coord.row)))
     }
 
-    fn compute_hash(&self, m: &Move, b: &Board) -> u64 {
-        let mut hash = self.hashes[self.hashes.len()-1];
-        hash = self.change_hash(hash, m);
+    /// Computes the hash of the position after `m` was played on
+    /// the position with the given hash. The board needs to be the
+    /// one after playing the move, as captures are taken into account.
+    pub fn hash_after(&self, hash: u64, m: &Move, b: &Board) -> u64 {
+        let mut hash = self.change_hash(hash, m);
         for coord in b.adv_stones_removed() {
             hash = self.change_hash(hash, &Play(m.color().opposite(), coord.col, coord.row));
         }
@@ -24,31 +24,80 @@ use config::Config;
 use engine::Engine;
 use super::GTPInterpreter;
 
+use std::io::prelude::*;
 use std::io::stdin;
+use std::io::stdout;
 use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::channel;
+use std::thread;
 
 pub struct Driver;
 
 impl Driver {
     pub fn new(config: Arc<Config>, engine: Box<Engine>) {
         let mut interpreter = GTPInterpreter::new(config, engine);
-        let reader = stdin();
-        let mut command = String::new();
+        // The number of commands that were read, but not yet
+        // executed. A running analysis (or pondering) stops as soon
+        // as there is one.
+        let pending = Arc::new(AtomicUsize::new(0));
+        let commands = Self::read_commands(pending.clone());
+        let stop = || pending.load(Ordering::SeqCst) > 0;
         let regex = regex!(r"^quit");
         loop {
-            command.clear();
-            reader.read_line(&mut command).unwrap();
+            let command = match commands.recv() {
+                Ok(command) => command,
+                Err(_) => return
+            };
+            pending.fetch_sub(1, Ordering::SeqCst);
 
             let response = interpreter.read(&*command);
 
             match response {
-                Ok(s)  => print!("= {}\n\n", s),
+                Ok(s) => {
+                    if interpreter.analysis_requested() {
+                        print!("= {}\n", s);
+                        interpreter.analyze(&mut stdout(), &stop);
+                        print!("\n");
+                    } else {
+                        print!("= {}\n\n", s);
+                    }
+                    if interpreter.ponder_requested() {
+                        stdout().flush().unwrap();
+                        interpreter.ponder(&stop);
+                    }
+                },
                 Err(s) => print!("? {}\n\n", s)
             }
+            stdout().flush().unwrap();
             if regex.is_match(&command) {
                 return;
             }
         }
 
     }
+
+    // Reads the commands on a separate thread so that a running
+    // analysis or pondering can be interrupted by the next command.
+    fn read_commands(pending: Arc<AtomicUsize>) -> Receiver<String> {
+        let (send_command, receive_command) = channel();
+        thread::spawn(move || {
+            let reader = stdin();
+            loop {
+                let mut command = String::new();
+                match reader.read_line(&mut command) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        pending.fetch_add(1, Ordering::SeqCst);
+                        if send_command.send(command).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        receive_command
+    }
 }
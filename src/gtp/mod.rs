@@ -20,39 +20,63 @@
  *                                                                      *
  ************************************************************************/
 
+use board::Black;
 use board::Color;
+use board::Coord;
+use board::Empty;
 use board::Move;
+use board::Resign;
+use board::White;
 use config::Config;
 use engine::Engine;
 use engine::EngineController;
+use engine::analysis::Format;
+use engine::analysis::info_line;
 use game::Game;
+use game::Info;
+use game::handicap;
+use life_and_death;
+use ruleset::Japanese;
+use ruleset::KgsChinese;
 use ruleset::Ruleset;
-use sgf::parser::Parser;
+use sgf::Parser;
+use sgf::Writer;
 use timer::Timer;
 use version;
 
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
 use std::path::Path;
 use std::sync::Arc;
+use time::Duration;
 use time::precise_time_ns;
+use toml;
 
 pub mod driver;
 mod test;
 
 pub struct GTPInterpreter<'a> {
+    analysis: Option<(Format, Color, Duration)>,
     byo_stones: i32,
     byo_time: i64,
     commands: Vec<&'a str>,
+    comments: HashMap<usize, String>,
     config: Arc<Config>,
     controller: EngineController<'a>,
+    engine_colors: Vec<Color>,
     game: Game,
     main_time: i64,
+    ponder: bool,
+    ponder_requested: bool,
+    ruleset: Ruleset,
     running: bool,
     timer: Timer,
 }
 
 impl<'a> GTPInterpreter<'a> {
     pub fn new(config: Arc<Config>, engine: Box<Engine>) -> GTPInterpreter<'a> {
-        let controller = EngineController::new(config.clone(), engine);
+        let controller = EngineController::new(engine);
         let komi = 6.5;
         let boardsize = 19;
         let commands = vec![
@@ -60,30 +84,50 @@ impl<'a> GTPInterpreter<'a> {
             "clear_board",
             "final_score",
             "final_status_list",
+            "fixed_handicap",
             "genmove",
+            "gg-undo",
             "gogui-analyze_commands",
+            "imrscl-get",
             "imrscl-ownership",
+            "imrscl-params",
+            "imrscl-ponder",
+            "imrscl-set",
+            "imrscl-solve-ld",
+            "kata-analyze",
+            "kgs-rules",
             "known_command",
             "komi",
             "list_commands",
             "loadsgf",
+            "lz-analyze",
             "name",
+            "place_free_handicap",
             "play",
+            "printsgf",
             "protocol_version",
             "quit",
+            "set_free_handicap",
             "showboard",
             "time_left",
             "time_settings",
+            "undo",
             "version",
             ];
         GTPInterpreter {
+            analysis: None,
             byo_stones: 0,
             byo_time: 0,
             commands: commands,
+            comments: HashMap::new(),
             config: config.clone(),
             controller: controller,
+            engine_colors: vec!(),
             game: Game::new(boardsize, komi, config.ruleset),
             main_time: 5,
+            ponder: config.time_control.ponder,
+            ponder_requested: false,
+            ruleset: config.ruleset,
             running: true,
             timer: Timer::new(config),
         }
@@ -98,14 +142,51 @@ impl<'a> GTPInterpreter<'a> {
     }
 
     pub fn ruleset(&self) -> Ruleset {
-        self.config.ruleset
+        self.ruleset
     }
 
     pub fn boardsize(&self) -> u8 {
         self.game.size()
     }
 
+    /// Whether the last command asked for an analysis. The driver
+    /// then needs to call `analyze()`.
+    pub fn analysis_requested(&self) -> bool {
+        self.analysis.is_some()
+    }
+
+    /// Runs the requested analysis and writes one line of `info`
+    /// entries per reporting interval to the output until `stop`
+    /// returns true.
+    pub fn analyze(&mut self, output: &mut Write, stop: &Fn() -> bool) {
+        if let Some((format, color, interval)) = self.analysis.take() {
+            let mut report = |candidates: Vec<_>| {
+                if candidates.len() > 0 {
+                    // There's nothing sensible to do if the GUI went away.
+                    let _ = write!(output, "{}\n", info_line(format, &candidates));
+                    let _ = output.flush();
+                }
+            };
+            self.controller.analyze(color, &self.game, interval, &mut report, stop);
+        }
+    }
+
+    /// Whether the engine should search on the opponent's time
+    /// now. The driver then needs to call `ponder()`.
+    pub fn ponder_requested(&self) -> bool {
+        self.ponder_requested
+    }
+
+    /// Searches the current position until `stop` returns true.
+    pub fn ponder(&mut self, stop: &Fn() -> bool) {
+        if self.ponder_requested {
+            self.ponder_requested = false;
+            self.controller.ponder(&self.game, stop);
+        }
+    }
+
     pub fn read(&mut self, input: &str) -> Result<String, String> {
+        self.ponder_requested = false;
         let preprocessed = self.preprocess(input);
         if preprocessed.len() == 0 {
             return Err("empty command".to_string())
@@ -120,20 +201,34 @@ impl<'a> GTPInterpreter<'a> {
             "clear_board" => self.execute_clear_board(arguments),
             "final_score" => self.execute_final_score(arguments),
             "final_status_list" => self.execute_final_status_list(arguments),
+            "fixed_handicap" => self.execute_fixed_handicap(arguments),
             "genmove" => self.execute_genmove(arguments),
+            "gg-undo" => self.execute_gg_undo(arguments),
             "gogui-analyze_commands" => self.execute_gogui_analyze_commands(arguments),
+            "imrscl-get" => self.execute_imrscl_get(arguments),
             "imrscl-ownership" => self.execute_imrscl_ownership(arguments),
+            "imrscl-params" => self.execute_imrscl_params(arguments),
+            "imrscl-ponder" => self.execute_imrscl_ponder(arguments),
+            "imrscl-set" => self.execute_imrscl_set(arguments),
+            "imrscl-solve-ld" => self.execute_imrscl_solve_ld(arguments),
+            "kata-analyze" => self.execute_analyze(Format::Kata, arguments),
+            "kgs-rules" => self.execute_kgs_rules(arguments),
             "known_command" => self.execute_known_command(arguments),
             "komi" => self.execute_komi(arguments),
             "list_commands" => self.execute_list_commands(arguments),
             "loadsgf" => self.execute_loadsgf(arguments),
+            "lz-analyze" => self.execute_analyze(Format::Lz, arguments),
             "name" => self.execute_name(arguments),
+            "place_free_handicap" => self.execute_place_free_handicap(arguments),
             "play" => self.execute_play(arguments),
+            "printsgf" => self.execute_printsgf(arguments),
             "protocol_version" => self.execute_protocol_version(arguments),
             "quit" => self.execute_quit(arguments),
+            "set_free_handicap" => self.execute_set_free_handicap(arguments),
             "showboard" => self.execute_showboard(arguments),
             "time_left" => self.execute_time_left(arguments),
             "time_settings" => self.execute_time_settings(arguments),
+            "undo" => self.execute_undo(arguments),
             "version" => self.execute_version(arguments),
             _ => Err("unknown command".to_string())
         }
@@ -168,6 +263,7 @@ impl<'a> GTPInterpreter<'a> {
             Some(comm) => match comm.parse::<u8>() {
                 Ok(size) => {
                     self.game = Game::new(size, self.komi(), self.ruleset());
+                    self.clear_game_record();
                     Ok("".to_string())
                 },
                 Err(e) => Err(format!("{:?}", e))
@@ -180,11 +276,30 @@ impl<'a> GTPInterpreter<'a> {
         let size = self.boardsize();
         let komi = self.komi();
         self.game = Game::new(size, komi, self.ruleset());
+        self.clear_game_record();
         self.timer.setup(self.main_time, self.byo_time, self.byo_stones);
         self.controller.reset(size, komi);
         Ok("".to_string())
     }
 
+    fn execute_kgs_rules(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(comm) => {
+                self.ruleset = match *comm {
+                    "japanese" => Japanese,
+                    "chinese" | "aga" | "new_zealand" => KgsChinese,
+                    _ => return Err("unknown ruleset".to_string())
+                };
+                let size = self.boardsize();
+                let komi = self.komi();
+                self.game = Game::new(size, komi, self.ruleset());
+                self.clear_game_record();
+                Ok("".to_string())
+            },
+            None => Err("missing argument".to_string())
+        }
+    }
+
     fn execute_komi(&mut self, arguments: &[&str]) -> Result<String, String> {
         match arguments.get(0) {
             Some(comm) =>
@@ -210,6 +325,12 @@ impl<'a> GTPInterpreter<'a> {
                     Ok(g) => {
                         self.game = g;
                         self.timer.stop();
+                        let index = self.game.move_count() - 1;
+                        self.comments.insert(index, format!("{} playouts", playouts));
+                        if !self.engine_colors.contains(&color) {
+                            self.engine_colors.push(color);
+                        }
+                        self.ponder_requested = self.ponder && !self.game.is_over();
                         Ok(m.to_gtp())
                     },
                     Err(e) => {
@@ -223,6 +344,206 @@ impl<'a> GTPInterpreter<'a> {
     	}
     }
 
+    fn execute_fixed_handicap(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(comm) => match comm.parse::<u8>() {
+                Ok(stones) => {
+                    let coords = try!(handicap::fixed(self.boardsize(), stones));
+                    self.place_handicap(coords)
+                },
+                Err(_) => Err("invalid number of stones".to_string())
+            },
+            None => Err("missing argument".to_string())
+        }
+    }
+
+    fn execute_place_free_handicap(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(comm) => match comm.parse::<u16>() {
+                Ok(stones) => {
+                    let coords = try!(handicap::free(self.boardsize(), stones));
+                    self.place_handicap(coords)
+                },
+                Err(_) => Err("invalid number of stones".to_string())
+            },
+            None => Err("missing argument".to_string())
+        }
+    }
+
+    fn execute_set_free_handicap(&mut self, arguments: &[&str]) -> Result<String, String> {
+        if arguments.len() < 2 {
+            return Err("missing argument(s)".to_string());
+        }
+        let mut coords = vec!();
+        for vertex in arguments {
+            match Self::parse_vertex(vertex) {
+                Some(coord) if !coords.contains(&coord) => coords.push(coord),
+                _ => return Err("bad vertex list".to_string())
+            }
+        }
+        try!(self.place_handicap(coords));
+        Ok("".to_string())
+    }
+
+    fn place_handicap(&mut self, coords: Vec<Coord>) -> Result<String, String> {
+        let size = self.boardsize() as u16;
+        if self.game.vacant_point_count() != size * size {
+            return Err("board not empty".to_string());
+        }
+        match self.game.place_handicap(&coords) {
+            Ok(g) => {
+                self.game = g;
+                let vertices: Vec<String> = coords.iter().map(|c| c.to_gtp()).collect();
+                Ok(vertices.join(" "))
+            },
+            Err(_) => Err("bad vertex list".to_string())
+        }
+    }
+
+    /// Parses a GTP vertex like `D4`. Doesn't check if it's on the
+    /// board.
+    pub fn parse_vertex(vertex: &str) -> Option<Coord> {
+        let lower = vertex.to_lowercase();
+        let mut chars = lower.chars();
+        match chars.next() {
+            Some(letter) if letter >= 'a' && letter <= 'z' && letter != 'i' => {
+                match chars.as_str().parse::<u8>() {
+                    Ok(row) if row > 0 => Some(Coord::from_gtp(&lower)),
+                    _ => None
+                }
+            },
+            _ => None
+        }
+    }
+
+    /// Parses `[color] [interval]` as well as `interval <n>`. The
+    /// interval is given in centiseconds. All other arguments (like
+    /// `minmoves` of Leela Zero) are ignored.
+    fn execute_analyze(&mut self, format: Format, arguments: &[&str]) -> Result<String, String> {
+        let mut color = self.game.board().next_player();
+        let mut interval = None;
+        let mut index = 0;
+        if let Some(color_arg) = arguments.get(0).and_then(|arg| Self::parse_color(arg)) {
+            color = color_arg;
+            index = 1;
+        }
+        while index < arguments.len() {
+            let value = match arguments[index] {
+                "interval" => {
+                    index += 1;
+                    arguments.get(index).map(|&arg| arg)
+                },
+                other if interval.is_none() && other.parse::<i64>().is_ok() => Some(other),
+                _ => None
+            };
+            if let Some(value) = value {
+                match value.parse::<i64>() {
+                    Ok(centiseconds) if centiseconds >= 0 => interval = Some(centiseconds),
+                    _ => return Err("invalid interval".to_string())
+                }
+            }
+            index += 1;
+        }
+        let interval = Duration::milliseconds(interval.unwrap_or(100) * 10);
+        self.analysis = Some((format, color, interval));
+        Ok("".to_string())
+    }
+
+    fn parse_color(color: &str) -> Option<Color> {
+        match color.to_lowercase().as_ref() {
+            "b" | "black" => Some(Black),
+            "w" | "white" => Some(White),
+            _ => None
+        }
+    }
+
+    fn execute_imrscl_ponder(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(&"on") => self.ponder = true,
+            Some(&"off") => self.ponder = false,
+            Some(_) => return Err("invalid argument".to_string()),
+            None => {}
+        }
+        Ok((if self.ponder { "on" } else { "off" }).to_string())
+    }
+
+    fn execute_imrscl_get(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(name) => match self.config.get(name) {
+                Some(value) => Ok(Self::param_value(&value)),
+                None => Err(format!("unknown parameter {}", name))
+            },
+            None => Err("missing argument".to_string())
+        }
+    }
+
+    fn execute_imrscl_set(&mut self, arguments: &[&str]) -> Result<String, String> {
+        if arguments.len() < 2 {
+            return Err("missing argument(s)".to_string());
+        }
+        let config = try!(self.config.set(arguments[0], &arguments[1..].join(" ")));
+        self.set_config(config);
+        Ok("".to_string())
+    }
+
+    /// Lists the parameters in the format GoGui expects for `param`
+    /// analyze commands. With a parameter and a value it works like
+    /// `imrscl-set`.
+    fn execute_imrscl_params(&mut self, arguments: &[&str]) -> Result<String, String> {
+        if arguments.len() > 0 {
+            return self.execute_imrscl_set(arguments);
+        }
+        let lines: Vec<String> = self.config.params().iter().map(|&(ref name, ref value)| {
+            let kind = match (value, Config::choices(name)) {
+                (&toml::Value::Boolean(_), _) => "bool".to_string(),
+                (_, Some(choices)) => format!("list/{}", choices.join("/")),
+                _ => "string".to_string(),
+            };
+            let value = match value {
+                &toml::Value::Boolean(b) => (if b { "1" } else { "0" }).to_string(),
+                _ => Self::param_value(value),
+            };
+            format!("[{}] {} {}", kind, name, value)
+        }).collect();
+        Ok(lines.join("\n"))
+    }
+
+    // The search only runs while a command is executed so the new
+    // configuration takes effect from the next search on.
+    fn set_config(&mut self, config: Config) {
+        if config.time_control.ponder != self.config.time_control.ponder {
+            self.ponder = config.time_control.ponder;
+        }
+        self.config = Arc::new(config);
+        self.timer.set_config(self.config.clone());
+        self.controller.set_config(self.config.clone());
+    }
+
+    fn param_value(value: &toml::Value) -> String {
+        match value {
+            &toml::Value::String(ref s) => s.clone(),
+            &toml::Value::Integer(i) => format!("{}", i),
+            &toml::Value::Float(f) => format!("{}", f),
+            &toml::Value::Boolean(b) => format!("{}", b),
+            other => format!("{:?}", other),
+        }
+    }
+
+    fn execute_imrscl_solve_ld(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let board = self.game.board();
+        let coord = match arguments.get(0) {
+            Some(vertex) => match Self::parse_vertex(vertex) {
+                Some(coord) if coord.is_inside(board.size()) => coord,
+                _ => return Err("invalid vertex".to_string())
+            },
+            None => return Err("missing argument".to_string())
+        };
+        if board.color(&coord) == Empty {
+            return Err("empty vertex".to_string());
+        }
+        Ok(life_and_death::status(&board, coord).to_string())
+    }
+
     fn execute_imrscl_ownership(&mut self, _: &[&str]) -> Result<String, String> {
         let stats = self.controller.ownership_statistics();
         Ok(stats)
@@ -244,6 +565,81 @@ impl<'a> GTPInterpreter<'a> {
         }
     }
 
+    fn execute_undo(&mut self, _: &[&str]) -> Result<String, String> {
+        self.undo(1)
+    }
+
+    fn execute_gg_undo(&mut self, arguments: &[&str]) -> Result<String, String> {
+        match arguments.get(0) {
+            Some(comm) => match comm.parse::<usize>() {
+                Ok(moves) => self.undo(moves),
+                Err(_) => Err("invalid number of moves".to_string())
+            },
+            None => self.undo(1)
+        }
+    }
+
+    fn undo(&mut self, moves: usize) -> Result<String, String> {
+        let mut game = self.game.clone();
+        for _ in 0..moves {
+            game = match game.undo() {
+                Some(g) => g,
+                None => return Err("cannot undo".to_string())
+            };
+        }
+        self.game = game;
+        let move_count = self.game.move_count();
+        let taken_back: Vec<usize> = self.comments.keys().cloned().filter(|&index| index >= move_count).collect();
+        for index in taken_back {
+            self.comments.remove(&index);
+        }
+        Ok("".to_string())
+    }
+
+    fn execute_printsgf(&mut self, arguments: &[&str]) -> Result<String, String> {
+        let sgf = self.sgf();
+        match arguments.get(0) {
+            Some(filename) => {
+                match File::create(filename).and_then(|mut f| f.write_all(sgf.as_bytes())) {
+                    Ok(_) => Ok("".to_string()),
+                    Err(_) => Err("cannot save file".to_string())
+                }
+            },
+            None => Ok(sgf.trim_right().to_string())
+        }
+    }
+
+    fn sgf(&self) -> String {
+        let mut writer = Writer::new(&self.game);
+        for &color in self.engine_colors.iter() {
+            writer.set_player(color, "Iomrascalai");
+        }
+        for (&index, comment) in self.comments.iter() {
+            writer.set_comment(index, comment);
+        }
+        if self.game.is_over() {
+            let result = match self.game.last_move() {
+                Resign(color) => format!("{}+R", Self::sgf_color(color.opposite())),
+                _ => self.controller.final_score(&self.game)
+            };
+            writer.set_result(&result);
+        }
+        writer.sgf()
+    }
+
+    fn sgf_color(color: Color) -> &'static str {
+        match color {
+            Black => "B",
+            White => "W",
+            _ => ""
+        }
+    }
+
+    fn clear_game_record(&mut self) {
+        self.comments.clear();
+        self.engine_colors.clear();
+    }
+
     fn execute_showboard(&mut self, _: &[&str]) -> Result<String, String> {
         Ok(format!("\n{}", self.game))
     }
@@ -310,7 +706,11 @@ impl<'a> GTPInterpreter<'a> {
                     	let game = parser.game();
                         match game {
                             Ok(g) => {
+                                // The ownership statistics have to
+                                // match the size of the new board.
+                                self.controller.reset(g.size(), g.komi());
                                 self.game = g;
+                                self.clear_game_record();
                                 Ok("".to_string())
                             },
                             Err(_) => Err("cannot load file".to_string())
@@ -326,6 +726,7 @@ impl<'a> GTPInterpreter<'a> {
     fn execute_gogui_analyze_commands(&mut self, _: &[&str]) -> Result<String, String> {
         let analyze_commands = vec![
             "dboard/Ownership/imrscl-ownership",
+            "param/Parameters/imrscl-params",
             "plist/Final Status List Dead/final_status_list dead",
             "plist/Final Status List Alive/final_status_list alive"
                 ];
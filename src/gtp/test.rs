@@ -22,10 +22,12 @@
 
 #![cfg(test)]
 
+pub use book::Book;
 pub use config::Config;
 pub use engine::EngineImpl;
 pub use patterns::Matcher;
 pub use ruleset::CGOS;
+pub use ruleset::Japanese;
 pub use ruleset::KgsChinese;
 pub use super::GTPInterpreter;
 
@@ -42,6 +44,23 @@ pub fn ok(s: &'static str) -> Result<String, String> {
     Ok(s.to_string())
 }
 
+// The win rate of white's best move after a short analysis.
+pub fn best_winrate(interpreter: &mut GTPInterpreter) -> f32 {
+    interpreter.read("kata-analyze w 0\n").unwrap();
+    let calls = ::std::cell::Cell::new(0);
+    let stop = || {
+        calls.set(calls.get() + 1);
+        calls.get() > 100
+    };
+    let mut output = vec!();
+    interpreter.analyze(&mut output, &stop);
+    let output = String::from_utf8(output).unwrap();
+    let last = output.lines().last().unwrap();
+    let tokens: Vec<&str> = last.split_whitespace().collect();
+    let index = tokens.iter().position(|&token| token == "winrate").unwrap();
+    tokens[index + 1].parse().unwrap()
+}
+
 describe! interpreter {
 
     describe! cgos {
@@ -51,7 +70,7 @@ describe! interpreter {
             c.ruleset = CGOS;
             let config = Arc::new(c);
             let matcher = Arc::new(Matcher::new());
-            let engine = Box::new(EngineImpl::new(config.clone(), matcher));
+            let engine = Box::new(EngineImpl::new(config.clone(), matcher, Arc::new(Book::new())));
             let mut interpreter = GTPInterpreter::new(config.clone(), engine);
         }
 
@@ -72,6 +91,13 @@ describe! interpreter {
                 assert_that(response, is(equal_to(err("missing argument"))));
             }
 
+            it "resets the engine for the new board size" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("loadsgf fixtures/sgf/seki/eyes.sgf\n").unwrap();
+                assert!(interpreter.read("final_status_list alive\n").is_ok());
+            }
+
         }
 
         describe! time_left {
@@ -157,6 +183,31 @@ describe! interpreter {
 
         }
 
+        describe! kgs_rules {
+
+            it "one argument" {
+                let response = interpreter.read("kgs-rules\n");
+                assert_that(response, is(equal_to(err("missing argument"))));
+            }
+
+            it "sets the japanese rules" {
+                let response = interpreter.read("kgs-rules japanese\n");
+                assert_that(response, is(equal_to(ok(""))));
+                assert_that(interpreter.ruleset(), is(equal_to(Japanese)));
+            }
+
+            it "uses area scoring for the other rules" {
+                interpreter.read("kgs-rules new_zealand\n").unwrap();
+                assert_that(interpreter.ruleset(), is(equal_to(KgsChinese)));
+            }
+
+            it "rejects unknown rules" {
+                let response = interpreter.read("kgs-rules other\n");
+                assert_that(response, is(equal_to(err("unknown ruleset"))));
+            }
+
+        }
+
         describe! boardsize {
 
             it "one argument" {
@@ -202,7 +253,7 @@ describe! interpreter {
 
             it "no newline at end" {
                 let response = interpreter.read("list_commands\n");
-                let expected = "boardsize\nclear_board\nfinal_score\nfinal_status_list\ngenmove\ngogui-analyze_commands\nimrscl-ownership\nknown_command\nkomi\nlist_commands\nloadsgf\nname\nplay\nprotocol_version\nquit\nshowboard\ntime_left\ntime_settings\nversion";
+                let expected = "boardsize\nclear_board\nfinal_score\nfinal_status_list\nfixed_handicap\ngenmove\ngg-undo\ngogui-analyze_commands\nimrscl-get\nimrscl-ownership\nimrscl-params\nimrscl-ponder\nimrscl-set\nimrscl-solve-ld\nkata-analyze\nkgs-rules\nknown_command\nkomi\nlist_commands\nloadsgf\nlz-analyze\nname\nplace_free_handicap\nplay\nprintsgf\nprotocol_version\nquit\nset_free_handicap\nshowboard\ntime_left\ntime_settings\nundo\nversion";
                 assert_that(response, is(equal_to(ok(expected))));
             }
 
@@ -238,6 +289,140 @@ describe! interpreter {
 
         }
 
+        describe! fixed_handicap {
+
+            it "places the stones in the standard locations" {
+                let response = interpreter.read("fixed_handicap 4\n");
+                assert_that(response, is(equal_to(ok("D4 Q16 D16 Q4"))));
+                assert_eq!(357, interpreter.game.board().vacant_point_count());
+            }
+
+            it "rejects an invalid number of stones" {
+                let response = interpreter.read("fixed_handicap 10\n");
+                assert_that(response, is(equal_to(err("invalid number of stones"))));
+            }
+
+            it "rejects a non-empty board" {
+                interpreter.read("play b a1\n").unwrap();
+                let response = interpreter.read("fixed_handicap 2\n");
+                assert_that(response, is(equal_to(err("board not empty"))));
+            }
+
+            it "requires an argument" {
+                let response = interpreter.read("fixed_handicap\n");
+                assert_that(response, is(equal_to(err("missing argument"))));
+            }
+
+        }
+
+        describe! place_free_handicap {
+
+            it "places the requested number of stones" {
+                let response = interpreter.read("place_free_handicap 3\n");
+                assert_that(response, is(equal_to(ok("D4 Q16 D16"))));
+                assert_eq!(358, interpreter.game.board().vacant_point_count());
+            }
+
+            it "allows more stones than the fixed placement" {
+                interpreter.read("place_free_handicap 12\n").unwrap();
+                assert_eq!(349, interpreter.game.board().vacant_point_count());
+            }
+
+            it "places up to one stone less than the size of the board" {
+                interpreter.read("boardsize 17\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("place_free_handicap 288\n").unwrap();
+                assert_eq!(288, interpreter.game.handicap());
+                assert_eq!(1, interpreter.game.board().vacant_point_count());
+            }
+
+            it "rejects more stones than that" {
+                interpreter.read("boardsize 17\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                let response = interpreter.read("place_free_handicap 289\n");
+                assert_that(response, is(equal_to(err("invalid number of stones"))));
+            }
+
+        }
+
+        describe! set_free_handicap {
+
+            it "places the given stones" {
+                let response = interpreter.read("set_free_handicap c3 e5\n");
+                assert_that(response, is(equal_to(ok(""))));
+                assert_eq!(359, interpreter.game.board().vacant_point_count());
+            }
+
+            it "rejects duplicate vertices" {
+                let response = interpreter.read("set_free_handicap c3 c3\n");
+                assert_that(response, is(equal_to(err("bad vertex list"))));
+            }
+
+            it "rejects invalid vertices" {
+                let response = interpreter.read("set_free_handicap c3 z99\n");
+                assert_that(response, is(equal_to(err("bad vertex list"))));
+            }
+
+            it "requires at least two stones" {
+                let response = interpreter.read("set_free_handicap c3\n");
+                assert_that(response, is(equal_to(err("missing argument(s)"))));
+            }
+
+        }
+
+        describe! analyze {
+
+            it "requests an analysis" {
+                assert_that(interpreter.read("lz-analyze b 10\n"), is(equal_to(ok(""))));
+                assert!(interpreter.analysis_requested());
+            }
+
+            it "accepts the interval as a key value pair" {
+                assert_that(interpreter.read("kata-analyze interval 50\n"), is(equal_to(ok(""))));
+                assert!(interpreter.analysis_requested());
+            }
+
+            it "rejects an invalid interval" {
+                let response = interpreter.read("lz-analyze b interval soon\n");
+                assert_that(response, is(equal_to(err("invalid interval"))));
+                assert!(!interpreter.analysis_requested());
+            }
+
+            it "streams info lines until it is stopped" {
+                interpreter.read("boardsize 5\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("lz-analyze b 0\n").unwrap();
+                let calls = ::std::cell::Cell::new(0);
+                let stop = || {
+                    calls.set(calls.get() + 1);
+                    calls.get() > 100
+                };
+                let mut output = vec!();
+                interpreter.analyze(&mut output, &stop);
+                let output = String::from_utf8(output).unwrap();
+                assert!(output.starts_with("info move "));
+                assert!(output.contains(" winrate "));
+                assert!(output.contains(" pv "));
+                assert!(!interpreter.analysis_requested());
+            }
+
+            it "doesn't play a move" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("kata-analyze b 0\n").unwrap();
+                let calls = ::std::cell::Cell::new(0);
+                let stop = || {
+                    calls.set(calls.get() + 1);
+                    calls.get() > 10
+                };
+                interpreter.analyze(&mut vec!(), &stop);
+                let response = interpreter.read("showboard\n");
+                let expected = "\nkomi: 6.5\n 3 . . . \n 2 . . . \n 1 . . . \n   1 2 3 \n";
+                assert_that(response, is(equal_to(ok(expected))));
+            }
+
+        }
+
         describe! name {
 
             it "returns the engine name" {
@@ -247,6 +432,34 @@ describe! interpreter {
 
         }
 
+        describe! printsgf {
+
+            it "returns the game record" {
+                interpreter.read("boardsize 9\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b c3\n").unwrap();
+                let response = interpreter.read("printsgf\n").unwrap();
+                assert!(response.starts_with("(;GM[1]FF[4]"));
+                assert!(response.contains("SZ[9]"));
+                assert!(response.ends_with("\n;B[cg])"));
+            }
+
+            it "records the engine moves with the number of playouts" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("genmove b\n").unwrap();
+                let response = interpreter.read("printsgf\n").unwrap();
+                assert!(response.contains("PB[Iomrascalai]"));
+                assert!(response.contains(" playouts]"));
+            }
+
+            it "can't save to an invalid path" {
+                let response = interpreter.read("printsgf /nonexistent/directory/game.sgf\n");
+                assert_that(response, is(equal_to(err("cannot save file"))));
+            }
+
+        }
+
         describe! protocol_version {
 
             it "returns 2" {
@@ -267,6 +480,87 @@ describe! interpreter {
 
         }
 
+        describe! undo {
+
+            it "takes back the last move" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b b2\n").unwrap();
+                interpreter.read("play w a1\n").unwrap();
+                assert_that(interpreter.read("undo\n"), is(equal_to(ok(""))));
+                let response = interpreter.read("showboard\n");
+                let expected = "\nkomi: 6.5\n 3 . . . \n 2 . X . \n 1 . . . \n   1 2 3 \n";
+                assert_that(response, is(equal_to(ok(expected))));
+            }
+
+            it "fails when there is no move to take back" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                assert_that(interpreter.read("undo\n"), is(equal_to(err("cannot undo"))));
+            }
+
+            it "doesn't take back handicap stones" {
+                interpreter.read("boardsize 9\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("fixed_handicap 2\n").unwrap();
+                assert_that(interpreter.read("undo\n"), is(equal_to(err("cannot undo"))));
+            }
+
+            it "allows replaying a move that was taken back" {
+                interpreter.read("boardsize 4\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                for m in &["b b1", "w c1", "b a2", "w d2", "b b3", "w c3", "b c2", "w b2"] {
+                    interpreter.read(&format!("play {}\n", m)).unwrap();
+                }
+                assert!(interpreter.read("play b c2\n").is_err());
+                interpreter.read("undo\n").unwrap();
+                assert!(interpreter.read("play w b2\n").is_ok());
+            }
+
+            it "removes the comments of the moves taken back" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("genmove b\n").unwrap();
+                interpreter.read("undo\n").unwrap();
+                interpreter.read("play b b2\n").unwrap();
+                let response = interpreter.read("printsgf\n").unwrap();
+                assert!(!response.contains(" playouts]"));
+            }
+
+        }
+
+        describe! gg_undo {
+
+            it "takes back the given number of moves" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b b2\n").unwrap();
+                interpreter.read("play w a1\n").unwrap();
+                interpreter.read("play b c3\n").unwrap();
+                assert_that(interpreter.read("gg-undo 2\n"), is(equal_to(ok(""))));
+                let response = interpreter.read("showboard\n");
+                let expected = "\nkomi: 6.5\n 3 . . . \n 2 . X . \n 1 . . . \n   1 2 3 \n";
+                assert_that(response, is(equal_to(ok(expected))));
+            }
+
+            it "takes back one move without an argument" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b b2\n").unwrap();
+                assert_that(interpreter.read("gg-undo\n"), is(equal_to(ok(""))));
+                assert_that(interpreter.read("undo\n"), is(equal_to(err("cannot undo"))));
+            }
+
+            it "leaves the game alone when there aren't enough moves" {
+                interpreter.read("boardsize 3\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b b2\n").unwrap();
+                assert_that(interpreter.read("gg-undo 2\n"), is(equal_to(err("cannot undo"))));
+                assert_that(interpreter.read("undo\n"), is(equal_to(ok(""))));
+            }
+
+        }
+
         describe! version {
 
             it "returns the current version" {
@@ -308,6 +602,22 @@ describe! interpreter {
                 assert_that(response, is(equal_to(ok(""))));
             }
 
+            it "reports the stones in seki" {
+                interpreter.read("boardsize 5\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("loadsgf fixtures/sgf/seki/eyes.sgf\n").unwrap();
+                let response = interpreter.read("final_status_list seki\n");
+                assert_that(response, is(equal_to(ok("A4 B4 B5 C4 D4 D5 E4"))));
+            }
+
+            it "doesn't report the stones in seki as alive" {
+                interpreter.read("boardsize 5\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("loadsgf fixtures/sgf/seki/eyes.sgf\n").unwrap();
+                let response = interpreter.read("final_status_list alive\n");
+                assert_that(response, is(equal_to(ok("A2 B2 C2 D2 E2 A3 B3 C3 D3 E3"))));
+            }
+
             it "returns an error on other arguments" {
                 let response = interpreter.read("final_status_list other\n");
                 assert_that(response, is(equal_to(err("unknown argument"))));
@@ -325,7 +635,7 @@ describe! interpreter {
             describe! analyze_commands {
 
                 it "returns the supported analyze commands" {
-                    let expected = "dboard/Ownership/imrscl-ownership\nplist/Final Status List Dead/final_status_list dead\nplist/Final Status List Alive/final_status_list alive";
+                    let expected = "dboard/Ownership/imrscl-ownership\nparam/Parameters/imrscl-params\nplist/Final Status List Dead/final_status_list dead\nplist/Final Status List Alive/final_status_list alive";
                     let response = interpreter.read("gogui-analyze_commands\n");
                     assert_that(response, is(equal_to(ok(expected))));
                 }
@@ -340,11 +650,175 @@ describe! interpreter {
             describe! ownership {
 
                 it "returns board of ownership likelihoods" {
-                    interpreter.read("boardsize 3\n").unwrap();
+                    // The pass-alive points are owned no matter how
+                    // many playouts the search runs.
+                    interpreter.read("boardsize 5\n").unwrap();
                     interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("loadsgf fixtures/sgf/safety/pass-alive.sgf\n").unwrap();
                     interpreter.read("genmove b\n").unwrap();
                     let response = interpreter.read("imrscl-ownership\n");
-                    assert_that(response, is(equal_to(ok("0 0 0 \n0 0 0 \n0 0 0 \n"))));
+                    assert_that(response, is(equal_to(ok("1 1 1 -1 -1 \n1 1 1 -1 -1 \n1 1 1 -1 -1 \n1 1 1 -1 -1 \n1 1 1 -1 -1 \n"))));
+                }
+            }
+
+            describe! solve_ld {
+
+                it "returns the status of the chain at the vertex" {
+                    interpreter.read("loadsgf fixtures/sgf/nakade/straight-three.sgf\n").unwrap();
+                    assert_that(interpreter.read("imrscl-solve-ld B2\n"), is(equal_to(ok("unsettled"))));
+                }
+
+                it "returns dead for a chain that can't make two eyes" {
+                    interpreter.read("loadsgf fixtures/sgf/life-and-death/dead.sgf\n").unwrap();
+                    assert_that(interpreter.read("imrscl-solve-ld A2\n"), is(equal_to(ok("dead"))));
+                }
+
+                it "requires a vertex" {
+                    assert_that(interpreter.read("imrscl-solve-ld\n"), is(equal_to(err("missing argument"))));
+                }
+
+                it "rejects invalid vertices" {
+                    interpreter.read("boardsize 9\n").unwrap();
+                    assert_that(interpreter.read("imrscl-solve-ld Z99\n"), is(equal_to(err("invalid vertex"))));
+                }
+
+                it "rejects empty vertices" {
+                    interpreter.read("boardsize 9\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    assert_that(interpreter.read("imrscl-solve-ld E5\n"), is(equal_to(err("empty vertex"))));
+                }
+            }
+
+            describe! ponder {
+
+                it "is off by default" {
+                    assert_that(interpreter.read("imrscl-ponder\n"), is(equal_to(ok("off"))));
+                }
+
+                it "can be turned on and off" {
+                    assert_that(interpreter.read("imrscl-ponder on\n"), is(equal_to(ok("on"))));
+                    assert_that(interpreter.read("imrscl-ponder\n"), is(equal_to(ok("on"))));
+                    assert_that(interpreter.read("imrscl-ponder off\n"), is(equal_to(ok("off"))));
+                }
+
+                it "rejects other arguments" {
+                    let response = interpreter.read("imrscl-ponder maybe\n");
+                    assert_that(response, is(equal_to(err("invalid argument"))));
+                }
+
+                it "is requested after genmove when turned on" {
+                    interpreter.read("boardsize 5\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("imrscl-ponder on\n").unwrap();
+                    interpreter.read("genmove b\n").unwrap();
+                    assert!(interpreter.ponder_requested());
+                    interpreter.read("showboard\n").unwrap();
+                    assert!(!interpreter.ponder_requested());
+                }
+
+                it "isn't requested when turned off" {
+                    interpreter.read("boardsize 5\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("genmove b\n").unwrap();
+                    assert!(!interpreter.ponder_requested());
+                }
+
+                it "keeps playing after pondering" {
+                    interpreter.read("boardsize 5\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("imrscl-ponder on\n").unwrap();
+                    interpreter.read("genmove b\n").unwrap();
+                    let calls = ::std::cell::Cell::new(0);
+                    let stop = || {
+                        calls.set(calls.get() + 1);
+                        calls.get() > 100
+                    };
+                    interpreter.ponder(&stop);
+                    assert!(!interpreter.ponder_requested());
+                    interpreter.read("play w pass\n").unwrap();
+                    assert!(interpreter.read("genmove b\n").is_ok());
+                }
+
+            }
+
+            describe! params {
+
+                it "returns the current value of a parameter" {
+                    assert_that(interpreter.read("imrscl-get tree.rave_equiv\n"), is(equal_to(ok("20"))));
+                    assert_that(interpreter.read("imrscl-get playout.policy\n"), is(equal_to(ok("cascade"))));
+                }
+
+                it "changes parameters" {
+                    interpreter.read("imrscl-set tree.rave_equiv 35.5\n").unwrap();
+                    interpreter.read("imrscl-set priors.empty 7\n").unwrap();
+                    interpreter.read("imrscl-set playout.policy weighted\n").unwrap();
+                    assert_that(interpreter.read("imrscl-get tree.rave_equiv\n"), is(equal_to(ok("35.5"))));
+                    assert_that(interpreter.read("imrscl-get priors.empty\n"), is(equal_to(ok("7"))));
+                    assert_that(interpreter.read("imrscl-get playout.policy\n"), is(equal_to(ok("weighted"))));
+                    assert_eq!(7, interpreter.config.priors.empty);
+                }
+
+                it "changes the dynamic komi of the engine" {
+                    interpreter.read("boardsize 5\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("place_free_handicap 2\n").unwrap();
+                    let without = best_winrate(&mut interpreter);
+                    interpreter.read("imrscl-set dynamic_komi.mode linear\n").unwrap();
+                    interpreter.read("imrscl-set dynamic_komi.handicap_stone_value 1000\n").unwrap();
+                    // White wins every playout with that much komi.
+                    let with = best_winrate(&mut interpreter);
+                    assert!(without < 0.5);
+                    assert!(with > 0.9);
+                }
+
+                it "keeps the other parameters" {
+                    interpreter.read("imrscl-set tree.rave_equiv 35\n").unwrap();
+                    assert_that(interpreter.read("imrscl-get tree.expand_after\n"), is(equal_to(ok("1"))));
+                }
+
+                it "rejects unknown parameters" {
+                    let response = interpreter.read("imrscl-set tree.unknown 1\n");
+                    assert_that(response, is(equal_to(err("unknown parameter tree.unknown"))));
+                    let response = interpreter.read("imrscl-get book.enabled\n");
+                    assert_that(response, is(equal_to(err("unknown parameter book.enabled"))));
+                }
+
+                it "rejects values of the wrong type" {
+                    let response = interpreter.read("imrscl-set priors.empty 1.5\n");
+                    assert_that(response, is(equal_to(err("expected non-negative integer for \"priors.empty\" but found \"1.5\""))));
+                    let response = interpreter.read("imrscl-set tree.rave_equiv many\n");
+                    assert_that(response, is(equal_to(err("expected float for \"tree.rave_equiv\" but found \"many\""))));
+                    let response = interpreter.read("imrscl-set playout.policy random\n");
+                    assert_that(response, is(equal_to(err("expected one of cascade, weighted for \"playout.policy\""))));
+                }
+
+                it "turns pondering on and off" {
+                    interpreter.read("imrscl-set time_control.ponder 1\n").unwrap();
+                    assert_that(interpreter.read("imrscl-ponder\n"), is(equal_to(ok("on"))));
+                }
+
+                it "keeps playing with the new parameters" {
+                    interpreter.read("boardsize 5\n").unwrap();
+                    interpreter.read("clear_board\n").unwrap();
+                    interpreter.read("genmove b\n").unwrap();
+                    interpreter.read("imrscl-set playout.policy weighted\n").unwrap();
+                    interpreter.read("imrscl-set tree.expand_after 2\n").unwrap();
+                    assert!(interpreter.read("genmove w\n").is_ok());
+                }
+
+                it "lists the parameters for gogui" {
+                    let response = interpreter.read("imrscl-params\n").unwrap();
+                    let lines: Vec<&str> = response.lines().collect();
+                    assert!(lines.contains(&"[list/cascade/weighted] playout.policy cascade"));
+                    assert!(lines.contains(&"[bool] time_control.ponder 0"));
+                    assert!(lines.contains(&"[string] tree.rave_equiv 20"));
+                    assert!(lines.contains(&"[list/adaptive/linear/off] dynamic_komi.mode off"));
+                    assert_eq!("[string] dynamic_komi.adaptive_high 0.85", lines[0]);
+                }
+
+                it "changes parameters from gogui" {
+                    interpreter.read("imrscl-params time_control.min_stones 20\n").unwrap();
+                    assert_that(interpreter.read("imrscl-get time_control.min_stones\n"), is(equal_to(ok("20"))));
                 }
             }
 
@@ -359,7 +833,7 @@ describe! interpreter {
             c.ruleset = KgsChinese;
             let config = Arc::new(c);
             let matcher = Arc::new(Matcher::new());
-            let engine = Box::new(EngineImpl::new(config.clone(), matcher));
+            let engine = Box::new(EngineImpl::new(config.clone(), matcher, Arc::new(Book::new())));
             let mut interpreter = GTPInterpreter::new(config.clone(), engine);
         }
 
@@ -419,4 +893,36 @@ describe! interpreter {
 
     }
 
+    describe! japanese {
+
+        before_each {
+            let mut c = Config::test_config();
+            c.ruleset = Japanese;
+            let config = Arc::new(c);
+            let matcher = Arc::new(Matcher::new());
+            let engine = Box::new(EngineImpl::new(config.clone(), matcher, Arc::new(Book::new())));
+            let mut interpreter = GTPInterpreter::new(config.clone(), engine);
+        }
+
+        describe! final_score {
+
+            it "no move" {
+                interpreter.read("boardsize 9\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                let response = interpreter.read("final_score\n");
+                assert_that(response, is(equal_to(ok("W+6.5"))));
+            }
+
+            it "doesn't count the stones" {
+                interpreter.read("boardsize 4\n").unwrap();
+                interpreter.read("clear_board\n").unwrap();
+                interpreter.read("play b c2\n").unwrap();
+                let response = interpreter.read("final_score\n");
+                assert_that(response, is(equal_to(ok("B+8.5"))));
+            }
+
+        }
+
+    }
+
 }
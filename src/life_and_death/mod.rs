@@ -0,0 +1,364 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Board;
+use board::Color;
+use board::Coord;
+use board::Empty;
+use board::Move;
+use board::Play;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+mod test;
+
+// The largest number of empty points in the region around the
+// target chain that the solver reads out.
+const MAX_REGION: usize = 15;
+// The chains of the wall with at most this many liberties can be
+// attacked.
+const MAX_WALL_LIBERTIES: usize = 2;
+// The number of positions the solver may visit per search before
+// giving up.
+const MAX_NODES: usize = 100000;
+
+// The outcomes of a search from the point of view of the defender,
+// ordered from worst to best.
+const DEAD: u8 = 0;
+const KO: u8 = 1;
+const SEKI: u8 = 2;
+const ALIVE: u8 = 3;
+
+/// The life and death status of a chain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The chain can't be captured, even if the opponent moves first.
+    Alive,
+    /// The chain can't be saved, even if its owner moves first.
+    Dead,
+    /// The outcome depends on a ko.
+    Ko,
+    /// The chain survives without two eyes, sharing liberties with
+    /// opponent chains.
+    Seki,
+    /// Whoever moves first decides the outcome.
+    Unsettled,
+    /// The region around the chain isn't enclosed or is too large to
+    /// be read out.
+    Unknown,
+}
+
+impl fmt::Display for Status {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Status::Alive => "alive",
+            Status::Dead => "dead",
+            Status::Ko => "ko",
+            Status::Seki => "seki",
+            Status::Unsettled => "unsettled",
+            Status::Unknown => "unknown",
+        };
+        s.fmt(f)
+    }
+}
+
+/// Reads out the life and death of a chain in a small enclosed
+/// region with an alpha-beta search.
+///
+/// The region consists of the empty points and the stones of the
+/// owner of the chain that are connected to it, i.e. the opponent
+/// stones around it form the wall. Chains of the wall that are short
+/// of liberties can be attacked as well.
+/// The chain is alive if it has two eyes or if it survives when both
+/// players pass without sharing a liberty with the opponent.
+pub struct Solver {
+    aborted: bool,
+    attacker: Color,
+    cutoffs: usize,
+    defender: Color,
+    max_nodes: usize,
+    nodes: usize,
+    region: Vec<Coord>,
+    table: HashMap<u64, u8>,
+    target: Coord,
+}
+
+impl Solver {
+
+    /// Returns `None` if there's no chain at the target or if the
+    /// region around it is too large, i.e. it contains too many empty
+    /// points or covers more than half of the board.
+    pub fn new(board: &Board, target: Coord) -> Option<Solver> {
+        Self::with_max_nodes(board, target, MAX_NODES)
+    }
+
+    /// Like `new`, but each search gives up (and the status is
+    /// `Unknown`) after visiting `max_nodes` positions.
+    pub fn with_max_nodes(board: &Board, target: Coord, max_nodes: usize) -> Option<Solver> {
+        let defender = board.color(&target);
+        if defender == Empty {
+            return None;
+        }
+        let attacker = defender.opposite();
+        let mut region = vec!(target);
+        let mut empty = 0;
+        let mut i = 0;
+        while i < region.len() {
+            let current = region[i];
+            for &n in board.neighbours(current).iter() {
+                let color = board.color(&n);
+                if color != attacker && !region.contains(&n) {
+                    if color == Empty {
+                        empty += 1;
+                        if empty > MAX_REGION {
+                            return None;
+                        }
+                    }
+                    region.push(n);
+                }
+            }
+            i += 1;
+        }
+        // Otherwise there's hardly a wall around the region.
+        let points = board.size() as usize * board.size() as usize;
+        if 2 * region.len() > points {
+            return None;
+        }
+        Some(Solver {
+            aborted: false,
+            attacker: attacker,
+            cutoffs: 0,
+            defender: defender,
+            max_nodes: max_nodes,
+            nodes: 0,
+            region: region,
+            table: HashMap::new(),
+            target: target,
+        })
+    }
+
+    /// The points of the region around the chain (in the order they
+    /// were found).
+    pub fn region(&self) -> &Vec<Coord> {
+        &self.region
+    }
+
+    /// The status of the chain, taking into account who moves first.
+    pub fn status(&mut self, board: &Board) -> Status {
+        let attacker = self.attacker;
+        let defender = self.defender;
+        match self.solve(board, attacker) {
+            None => Status::Unknown,
+            Some(ALIVE) => Status::Alive,
+            Some(attacker_first) => match self.solve(board, defender) {
+                None => Status::Unknown,
+                Some(DEAD) => Status::Dead,
+                Some(KO) => Status::Ko,
+                Some(_) if attacker_first == KO => Status::Ko,
+                Some(_) if attacker_first == SEKI => Status::Seki,
+                Some(_) => Status::Unsettled,
+            }
+        }
+    }
+
+    // The outcome for the defender if `first` moves first. Returns
+    // `None` if the search was aborted.
+    fn solve(&mut self, board: &Board, first: Color) -> Option<u8> {
+        let mut board = board.clone();
+        board.resume();
+        board.set_next_player(first);
+        self.aborted = false;
+        self.cutoffs = 0;
+        self.nodes = 0;
+        self.table.clear();
+        let outcome = self.search(&board, 0, 0);
+        if self.aborted {
+            None
+        } else {
+            Some(outcome)
+        }
+    }
+
+    fn search(&mut self, board: &Board, passes: usize, depth: usize) -> u8 {
+        self.nodes += 1;
+        if self.nodes > self.max_nodes {
+            self.aborted = true;
+            return SEKI;
+        }
+        if board.color(&self.target) != self.defender {
+            return DEAD;
+        }
+        if self.has_two_eyes(board) {
+            return ALIVE;
+        }
+        if passes >= 2 {
+            return self.outcome_after_passes(board);
+        }
+        // Cycles (e.g. a triple ko) would otherwise go on forever.
+        if depth > 2 * self.region.len() + 10 {
+            self.cutoffs += 1;
+            return KO;
+        }
+        let key = self.key(board, passes);
+        if let Some(&outcome) = self.table.get(&key) {
+            return outcome;
+        }
+        // The outcome only depends on the position (and can be
+        // stored) unless the search below runs into the depth limit.
+        let cutoffs = self.cutoffs;
+        let color = board.next_player();
+        let defending = color == self.defender;
+        let mut best = if defending { DEAD } else { ALIVE };
+        let done = if defending { ALIVE } else { DEAD };
+        for m in self.moves(board, color) {
+            let mut child = board.clone();
+            child.play_legal_move(m);
+            // Suicide (allowed by some rulesets) never helps.
+            if child.color(&m.coord()) != color {
+                continue;
+            }
+            let mut outcome = self.search(&child, 0, depth + 1);
+            // A result that relies on taking a ko is only a ko.
+            if Self::is_ko_capture(&child, &m) {
+                outcome = if defending { cmp::min(outcome, KO) } else { cmp::max(outcome, KO) };
+            }
+            best = if defending { cmp::max(best, outcome) } else { cmp::min(best, outcome) };
+            if best == done || self.aborted {
+                break;
+            }
+        }
+        if best != done && !self.aborted {
+            let mut child = board.clone();
+            child.set_next_player(color.opposite());
+            let outcome = self.search(&child, passes + 1, depth + 1);
+            best = if defending { cmp::max(best, outcome) } else { cmp::min(best, outcome) };
+        }
+        if !self.aborted && self.cutoffs == cutoffs {
+            self.table.insert(key, best);
+        }
+        best
+    }
+
+    // The legal moves in the region and on the liberties of the
+    // chains of the wall that are short of liberties (to be able to
+    // read out capturing races). The defender doesn't fill its own
+    // eyes. The vital points of the eye spaces of the chain are tried
+    // first, then the other points of its eye spaces and then the
+    // points with more empty neighbours.
+    fn moves(&self, board: &Board, color: Color) -> Vec<Move> {
+        let mut points: Vec<Coord> = self.region.iter()
+            .filter(|c| board.color(c) == Empty)
+            .cloned()
+            .collect();
+        for c in self.region.iter() {
+            for n in board.neighbours(*c).iter().filter(|n| board.color(n) == self.attacker) {
+                let liberties = board.get_chain(*n).unwrap().liberties();
+                if liberties.len() <= MAX_WALL_LIBERTIES {
+                    for &liberty in liberties.iter() {
+                        if !points.contains(&liberty) {
+                            points.push(liberty);
+                        }
+                    }
+                }
+            }
+        }
+        let spaces = board.eye_spaces(self.target);
+        let vital_points: Vec<Coord> = spaces.iter()
+            .filter_map(|space| space.vital_point())
+            .collect();
+        let mut moves: Vec<((bool, bool, usize), Move)> = points.iter()
+            .filter(|c| color != self.defender || !board.is_eye(c, color))
+            .map(|c| {
+                let vital = vital_points.contains(c);
+                let inside = spaces.iter().any(|space| space.points().contains(c));
+                let empty = board.neighbours(*c).iter().filter(|n| board.color(n) == Empty).count();
+                ((vital, inside, empty), Play(color, c.col, c.row))
+            })
+            .filter(|&(_, m)| board.is_legal(m).is_ok())
+            .collect();
+        moves.sort_by(|a, b| b.0.cmp(&a.0));
+        moves.into_iter().map(|(_, m)| m).collect()
+    }
+
+    fn has_two_eyes(&self, board: &Board) -> bool {
+        board.get_chain(self.target).unwrap().liberties().iter()
+            .filter(|c| board.is_eye(c, self.defender))
+            .take(2)
+            .count() == 2
+    }
+
+    // After both players passed the chain is alive unless it shares
+    // a liberty with a chain of the attacker, which makes it a seki.
+    fn outcome_after_passes(&self, board: &Board) -> u8 {
+        let attacker = self.attacker;
+        let shared = board.get_chain(self.target).unwrap().liberties().iter()
+            .any(|&liberty| board.neighbours(liberty).iter().any(|c| board.color(c) == attacker));
+        if shared { SEKI } else { ALIVE }
+    }
+
+    // Taking a ko leaves a single stone with one liberty where the
+    // captured stone was.
+    fn is_ko_capture(board: &Board, m: &Move) -> bool {
+        match (board.ko(), board.get_chain(m.coord())) {
+            (Some(ko), Some(chain)) => {
+                chain.coords().len() == 1 && chain.liberties().len() == 1 && chain.liberties().contains(&ko)
+            },
+            _ => false
+        }
+    }
+
+    // A hash of the whole board (the captures may change points
+    // outside of the region), the passes and the player to move.
+    fn key(&self, board: &Board, passes: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for point in board.points().iter() {
+            point.color.hash(&mut hasher);
+        }
+        passes.hash(&mut hasher);
+        board.next_player().hash(&mut hasher);
+        board.ko().hash(&mut hasher);
+        hasher.finish()
+    }
+
+}
+
+/// The life and death status of the chain at `target`. Pass-alive
+/// chains (and the chains inside their eye spaces) are settled
+/// without a search, all others are read out by the `Solver`.
+pub fn status(board: &Board, target: Coord) -> Status {
+    let color = board.color(&target);
+    let owner = board.pass_alive()[target.to_index(board.size())];
+    if color != Empty && owner == color {
+        return Status::Alive;
+    } else if color != Empty && owner == color.opposite() {
+        return Status::Dead;
+    }
+    match Solver::new(board, target) {
+        Some(mut solver) => solver.status(board),
+        None => Status::Unknown
+    }
+}
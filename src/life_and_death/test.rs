@@ -0,0 +1,75 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2015 Urban Hafner, Igor Polyakov                           *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Coord;
+use fixtures::load_board;
+use super::Status;
+use super::status;
+
+#[test]
+fn a_straight_three_is_unsettled() {
+    let board = load_board("nakade/straight-three");
+    assert_eq!(Status::Unsettled, status(&board, Coord::new(2,2)));
+}
+
+#[test]
+fn a_straight_three_with_a_stone_on_the_vital_point_is_dead() {
+    let board = load_board("life-and-death/dead");
+    assert_eq!(Status::Dead, status(&board, Coord::new(2,2)));
+}
+
+#[test]
+fn a_chain_with_two_eyes_is_alive() {
+    let board = load_board("life-and-death/alive");
+    assert_eq!(Status::Alive, status(&board, Coord::new(2,2)));
+}
+
+#[test]
+fn a_chain_in_a_large_region_has_an_unknown_status() {
+    let board = load_board("life-and-death/dead");
+    assert_eq!(Status::Unknown, status(&board, Coord::new(1,3)));
+}
+
+#[test]
+fn a_chain_sharing_a_liberty_with_an_eye_is_in_seki() {
+    let board = load_board("seki/eyes");
+    assert_eq!(Status::Seki, status(&board, Coord::new(2,4)));
+}
+
+#[test]
+fn a_stone_inside_a_pass_alive_eye_space_is_dead() {
+    let board = load_board("safety/dead-stone");
+    assert_eq!(Status::Dead, status(&board, Coord::from_gtp("A5")));
+    assert_eq!(Status::Alive, status(&board, Coord::from_gtp("B5")));
+}
+
+#[test]
+fn an_empty_point_has_no_status() {
+    let board = load_board("nakade/straight-three");
+    assert_eq!(Status::Unknown, status(&board, Coord::new(5,5)));
+}
+
+#[test]
+fn the_status_is_displayed_in_lower_case() {
+    assert_eq!("unsettled", Status::Unsettled.to_string());
+}
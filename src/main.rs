@@ -20,7 +20,6 @@
  * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
  *                                                                      *
  ************************************************************************/
-#![feature(mpsc_select)]
 #![feature(plugin)]
 #![feature(test)]
 #![plugin(regex_macros)]
@@ -47,6 +46,7 @@ extern crate toml;
 // Use everything in config publicly to force the generation of
 // documentation.
 pub use config::*;
+use book::Book;
 use gtp::driver::Driver;
 use patterns::Matcher;
 use ruleset::Ruleset;
@@ -57,11 +57,13 @@ use std::env::args;
 use std::process::exit;
 
 mod board;
+mod book;
 mod config;
 mod engine;
 mod fixtures;
 mod game;
 mod gtp;
+mod life_and_death;
 mod ownership;
 mod patterns;
 mod playout;
@@ -69,6 +71,7 @@ mod ruleset;
 mod score;
 mod sgf;
 mod timer;
+mod tools;
 mod version;
 
 fn main() {
@@ -80,9 +83,13 @@ fn main() {
     opts.optflag("l", "log", "Print logging information to STDERR");
     opts.optflag("v", "version", "Print the version number");
     opts.optopt("c", "config", "Config file", "FILE");
-    let r_expl = format!("cgos|chinese|tromp-taylor (defaults to {})", default_ruleset);
+    opts.optopt("", "check-config", "Report all problems with a config file", "FILE");
+    let r_expl = format!("cgos|chinese|japanese|tromp-taylor (defaults to {})", default_ruleset);
     opts.optopt("r", "rules", "Pick ruleset", &r_expl);
     let args : Vec<String> = args().collect();
+    if let Some(code) = tools::run(&args) {
+        exit(code);
+    }
 
     let (_, tail) = args.split_first().unwrap();
     let matches = match opts.parse(tail) {
@@ -94,7 +101,10 @@ fn main() {
     };
 
     if matches.opt_present("h") {
-        let brief = format!("Usage: {} [options]", args[0]);
+        let mut brief = format!("Usage: {} [options]\n       {} SUBCOMMAND [options]\n\nSubcommands:", args[0], args[0]);
+        for &(name, description) in tools::SUBCOMMANDS {
+            brief.push_str(&format!("\n    {:16}{}", name, description));
+        }
         println!("{}", opts.usage(brief.as_ref()));
         exit(0);
     }
@@ -106,6 +116,18 @@ fn main() {
         println!("{}", Config::toml());
         exit(0);
     }
+    if let Some(filename) = matches.opt_str("check-config") {
+        match Config::from_file(filename.clone(), false, false, default_ruleset) {
+            Ok(_) => {
+                println!("{}: no problems found", filename);
+                exit(0);
+            },
+            Err(errors) => {
+                println!("{}", describe_errors(&filename, &errors));
+                exit(1);
+            }
+        }
+    }
     let log = matches.opt_present("l");
     let gfx = matches.opt_present("g");
     let ruleset = match matches.opt_str("r") {
@@ -122,7 +144,13 @@ fn main() {
     let config_file_opt = matches.opt_str("c");
     let config = match config_file_opt {
         Some(filename) => {
-            Config::from_file(filename, log, gfx, ruleset)
+            match Config::from_file(filename.clone(), log, gfx, ruleset) {
+                Ok(config) => config,
+                Err(errors) => {
+                    println!("{}", describe_errors(&filename, &errors));
+                    exit(1);
+                }
+            }
         },
         None => {
             Config::default(log, gfx, ruleset)
@@ -132,9 +160,29 @@ fn main() {
     let config = Arc::new(config);
     // Instantiate only one matcher as it does a lot of computation
     // during setup.
-    let matcher = Arc::new(Matcher::new());
+    let matcher = match Matcher::from_config(&config) {
+        Ok(matcher) => Arc::new(matcher),
+        Err(error) => {
+            println!("{}", error);
+            exit(1);
+        }
+    };
+    if matcher.has_large_patterns() {
+        config.log(format!("Loaded {} large patterns", matcher.large_pattern_count()));
+    }
+
+    let book = match Book::from_config(&config) {
+        Ok(book) => Arc::new(book),
+        Err(error) => {
+            println!("{}", error);
+            exit(1);
+        }
+    };
+    if !book.is_empty() {
+        config.log(format!("Loaded {} book positions", book.len()));
+    }
 
-    let engine = engine::factory(config.clone(), matcher);
+    let engine = engine::factory(config.clone(), matcher, book);
 
     config.log(format!("Current configuration: {:#?}", config));
 
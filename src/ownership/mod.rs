@@ -20,6 +20,7 @@
  ************************************************************************/
 
 use board::Black;
+use board::Board;
 use board::Color;
 use board::Coord;
 use board::Empty;
@@ -40,6 +41,7 @@ pub struct OwnershipStatistics {
     config: Arc<Config>,
     empty: Vec<usize>,
     komi: f32,
+    pass_alive: Vec<Color>,
     size: u8,
     white: Vec<usize>,
 }
@@ -54,11 +56,20 @@ impl OwnershipStatistics {
             config: config,
             empty: vec![prior; len],
             komi: komi,
+            pass_alive: vec![],
             size: size,
             white: vec![0; len],
         }
     }
 
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    pub fn komi(&self) -> f32 {
+        self.komi
+    }
+
     pub fn merge(&mut self, score: &Score) {
         for (i, color) in score.owner().iter().enumerate() {
             match *color {
@@ -75,8 +86,29 @@ impl OwnershipStatistics {
         }
     }
 
+    /// Adds the counts collected by another instance (e.g. of one of
+    /// the worker threads). The prior is only counted once.
+    pub fn add(&mut self, other: &OwnershipStatistics) {
+        let prior = self.config.scoring.ownership_prior;
+        for i in 0..self.black.len() {
+            self.black[i] += other.black[i];
+            self.white[i] += other.white[i];
+            self.empty[i] += other.empty[i] - prior;
+        }
+    }
+
+    /// The pass-alive points of the board (see `Board::pass_alive`)
+    /// are owned regardless of the playouts.
+    pub fn set_pass_alive(&mut self, board: &Board) {
+        self.pass_alive = board.pass_alive();
+    }
+
     pub fn owner(&self, coord: &Coord) -> Color {
         let index = coord.to_index(self.size);
+        match self.pass_alive.get(index) {
+            Some(&Empty) | None => {},
+            Some(&color) => return color
+        }
         let b = self.black[index];
         let w = self.white[index];
         let e = self.empty[index];
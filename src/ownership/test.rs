@@ -21,8 +21,13 @@
 
 #![cfg(test)]
 
+pub use board::Black;
 pub use board::Board;
+pub use board::Coord;
+pub use board::Empty;
+pub use board::Play;
 pub use config::Config;
+pub use fixtures::load_board;
 pub use ruleset::KgsChinese;
 pub use super::OwnershipStatistics;
 
@@ -40,6 +45,48 @@ describe! ownership {
         // Tests for merge
         // Tests for formatting
 
+        describe! add {
+
+            before_each {
+                let mut config = Config::test_config();
+                config.scoring.ownership_prior = 1;
+                let config = Arc::new(config);
+                let mut stats = OwnershipStatistics::new(config.clone(), 3, 6.5);
+                let mut other = OwnershipStatistics::new(config.clone(), 3, 6.5);
+                let mut board = Board::new(3, 6.5, KgsChinese);
+                board.play(Play(Black, 2, 2)).unwrap();
+                let score = board.score();
+            }
+
+            it "adds up the counts of both" {
+                stats.merge(&score);
+                other.merge(&score);
+                stats.add(&other);
+                assert_that(stats.black, is(equal_to(vec![2; 9])));
+                assert_that(stats.white, is(equal_to(vec![0; 9])));
+            }
+
+            it "only counts the prior once" {
+                stats.merge(&score);
+                stats.add(&other);
+                stats.add(&other);
+                assert_that(stats.empty, is(equal_to(vec![1; 9])));
+                assert_that(stats.black, is(equal_to(vec![1; 9])));
+            }
+        }
+
+        describe! pass_alive {
+
+            it "owns the pass-alive points without playouts" {
+                let config = Arc::new(Config::test_config());
+                let mut stats = OwnershipStatistics::new(config, 5, 0.5);
+                assert_that(stats.owner(&Coord::from_gtp("A5")), is(equal_to(Empty)));
+                stats.set_pass_alive(&load_board("safety/pass-alive"));
+                assert_that(stats.owner(&Coord::from_gtp("A5")), is(equal_to(Black)));
+                assert!(stats.decided());
+            }
+        }
+
         describe! formatting {
 
             before_each {
@@ -0,0 +1,164 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+pub use self::shape::MAX_DISTANCE;
+pub use self::shape::MIN_DISTANCE;
+pub use self::shape::PointState;
+pub use self::shape::Shapes;
+use board::Board;
+use board::Move;
+use patterns::Feature;
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+mod shape;
+mod test;
+
+/// The large patterns (Mogo/Fuego style) together with their gamma
+/// values. The gamma of a pattern is its strength in the
+/// Bradley-Terry model, i.e. a move that matches a pattern with
+/// gamma 2.0 is twice as likely to be played as one with gamma 1.0.
+///
+/// The patterns are read from a text file with one pattern per line.
+/// Each line contains the gamma and the shape, separated by
+/// whitespace. The shape lists the states of the points around the
+/// move in the order given by `Shapes`: `.` is empty, `X` is a stone
+/// of the player to move, `O` a stone of the opponent and `#` is off
+/// the board. Instead of a shape a line can also contain the name
+/// of one of the tactical features (see `Feature::name()`). Empty
+/// lines and lines starting with `#` are ignored.
+pub struct Dictionary {
+    features: HashMap<Feature, f32>,
+    gammas: HashMap<u64, f32>,
+    len: usize,
+    max_distance: u8,
+    shapes: Shapes,
+}
+
+impl Dictionary {
+
+    pub fn new() -> Dictionary {
+        Dictionary {
+            features: HashMap::new(),
+            gammas: HashMap::new(),
+            len: 0,
+            max_distance: 0,
+            shapes: Shapes::new(),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Dictionary, String> {
+        let mut contents = String::new();
+        try!(File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("Unable to read {}: {}", path.display(), e)));
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Dictionary, String> {
+        let mut dictionary = Self::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+            try!(dictionary.parse_line(line).map_err(|e| format!("line {}: {}", i + 1, e)));
+        }
+        Ok(dictionary)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 2 {
+            return Err(format!("expected a gamma and a shape"));
+        }
+        let gamma = try!(fields[0].parse::<f32>().map_err(|_| format!("invalid gamma {:?}", fields[0])));
+        if !(gamma > 0.0) {
+            return Err(format!("the gamma needs to be positive"));
+        }
+        if let Some(feature) = Feature::from_name(fields[1]) {
+            self.insert_feature(feature, gamma);
+            return Ok(());
+        }
+        let states: Vec<Option<PointState>> = fields[1].chars().map(PointState::from_char).collect();
+        if states.iter().any(|state| state.is_none()) {
+            return Err(format!("invalid shape {:?}", fields[1]));
+        }
+        if self.shapes.distance_for_size(states.len()).is_none() {
+            return Err(format!("a shape of {} points doesn't exist", states.len()));
+        }
+        let states: Vec<PointState> = states.into_iter().map(|state| state.unwrap()).collect();
+        self.insert(&states, gamma);
+        Ok(())
+    }
+
+    /// Adds the pattern (in all its rotations and reflections).
+    pub fn insert(&mut self, states: &[PointState], gamma: f32) {
+        for symmetry in self.shapes.symmetries(states) {
+            let hash = self.shapes.hash(&symmetry);
+            self.gammas.insert(hash, gamma);
+        }
+        let distance = self.shapes.distance_for_size(states.len()).unwrap();
+        self.max_distance = cmp::max(self.max_distance, distance);
+        self.len += 1;
+    }
+
+    pub fn insert_feature(&mut self, feature: Feature, gamma: f32) {
+        self.features.insert(feature, gamma);
+    }
+
+    pub fn feature_gamma(&self, feature: Feature) -> Option<f32> {
+        self.features.get(&feature).cloned()
+    }
+
+    /// The gamma of the largest pattern that matches the move.
+    pub fn gamma(&self, board: &Board, m: &Move) -> Option<f32> {
+        if self.len == 0 || m.is_pass() {
+            return None;
+        }
+        self.shapes.hashes(board, &m.coord(), *m.color(), self.max_distance)
+            .iter()
+            .rev()
+            .filter_map(|hash| self.gammas.get(hash))
+            .next()
+            .cloned()
+    }
+
+    /// The distance of the largest pattern.
+    pub fn max_distance(&self) -> u8 {
+        self.max_distance
+    }
+
+    /// The number of patterns (without the rotations and
+    /// reflections).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0 && self.features.is_empty()
+    }
+
+}
@@ -0,0 +1,217 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Board;
+use board::Color;
+use board::Coord;
+use board::Empty;
+
+use std::cmp;
+use std::collections::HashMap;
+
+/// The smallest shape is the 3x3 square around the move.
+pub const MIN_DISTANCE: u8 = 3;
+/// The largest shape contains all points up to this distance.
+pub const MAX_DISTANCE: u8 = 10;
+
+const SYMMETRIES: [(i8, i8, i8, i8); 8] = [
+    (1, 0, 0, 1), (-1, 0, 0, 1), (1, 0, 0, -1), (-1, 0, 0, -1),
+    (0, 1, 1, 0), (0, -1, 1, 0), (0, 1, -1, 0), (0, -1, -1, 0)];
+
+/// The state of a point of a shape, as seen by the player to move.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PointState {
+    Empty,
+    Own,
+    Opponent,
+    OffBoard,
+}
+
+impl PointState {
+
+    pub fn to_char(&self) -> char {
+        match *self {
+            PointState::Empty => '.',
+            PointState::Own => 'X',
+            PointState::Opponent => 'O',
+            PointState::OffBoard => '#',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<PointState> {
+        match c {
+            '.' => Some(PointState::Empty),
+            'X' => Some(PointState::Own),
+            'O' => Some(PointState::Opponent),
+            '#' => Some(PointState::OffBoard),
+            _ => None
+        }
+    }
+
+    fn index(&self) -> u64 {
+        *self as u64
+    }
+
+}
+
+/// The points around a move, ordered by their (gridcular) distance
+/// to the move. The gridcular distance `dx + dy + max(dx, dy)` gives
+/// roughly diamond shaped patterns, that grow by one ring of points
+/// per distance. The points of each distance are hashed with Zobrist
+/// keys, so that the hash of a shape is the combination of the
+/// hashes of its rings.
+pub struct Shapes {
+    indexes: HashMap<(i8, i8), usize>,
+    offsets: Vec<(i8, i8)>,
+    // The number of points of the shapes of each distance.
+    sizes: Vec<usize>,
+}
+
+impl Shapes {
+
+    pub fn new() -> Shapes {
+        let max = MAX_DISTANCE as i8;
+        let mut offsets = vec!();
+        for dx in -max..max+1 {
+            for dy in -max..max+1 {
+                if (dx, dy) != (0, 0) && Self::distance(dx, dy) <= MAX_DISTANCE {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets.sort_by_key(|&(dx, dy)| (Self::distance(dx, dy), dy, dx));
+        let sizes = (0..MAX_DISTANCE+1)
+            .map(|d| offsets.iter().filter(|&&(dx, dy)| Self::distance(dx, dy) <= d).count())
+            .collect();
+        let indexes = offsets.iter().enumerate().map(|(i, &offset)| (offset, i)).collect();
+        Shapes {
+            indexes: indexes,
+            offsets: offsets,
+            sizes: sizes,
+        }
+    }
+
+    fn distance(dx: i8, dy: i8) -> u8 {
+        let (dx, dy) = (dx.abs() as u8, dy.abs() as u8);
+        dx + dy + cmp::max(dx, dy)
+    }
+
+    /// The number of points in the shape of the given distance.
+    pub fn size(&self, distance: u8) -> usize {
+        self.sizes[distance as usize]
+    }
+
+    /// The distance of the shape with the given number of points.
+    pub fn distance_for_size(&self, size: usize) -> Option<u8> {
+        (MIN_DISTANCE..MAX_DISTANCE+1).find(|&d| self.size(d) == size)
+    }
+
+    /// The states of the points around the move of `color` at
+    /// `coord`, up to the given distance.
+    pub fn extract(&self, board: &Board, coord: &Coord, color: Color, distance: u8) -> Vec<PointState> {
+        self.offsets[0..self.size(distance)].iter()
+            .map(|&offset| self.state(board, coord, color, offset))
+            .collect()
+    }
+
+    fn state(&self, board: &Board, coord: &Coord, color: Color, (dx, dy): (i8, i8)) -> PointState {
+        let col = coord.col as i16 + dx as i16;
+        let row = coord.row as i16 + dy as i16;
+        let size = board.size() as i16;
+        if col < 1 || row < 1 || col > size || row > size {
+            return PointState::OffBoard;
+        }
+        let c = board.color(&Coord::new(col as u8, row as u8));
+        if c == Empty {
+            PointState::Empty
+        } else if c == color {
+            PointState::Own
+        } else {
+            PointState::Opponent
+        }
+    }
+
+    /// The hashes of the shapes around the move, from the smallest
+    /// to the largest distance.
+    pub fn hashes(&self, board: &Board, coord: &Coord, color: Color, max_distance: u8) -> Vec<u64> {
+        let states = self.extract(board, coord, color, max_distance);
+        let mut hashes = vec!();
+        let mut hash = 0;
+        let mut i = 0;
+        for distance in MIN_DISTANCE..max_distance+1 {
+            while i < self.size(distance) {
+                hash ^= Self::key(i, states[i]);
+                i += 1;
+            }
+            hashes.push(hash ^ Self::distance_key(distance));
+        }
+        hashes
+    }
+
+    /// The hash of the shape.
+    pub fn hash(&self, states: &[PointState]) -> u64 {
+        let distance = self.distance_for_size(states.len()).unwrap();
+        let hash = states.iter()
+            .enumerate()
+            .fold(0, |hash, (i, state)| hash ^ Self::key(i, *state));
+        hash ^ Self::distance_key(distance)
+    }
+
+    /// The same hash for all rotations and reflections of the shape.
+    pub fn canonical_hash(&self, states: &[PointState]) -> u64 {
+        self.symmetries(states).iter().map(|symmetry| self.hash(symmetry)).min().unwrap()
+    }
+
+    /// The shape rotated and mirrored in all eight ways (some of
+    /// which may be identical).
+    pub fn symmetries(&self, states: &[PointState]) -> Vec<Vec<PointState>> {
+        SYMMETRIES.iter()
+            .map(|&(a, b, c, d)| {
+                let mut transformed = states.to_vec();
+                for (i, &state) in states.iter().enumerate() {
+                    let (dx, dy) = self.offsets[i];
+                    let offset = (a * dx + b * dy, c * dx + d * dy);
+                    transformed[self.indexes[&offset]] = state;
+                }
+                transformed
+            })
+            .collect()
+    }
+
+    // The Zobrist keys are derived from the index of the point with
+    // SplitMix64 instead of a random number generator, so that the
+    // hashes are the same every time the program runs.
+    fn key(index: usize, state: PointState) -> u64 {
+        Self::mix(index as u64 * 4 + state.index())
+    }
+
+    fn distance_key(distance: u8) -> u64 {
+        Self::mix(0xffff + distance as u64)
+    }
+
+    fn mix(value: u64) -> u64 {
+        let mut z = value.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+}
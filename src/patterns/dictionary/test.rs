@@ -0,0 +1,181 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Board;
+use board::Coord;
+use board::Pass;
+use board::Play;
+use board::White;
+use ruleset::KgsChinese;
+use patterns::Feature;
+use super::Dictionary;
+use super::shape::MIN_DISTANCE;
+use super::shape::PointState;
+use super::shape::Shapes;
+
+use std::path::Path;
+
+fn board() -> Board {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 5, 5)).unwrap();
+    board.play(Play(White, 5, 6)).unwrap();
+    board
+}
+
+fn count(states: &[PointState], state: PointState) -> usize {
+    states.iter().filter(|&&s| s == state).count()
+}
+
+#[test]
+fn the_smallest_shape_is_the_3x3_square() {
+    let shapes = Shapes::new();
+    assert_eq!(8, shapes.size(MIN_DISTANCE));
+    assert_eq!(12, shapes.size(MIN_DISTANCE + 1));
+    assert_eq!(Some(MIN_DISTANCE), shapes.distance_for_size(8));
+    assert_eq!(None, shapes.distance_for_size(9));
+}
+
+#[test]
+fn extract_sees_the_stones_from_the_point_of_view_of_the_player() {
+    let shapes = Shapes::new();
+    let board = board();
+    let black = shapes.extract(&board, &Coord::new(4, 5), Black, MIN_DISTANCE);
+    let white = shapes.extract(&board, &Coord::new(4, 5), White, MIN_DISTANCE);
+    assert_eq!(1, count(&black, PointState::Own));
+    assert_eq!(1, count(&black, PointState::Opponent));
+    let swapped: Vec<PointState> = black.iter()
+        .map(|&s| match s {
+            PointState::Own => PointState::Opponent,
+            PointState::Opponent => PointState::Own,
+            other => other
+        })
+        .collect();
+    assert_eq!(swapped, white);
+}
+
+#[test]
+fn extract_marks_points_off_the_board() {
+    let shapes = Shapes::new();
+    let board = Board::new(9, 6.5, KgsChinese);
+    let states = shapes.extract(&board, &Coord::new(1, 1), Black, MIN_DISTANCE);
+    assert_eq!(5, count(&states, PointState::OffBoard));
+}
+
+#[test]
+fn the_symmetries_keep_the_number_of_stones() {
+    let shapes = Shapes::new();
+    let states = shapes.extract(&board(), &Coord::new(4, 5), Black, 5);
+    for symmetry in shapes.symmetries(&states) {
+        assert_eq!(count(&states, PointState::Own), count(&symmetry, PointState::Own));
+        assert_eq!(count(&states, PointState::Opponent), count(&symmetry, PointState::Opponent));
+    }
+}
+
+#[test]
+fn parse_skips_comments_and_empty_lines() {
+    let dictionary = Dictionary::parse("# comment\n\n2.5 ........\n").unwrap();
+    assert_eq!(1, dictionary.len());
+}
+
+#[test]
+fn parse_reports_the_line_of_an_error() {
+    let result = Dictionary::parse("2.5 ........\n1.0 ...\n");
+    assert_eq!(Err(String::from("line 2: a shape of 3 points doesn't exist")), result.map(|d| d.len()));
+}
+
+#[test]
+fn parse_rejects_invalid_gammas_and_shapes() {
+    assert!(Dictionary::parse("x ........").is_err());
+    assert!(Dictionary::parse("-1.0 ........").is_err());
+    assert!(Dictionary::parse("1.0 .......?").is_err());
+    assert!(Dictionary::parse("1.0").is_err());
+}
+
+#[test]
+fn from_file_reports_missing_files() {
+    assert!(Dictionary::from_file(Path::new("fixtures/patterns/missing.pat")).is_err());
+}
+
+#[test]
+fn from_file_reads_the_patterns() {
+    let dictionary = Dictionary::from_file(Path::new("fixtures/patterns/example.pat")).unwrap();
+    assert_eq!(2, dictionary.len());
+}
+
+#[test]
+fn an_empty_dictionary_matches_nothing() {
+    let dictionary = Dictionary::new();
+    assert_eq!(None, dictionary.gamma(&board(), &Play(Black, 4, 5)));
+}
+
+#[test]
+fn gamma_finds_the_pattern_in_all_orientations() {
+    let shapes = Shapes::new();
+    let board = board();
+    let mut dictionary = Dictionary::new();
+    dictionary.insert(&shapes.extract(&board, &Coord::new(4, 5), Black, 4), 3.0);
+    assert_eq!(Some(3.0), dictionary.gamma(&board, &Play(Black, 4, 5)));
+    // The same shape mirrored at the central column of the board
+    assert_eq!(Some(3.0), dictionary.gamma(&board, &Play(Black, 6, 5)));
+    assert_eq!(None, dictionary.gamma(&board, &Play(White, 4, 5)));
+}
+
+#[test]
+fn gamma_uses_the_largest_matching_pattern() {
+    let shapes = Shapes::new();
+    let board = board();
+    let coord = Coord::new(4, 5);
+    let mut dictionary = Dictionary::new();
+    dictionary.insert(&shapes.extract(&board, &coord, Black, MIN_DISTANCE), 2.0);
+    dictionary.insert(&shapes.extract(&board, &coord, Black, MIN_DISTANCE + 2), 5.0);
+    assert_eq!(Some(5.0), dictionary.gamma(&board, &Play(Black, 4, 5)));
+    let mut other = board.clone();
+    other.play(Play(Black, 2, 5)).unwrap();
+    assert_eq!(Some(2.0), dictionary.gamma(&other, &Play(Black, 4, 5)));
+}
+
+#[test]
+fn gamma_ignores_passes() {
+    let dictionary = Dictionary::parse("2.5 ........").unwrap();
+    assert_eq!(None, dictionary.gamma(&board(), &Pass(Black)));
+}
+
+#[test]
+fn parse_reads_the_feature_gammas() {
+    let dictionary = Dictionary::parse("4.0 capture\n0.5 self-atari\n").unwrap();
+    assert_eq!(0, dictionary.len());
+    assert!(!dictionary.is_empty());
+    assert_eq!(Some(4.0), dictionary.feature_gamma(Feature::Capture));
+    assert_eq!(Some(0.5), dictionary.feature_gamma(Feature::SelfAtari));
+    assert_eq!(None, dictionary.feature_gamma(Feature::Atari));
+}
+
+#[test]
+fn the_canonical_hash_is_the_same_for_all_symmetries() {
+    let shapes = Shapes::new();
+    let board = board();
+    let states = shapes.extract(&board, &Coord::new(4, 5), Black, 4);
+    let hash = shapes.canonical_hash(&states);
+    assert!(shapes.symmetries(&states).iter().all(|symmetry| shapes.canonical_hash(symmetry) == hash));
+}
@@ -0,0 +1,117 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Board;
+use board::Coord;
+use board::Move;
+
+use std::cmp;
+
+mod test;
+
+/// Tactical features of a move. They are learned together with the
+/// large patterns (see `learn-patterns`) and each has its own gamma.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Feature {
+    /// Puts an opponent chain into atari.
+    Atari,
+    /// Captures at least one opponent stone.
+    Capture,
+    /// Next to the last move.
+    Distance2,
+    /// Diagonal to the last move.
+    Distance3,
+    /// Two points away from the last move (on a straight line).
+    Distance4,
+    /// Extends one of our own chains that is in atari.
+    Escape,
+    /// Puts one of our own chains into atari.
+    SelfAtari,
+}
+
+impl Feature {
+
+    pub fn all() -> Vec<Feature> {
+        vec!(
+            Feature::Atari,
+            Feature::Capture,
+            Feature::Distance2,
+            Feature::Distance3,
+            Feature::Distance4,
+            Feature::Escape,
+            Feature::SelfAtari)
+    }
+
+    /// The name of the feature in the pattern files.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Feature::Atari => "atari",
+            Feature::Capture => "capture",
+            Feature::Distance2 => "distance-2",
+            Feature::Distance3 => "distance-3",
+            Feature::Distance4 => "distance-4",
+            Feature::Escape => "escape",
+            Feature::SelfAtari => "self-atari",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Feature> {
+        Self::all().into_iter().find(|feature| feature.name() == name)
+    }
+
+    /// The features of a legal move. `last_move` is the coordinate
+    /// of the previous move (if it wasn't a pass).
+    pub fn of_move(board: &Board, m: &Move, last_move: Option<Coord>) -> Vec<Feature> {
+        let mut features = vec!();
+        if m.is_pass() {
+            return features;
+        }
+        let coord = m.coord();
+        let color = *m.color();
+        let chains: Vec<_> = board.neighbours(coord).iter()
+            .filter_map(|&c| board.get_chain(c))
+            .collect();
+        if chains.iter().any(|chain| chain.color() != color && chain.liberties().len() == 1) {
+            features.push(Feature::Capture);
+        }
+        if chains.iter().any(|chain| chain.color() != color && chain.liberties().len() == 2) {
+            features.push(Feature::Atari);
+        }
+        if chains.iter().any(|chain| chain.color() == color && chain.liberties().len() == 1) {
+            features.push(Feature::Escape);
+        }
+        if !board.is_not_self_atari(m) {
+            features.push(Feature::SelfAtari);
+        }
+        if let Some(last) = last_move {
+            let dx = (coord.col as i16 - last.col as i16).abs();
+            let dy = (coord.row as i16 - last.row as i16).abs();
+            match dx + dy + cmp::max(dx, dy) {
+                2 => features.push(Feature::Distance2),
+                3 => features.push(Feature::Distance3),
+                4 => features.push(Feature::Distance4),
+                _ => {}
+            }
+        }
+        features
+    }
+
+}
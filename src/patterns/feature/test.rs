@@ -0,0 +1,82 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Board;
+use board::Coord;
+use board::Pass;
+use board::Play;
+use board::White;
+use ruleset::KgsChinese;
+use super::Feature;
+
+#[test]
+fn the_names_can_be_parsed() {
+    for feature in Feature::all() {
+        assert_eq!(Some(feature), Feature::from_name(feature.name()));
+    }
+    assert_eq!(None, Feature::from_name("foo"));
+}
+
+#[test]
+fn capture_and_escape() {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 1, 1)).unwrap();
+    board.play(Play(White, 2, 1)).unwrap();
+    board.play(Play(Black, 5, 5)).unwrap();
+    let features = Feature::of_move(&board, &Play(White, 1, 2), None);
+    assert!(features.contains(&Feature::Capture));
+    let features = Feature::of_move(&board, &Play(Black, 1, 2), None);
+    assert!(features.contains(&Feature::Escape));
+}
+
+#[test]
+fn atari_and_self_atari() {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 1, 1)).unwrap();
+    let features = Feature::of_move(&board, &Play(White, 2, 1), None);
+    assert!(features.contains(&Feature::Atari));
+    assert!(!features.contains(&Feature::SelfAtari));
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 2, 1)).unwrap();
+    let features = Feature::of_move(&board, &Play(White, 1, 1), None);
+    assert!(features.contains(&Feature::SelfAtari));
+    assert!(!features.contains(&Feature::Atari));
+}
+
+#[test]
+fn distance_to_the_last_move() {
+    let board = Board::new(9, 6.5, KgsChinese);
+    let last = Some(Coord::new(5, 5));
+    assert_eq!(vec!(Feature::Distance2), Feature::of_move(&board, &Play(Black, 5, 6), last));
+    assert_eq!(vec!(Feature::Distance3), Feature::of_move(&board, &Play(Black, 6, 6), last));
+    assert_eq!(vec!(Feature::Distance4), Feature::of_move(&board, &Play(Black, 5, 7), last));
+    assert!(Feature::of_move(&board, &Play(Black, 6, 7), last).is_empty());
+    assert!(Feature::of_move(&board, &Play(Black, 5, 7), None).is_empty());
+}
+
+#[test]
+fn passes_have_no_features() {
+    let board = Board::new(9, 6.5, KgsChinese);
+    assert!(Feature::of_move(&board, &Pass(Black), Some(Coord::new(5, 5))).is_empty());
+}
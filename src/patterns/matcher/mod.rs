@@ -22,14 +22,23 @@
 pub use self::pattern::Pattern;
 use board::Board;
 use board::Coord;
+use board::Move;
+use config::Config;
+use patterns::Dictionary;
+use patterns::Feature;
+use patterns::dictionary::MIN_DISTANCE;
 use self::tree::Tree;
 
+use std::cmp;
+use std::path::Path;
+
 mod pattern;
 mod point;
 mod test;
 mod tree;
 
 pub struct Matcher {
+    dictionary: Dictionary,
     tree: Tree
 }
 
@@ -39,14 +48,68 @@ impl Matcher {
         Self::with_patterns(Self::expand_patterns(Self::patterns()))
     }
 
+    /// The built-in 3x3 patterns plus the large patterns from the
+    /// file set in the configuration (if any).
+    pub fn from_config(config: &Config) -> Result<Matcher, String> {
+        let mut matcher = Self::new();
+        if !config.patterns.file.is_empty() {
+            matcher.dictionary = try!(Dictionary::from_file(Path::new(&config.patterns.file)));
+        }
+        Ok(matcher)
+    }
+
     fn with_patterns(patterns: Vec<Pattern>) -> Matcher {
-        Matcher { tree: Tree::from_patterns(patterns) }
+        Matcher {
+            dictionary: Dictionary::new(),
+            tree: Tree::from_patterns(patterns)
+        }
     }
 
     pub fn pattern_count(&self, board: &Board, coord: &Coord) -> usize {
         self.tree.pattern_count(board, coord)
     }
 
+    /// The gamma of the largest of the large patterns that matches
+    /// the move.
+    pub fn gamma(&self, board: &Board, m: &Move) -> Option<f32> {
+        self.dictionary.gamma(board, m)
+    }
+
+    /// The product of the gammas of the largest matching pattern and
+    /// the tactical features of the move. Returns `None` if neither
+    /// a pattern nor a feature with a gamma matches.
+    pub fn move_gamma(&self, board: &Board, m: &Move, last_move: Option<Coord>) -> Option<f32> {
+        let pattern = self.gamma(board, m);
+        let features: Vec<f32> = Feature::of_move(board, m, last_move).iter()
+            .filter_map(|&feature| self.dictionary.feature_gamma(feature))
+            .collect();
+        if pattern.is_none() && features.is_empty() {
+            None
+        } else {
+            Some(features.iter().fold(pattern.unwrap_or(1.0), |acc, gamma| acc * gamma))
+        }
+    }
+
+    /// The learned gamma of the tactical feature, if the pattern file
+    /// contains one.
+    pub fn feature_gamma(&self, feature: Feature) -> Option<f32> {
+        self.dictionary.feature_gamma(feature)
+    }
+
+    /// The largest distance from a move at which a stone can change
+    /// the pattern of the move.
+    pub fn pattern_distance(&self) -> u8 {
+        cmp::max(MIN_DISTANCE, self.dictionary.max_distance())
+    }
+
+    pub fn has_large_patterns(&self) -> bool {
+        !self.dictionary.is_empty()
+    }
+
+    pub fn large_pattern_count(&self) -> usize {
+        self.dictionary.len()
+    }
+
     fn expand_patterns(patterns: Vec<Pattern>) -> Vec<Pattern> {
         patterns.iter().flat_map(|pattern| pattern.expand()).collect()
     }
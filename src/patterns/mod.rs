@@ -19,6 +19,10 @@
  *                                                                      *
  ************************************************************************/
 
+pub use self::dictionary::Dictionary;
+pub use self::feature::Feature;
 pub use self::matcher::Matcher;
 
+pub mod dictionary;
+mod feature;
 mod matcher;
@@ -27,8 +27,11 @@ use board::Move;
 use board::Pass;
 use board::Play;
 use config::Config;
+use config::PlayoutPolicy;
+use patterns::Feature;
 use patterns::Matcher;
 use score::Score;
+use self::weights::Weights;
 
 use rand::Rng;
 use rand::XorShiftRng;
@@ -37,18 +40,35 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 mod test;
+mod weights;
 
 const ATARI_CUTOFF: usize = 7;
 
 pub struct Playout {
     config: Arc<Config>,
+    // The gammas of the tactical features for the weighted policy,
+    // in the order of `Feature::all()`.
+    feature_gammas: Vec<f32>,
     matcher: Arc<Matcher>
 }
 
 impl Playout {
 
     pub fn new(config: Arc<Config>, matcher: Arc<Matcher>) -> Playout {
-        Playout { config: config, matcher: matcher }
+        let feature_gammas = Feature::all().iter()
+            .map(|&feature| matcher.feature_gamma(feature).unwrap_or(Self::default_gamma(&config, feature)))
+            .collect();
+        Playout { config: config, feature_gammas: feature_gammas, matcher: matcher }
+    }
+
+    fn default_gamma(config: &Config, feature: Feature) -> f32 {
+        match feature {
+            Feature::Atari => config.playout.weighted_atari,
+            Feature::Capture => config.playout.weighted_capture,
+            Feature::Distance2 | Feature::Distance3 | Feature::Distance4 => config.playout.weighted_distance,
+            Feature::Escape => config.playout.weighted_escape,
+            Feature::SelfAtari => config.playout.weighted_self_atari,
+        }
     }
 
     pub fn run(&self, board: &mut Board, initial_move: Option<&Move>, rng: &mut XorShiftRng) -> PlayoutResult {
@@ -62,11 +82,28 @@ impl Playout {
             }
         });
 
+        let mut weights = match self.config.playout.policy {
+            PlayoutPolicy::Cascade => None,
+            PlayoutPolicy::Weighted => {
+                let mut weights = Weights::new(board.size(), self.matcher.pattern_distance());
+                weights.mark_all(board);
+                Some(weights)
+            }
+        };
         let max_moves = self.max_moves(board.size());
         while !board.is_game_over() && played_moves.len() < max_moves {
-            let heuristic_set = self.heuristic_set(&played_moves, board, rng);
-            let m = self.select_move(board, heuristic_set, rng);
+            let last_move = played_moves.last().and_then(|m| if m.is_pass() { None } else { Some(m.coord()) });
+            let m = match weights {
+                Some(ref mut weights) => self.weighted_move(board, weights, last_move, rng),
+                None => {
+                    let heuristic_set = self.heuristic_set(&played_moves, board, rng);
+                    self.select_move(board, heuristic_set, last_move, rng)
+                }
+            };
             board.play_legal_move(m);
+            if let Some(ref mut weights) = weights {
+                weights.played(board, &m);
+            }
             played_moves.push(m);
             if !m.is_pass() && !amaf.contains_key(&m.coord()) {
                 amaf.insert(m.coord(), *m.color());
@@ -108,7 +145,7 @@ impl Playout {
         coords
     }
 
-    fn select_move(&self, board: &Board, heuristic_set: Vec<Coord>, rng: &mut XorShiftRng) -> Move {
+    fn select_move(&self, board: &Board, heuristic_set: Vec<Coord>, last_move: Option<Coord>, rng: &mut XorShiftRng) -> Move {
         let color = board.next_player();
 
         if self.check_for_atari(rng) {
@@ -117,8 +154,32 @@ impl Playout {
                 return possible_move.unwrap();
             }
         }
+        if self.check_for_captures(rng) {
+            let possible_move = self.random_choice(board.capturing_moves(color), rng);
+            if possible_move.is_some() {
+                return possible_move.unwrap();
+            }
+        }
+        if let Some(coord) = last_move {
+            if self.check_for_nakade(rng) {
+                let possible_move = self.nakade_move(color, coord, board);
+                if possible_move.is_some() {
+                    return possible_move.unwrap();
+                }
+            }
+            if self.check_for_two_liberties(rng) {
+                let possible_move = self.random_choice(board.two_liberty_moves(color, coord), rng);
+                if possible_move.is_some() {
+                    return possible_move.unwrap();
+                }
+            }
+        }
         if self.use_patterns(rng) {
-            let possible_move = self.pattern_move(color, heuristic_set, board);
+            let possible_move = if self.config.playout.pattern_weights && self.matcher.has_large_patterns() {
+                self.weighted_pattern_move(color, heuristic_set, last_move, board, rng)
+            } else {
+                self.pattern_move(color, heuristic_set, board)
+            };
             if possible_move.is_some() {
                 return possible_move.unwrap();
             }
@@ -126,6 +187,82 @@ impl Playout {
         self.random_move(color, board, rng)
     }
 
+    // Updates the dirty weights of the player to move and samples a
+    // move. The points around the last move are boosted only while
+    // sampling as they change with every move.
+    fn weighted_move(&self, board: &Board, weights: &mut Weights, last_move: Option<Coord>, rng: &mut XorShiftRng) -> Move {
+        let color = board.next_player();
+        for coord in weights.dirty(color) {
+            let weight = self.weight(board, &Play(color, coord.col, coord.row));
+            weights.set(color, &coord, weight);
+        }
+        let mut boosted = vec!();
+        if let Some(coord) = last_move {
+            for (c, gamma) in self.distance_gammas(board, coord) {
+                let weight = weights.get(color, &c);
+                if weight > 0.0 {
+                    weights.set(color, &c, weight * gamma);
+                    boosted.push((c, weight));
+                }
+            }
+        }
+        let mut m = Pass(color);
+        while let Some(coord) = weights.sample(color, rng) {
+            let candidate = Play(color, coord.col, coord.row);
+            if board.is_legal(candidate).is_ok() {
+                m = candidate;
+                break;
+            }
+            // Shouldn't happen, but an illegal move would corrupt the
+            // board.
+            weights.set(color, &coord, 0.0);
+        }
+        for (c, weight) in boosted {
+            if weights.get(color, &c) > 0.0 {
+                weights.set(color, &c, weight);
+            }
+        }
+        m
+    }
+
+    // The weight of a move for the weighted policy: the gamma of the
+    // pattern times the gammas of the tactical features. Moves that
+    // aren't playable have a weight of 0.
+    fn weight(&self, board: &Board, m: &Move) -> f32 {
+        if board.is_legal(*m).is_err() || !self.is_playable(board, m) {
+            return 0.0;
+        }
+        let pattern = match self.matcher.gamma(board, m) {
+            Some(gamma) => gamma,
+            None => if self.matches(board, m) { self.config.playout.weighted_pattern } else { 1.0 }
+        };
+        Feature::of_move(board, m, None).iter()
+            .fold(pattern, |acc, &feature| acc * self.feature_gamma(feature))
+    }
+
+    fn feature_gamma(&self, feature: Feature) -> f32 {
+        let index = Feature::all().iter().position(|&f| f == feature).unwrap();
+        self.feature_gammas[index]
+    }
+
+    // The points close to the last move with the gammas of their
+    // distance feature.
+    fn distance_gammas(&self, board: &Board, coord: Coord) -> Vec<(Coord, f32)> {
+        let size = board.size() as i16;
+        let mut gammas: Vec<(Coord, f32)> = board.neighbours(coord).iter()
+            .map(|&c| (c, self.feature_gamma(Feature::Distance2)))
+            .chain(board.diagonals(coord).iter().map(|&c| (c, self.feature_gamma(Feature::Distance3))))
+            .collect();
+        for &(dx, dy) in [(-2, 0), (2, 0), (0, -2), (0, 2)].iter() {
+            let col = coord.col as i16 + dx;
+            let row = coord.row as i16 + dy;
+            if col >= 1 && row >= 1 && col <= size && row <= size {
+                gammas.push((Coord::new(col as u8, row as u8), self.feature_gamma(Feature::Distance4)));
+            }
+        }
+        gammas
+    }
+
     // If own group of more than one stone has one liberty, check if it can be captured
     fn atari_move(&self, color: Color, board: &Board, rng: &mut XorShiftRng) -> Option<Move> {
         let mut in_danger = board.chains().iter()
@@ -150,6 +287,21 @@ impl Playout {
         }
     }
 
+    fn nakade_move(&self, color: Color, coord: Coord, board: &Board) -> Option<Move> {
+        board.nakade_point(coord)
+            .map(|c| Play(color, c.col, c.row))
+            .and_then(|m| if board.is_legal(m).is_ok() { Some(m) } else { None })
+    }
+
+    fn random_choice(&self, moves: Vec<Move>, rng: &mut XorShiftRng) -> Option<Move> {
+        if moves.len() > 0 {
+            let random = rng.gen::<usize>() % moves.len();
+            Some(moves[random])
+        } else {
+            None
+        }
+    }
+
     fn pattern_move(&self, color: Color, coords: Vec<Coord>, board: &Board) -> Option<Move> {
         // This works as coords is randomly ordered, so taking the
         // first we find is OK.
@@ -160,6 +312,33 @@ impl Playout {
             })
     }
 
+    // Picks one of the legal moves that match a pattern at random.
+    // The gamma of the largest matching pattern (and the tactical
+    // features) is used as the weight, or 1.0 if only one of the 3x3
+    // patterns matches.
+    fn weighted_pattern_move(&self, color: Color, coords: Vec<Coord>, last_move: Option<Coord>, board: &Board, rng: &mut XorShiftRng) -> Option<Move> {
+        let candidates: Vec<(Move, f32)> = coords.iter()
+            .map(|c| Play(color, c.col, c.row))
+            .filter(|&m| board.is_legal(m).is_ok())
+            .filter_map(|m| match self.matcher.move_gamma(board, &m, last_move) {
+                Some(gamma) => Some((m, gamma)),
+                None => if self.matches(board, &m) { Some((m, 1.0)) } else { None }
+            })
+            .collect();
+        let total = candidates.iter().fold(0.0, |acc, &(_, gamma)| acc + gamma);
+        if total == 0.0 {
+            return None;
+        }
+        let mut r = rng.gen_range(0f32, total);
+        for &(m, gamma) in candidates.iter() {
+            if r < gamma {
+                return Some(m);
+            }
+            r -= gamma;
+        }
+        candidates.last().map(|&(m, _)| m)
+    }
+
     fn matches(&self, board: &Board, m: &Move) -> bool {
         self.matcher.pattern_count(board, &m.coord()) > 0
     }
@@ -205,6 +384,18 @@ impl Playout {
         rng.gen_range(0f32, 1f32) <= self.config.playout.atari_check
     }
 
+    fn check_for_captures(&self, rng: &mut XorShiftRng) -> bool {
+        rng.gen_range(0f32, 1f32) <= self.config.playout.capture_probability
+    }
+
+    fn check_for_nakade(&self, rng: &mut XorShiftRng) -> bool {
+        rng.gen_range(0f32, 1f32) <= self.config.playout.nakade_probability
+    }
+
+    fn check_for_two_liberties(&self, rng: &mut XorShiftRng) -> bool {
+        rng.gen_range(0f32, 1f32) <= self.config.playout.two_liberties_probability
+    }
+
     fn use_patterns(&self, rng: &mut XorShiftRng) -> bool {
         rng.gen_range(0f32, 1f32) <= self.config.playout.pattern_probability
     }
@@ -229,13 +420,6 @@ impl PlayoutResult {
         }
     }
 
-    pub fn empty() -> PlayoutResult {
-        PlayoutResult {
-            amaf: HashMap::new(),
-            score: Score::empty(),
-        }
-    }
-
     pub fn winner(&self) -> Color {
         self.score.color()
     }
@@ -23,13 +23,19 @@
 
 use board::Black;
 use board::Board;
+use board::Coord;
 use board::Play;
+use board::White;
 use config::Config;
+use config::PlayoutPolicy;
 use patterns::Matcher;
 use ruleset::KgsChinese;
+use sgf::Parser;
 use super::Playout;
+use super::weights::Weights;
 
 use rand::weak_rng;
+use std::path::Path;
 use std::sync::Arc;
 use test::Bencher;
 
@@ -46,6 +52,124 @@ fn max_moves() {
     assert_eq!(1083, playout(Arc::new(Matcher::new())).max_moves(19));
 }
 
+#[test]
+fn weighted_pattern_move_only_plays_matching_moves() {
+    let mut config = Config::test_config();
+    config.patterns.file = String::from("fixtures/patterns/example.pat");
+    let config = Arc::new(config);
+    let matcher = Arc::new(Matcher::from_config(&config).unwrap());
+    let playout = Playout::new(config, matcher);
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    board.play(Play(Black, 6, 5)).unwrap();
+    board.play(Play(White, 4, 5)).unwrap();
+    let mut rng = weak_rng();
+    let coords = vec!(Coord::new(5, 5), Coord::new(8, 8));
+    for _ in 0..10 {
+        let m = playout.weighted_pattern_move(Black, coords.clone(), None, &board, &mut rng);
+        assert_eq!(Some(Play(Black, 5, 5)), m);
+    }
+}
+
+// Only the given heuristic is used (besides the random moves).
+fn heuristic_playout(probability: &Fn(&mut Config)) -> Playout {
+    let mut config = Config::test_config();
+    config.playout.atari_check = 0.0;
+    config.playout.capture_probability = 0.0;
+    config.playout.nakade_probability = 0.0;
+    config.playout.pattern_probability = 0.0;
+    config.playout.two_liberties_probability = 0.0;
+    probability(&mut config);
+    Playout::new(Arc::new(config), Arc::new(Matcher::new()))
+}
+
+fn fixture(path: &str) -> Board {
+    Parser::from_path(Path::new(path)).unwrap().game().unwrap().board()
+}
+
+#[test]
+fn select_move_captures_chains_in_atari() {
+    let playout = heuristic_playout(&|config| config.playout.capture_probability = 1.0);
+    let board = fixture("fixtures/sgf/tactics/capture.sgf");
+    let mut rng = weak_rng();
+    assert_eq!(Play(Black, 5, 5), playout.select_move(&board, vec!(), None, &mut rng));
+}
+
+#[test]
+fn select_move_plays_the_vital_point_of_a_nakade() {
+    let playout = heuristic_playout(&|config| config.playout.nakade_probability = 1.0);
+    let board = fixture("fixtures/sgf/nakade/bulky-five.sgf");
+    let mut rng = weak_rng();
+    assert_eq!(Play(Black, 2, 1), playout.select_move(&board, vec!(), Some(Coord::new(3, 2)), &mut rng));
+}
+
+#[test]
+fn select_move_attacks_chains_with_two_liberties() {
+    let playout = heuristic_playout(&|config| config.playout.two_liberties_probability = 1.0);
+    let board = fixture("fixtures/sgf/tactics/two-liberties.sgf");
+    let mut rng = weak_rng();
+    let expected = vec!(Play(Black, 3, 1), Play(Black, 4, 2), Play(Black, 5, 2));
+    for _ in 0..10 {
+        assert!(expected.contains(&playout.select_move(&board, vec!(), Some(Coord::new(5, 1)), &mut rng)));
+    }
+}
+
+fn weighted_config() -> Config {
+    let mut config = Config::test_config();
+    config.playout.policy = PlayoutPolicy::Weighted;
+    config
+}
+
+#[test]
+fn the_weighted_policy_plays_until_the_end_of_the_game() {
+    let playout = Playout::new(Arc::new(weighted_config()), Arc::new(Matcher::new()));
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    let mut rng = weak_rng();
+    playout.run(&mut board, Some(&Play(Black, 5, 5)), &mut rng);
+    assert!(board.is_game_over() || board.vacant_point_count() < 20);
+}
+
+#[test]
+fn weighted_move_prefers_captures() {
+    let mut config = weighted_config();
+    config.playout.weighted_capture = 1e9;
+    let playout = Playout::new(Arc::new(config), Arc::new(Matcher::new()));
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    for &m in [Play(Black, 4, 5), Play(White, 5, 5), Play(Black, 6, 5), Play(White, 1, 9), Play(Black, 5, 4), Play(White, 9, 1)].iter() {
+        board.play(m).unwrap();
+    }
+    let mut rng = weak_rng();
+    let mut weights = Weights::new(9, 3);
+    weights.mark_all(&board);
+    for _ in 0..10 {
+        let m = playout.weighted_move(&board, &mut weights, Some(Coord::new(9, 1)), &mut rng);
+        assert_eq!(Play(Black, 5, 6), m);
+    }
+}
+
+#[test]
+fn the_incremental_weights_match_the_computed_ones() {
+    let playout = Playout::new(Arc::new(weighted_config()), Arc::new(Matcher::new()));
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    let mut rng = weak_rng();
+    let mut weights = Weights::new(9, 3);
+    weights.mark_all(&board);
+    let mut last_move = None;
+    while !board.is_game_over() && board.vacant_point_count() > 10 {
+        let m = playout.weighted_move(&board, &mut weights, last_move, &mut rng);
+        board.play_legal_move(m);
+        weights.played(&board, &m);
+        last_move = if m.is_pass() { None } else { Some(m.coord()) };
+        let color = board.next_player();
+        for coord in weights.dirty(color) {
+            weights.set(color, &coord, playout.weight(&board, &Play(color, coord.col, coord.row)));
+        }
+        for coord in Coord::for_board_size(9) {
+            let expected = playout.weight(&board, &Play(color, coord.col, coord.row));
+            assert_eq!(expected, weights.get(color, &coord));
+        }
+    }
+}
+
 #[bench]
 fn playout_09x09(b: &mut Bencher) {
     let board = Board::new(9, 6.5, KgsChinese);
@@ -81,3 +205,14 @@ fn playout_19x19(b: &mut Bencher) {
         playout.run(&mut b, Some(&Play(Black, 1, 1)), &mut rng)
     });
 }
+
+#[bench]
+fn weighted_playout_09x09(b: &mut Bencher) {
+    let board = Board::new(9, 6.5, KgsChinese);
+    let playout = Playout::new(Arc::new(weighted_config()), Arc::new(Matcher::new()));
+    let mut rng = weak_rng();
+    b.iter(|| {
+        let mut b = board.clone();
+        playout.run(&mut b, Some(&Play(Black, 1, 1)), &mut rng)
+    });
+}
@@ -0,0 +1,222 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Black;
+use board::Board;
+use board::Color;
+use board::Coord;
+use board::Empty;
+use board::Move;
+use board::White;
+
+use rand::Rng;
+use rand::XorShiftRng;
+use std::cmp;
+use std::mem;
+
+mod test;
+
+// The weights of all points for one player, with the sums of the
+// rows to speed up sampling.
+struct ColorWeights {
+    dirty: Vec<Coord>,
+    is_dirty: Vec<bool>,
+    rows: Vec<f32>,
+    total: f32,
+    weights: Vec<f32>,
+}
+
+impl ColorWeights {
+
+    fn new(size: u8) -> ColorWeights {
+        let points = size as usize * size as usize;
+        ColorWeights {
+            dirty: vec!(),
+            is_dirty: vec!(false; points),
+            rows: vec!(0.0; size as usize),
+            total: 0.0,
+            weights: vec!(0.0; points),
+        }
+    }
+
+}
+
+/// The weights of the points of the board for the weighted playout
+/// policy. The weights are kept for both players as the patterns
+/// and features depend on the player to move. After each move the
+/// points whose weight may have changed are marked as dirty; they
+/// are recomputed (by the playout) only when it's the turn of the
+/// player again.
+pub struct Weights {
+    black: ColorWeights,
+    captured: Vec<Coord>,
+    offsets: Vec<(i16, i16)>,
+    size: u8,
+    white: ColorWeights,
+}
+
+impl Weights {
+
+    /// A stone changes the weights of all points up to `distance`
+    /// away (measured as the distance of the patterns).
+    pub fn new(size: u8, distance: u8) -> Weights {
+        let d = distance as i16;
+        let mut offsets = vec!();
+        for dx in -d..d+1 {
+            for dy in -d..d+1 {
+                if dx.abs() + dy.abs() + cmp::max(dx.abs(), dy.abs()) <= d {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        Weights {
+            black: ColorWeights::new(size),
+            captured: vec!(),
+            offsets: offsets,
+            size: size,
+            white: ColorWeights::new(size),
+        }
+    }
+
+    /// Marks all empty points as dirty.
+    pub fn mark_all(&mut self, board: &Board) {
+        for &coord in board.vacant() {
+            self.mark(coord);
+        }
+    }
+
+    /// Marks the points that are affected by the move: the points
+    /// around the move and the captured stones and the liberties of
+    /// all chains that gained or lost a liberty.
+    pub fn played(&mut self, board: &Board, m: &Move) {
+        // The stone captured by the previous move may have been a ko
+        // that can be retaken now.
+        let previous = mem::replace(&mut self.captured, vec!());
+        for coord in previous {
+            self.mark(coord);
+        }
+        if m.is_pass() || m.is_resign() {
+            return;
+        }
+        let coord = m.coord();
+        self.captured = board.adv_stones_removed().clone();
+        let mut changed = vec!(coord);
+        changed.extend(self.captured.iter().cloned());
+        if board.color(&coord) == Empty {
+            changed.extend(board.friend_stones_removed().iter().cloned());
+        }
+        for &c in changed.iter() {
+            self.mark_around(c);
+            for &neighbour in board.neighbours(c).iter().chain(Some(c).iter()) {
+                if let Some(chain) = board.get_chain(neighbour) {
+                    for &liberty in chain.liberties() {
+                        self.mark(liberty);
+                    }
+                }
+            }
+        }
+    }
+
+    fn mark_around(&mut self, coord: Coord) {
+        let size = self.size as i16;
+        for i in 0..self.offsets.len() {
+            let (dx, dy) = self.offsets[i];
+            let col = coord.col as i16 + dx;
+            let row = coord.row as i16 + dy;
+            if col >= 1 && row >= 1 && col <= size && row <= size {
+                self.mark(Coord::new(col as u8, row as u8));
+            }
+        }
+    }
+
+    fn mark(&mut self, coord: Coord) {
+        let index = coord.to_index(self.size);
+        for weights in vec!(&mut self.black, &mut self.white) {
+            if !weights.is_dirty[index] {
+                weights.is_dirty[index] = true;
+                weights.dirty.push(coord);
+            }
+        }
+    }
+
+    /// Removes and returns the dirty points of the player.
+    pub fn dirty(&mut self, color: Color) -> Vec<Coord> {
+        let size = self.size;
+        let weights = self.of_mut(color);
+        for coord in weights.dirty.iter() {
+            weights.is_dirty[coord.to_index(size)] = false;
+        }
+        mem::replace(&mut weights.dirty, vec!())
+    }
+
+    pub fn get(&self, color: Color, coord: &Coord) -> f32 {
+        self.of(color).weights[coord.to_index(self.size)]
+    }
+
+    pub fn set(&mut self, color: Color, coord: &Coord, weight: f32) {
+        let size = self.size as usize;
+        let index = coord.to_index(self.size);
+        let row = coord.row as usize - 1;
+        let weights = self.of_mut(color);
+        weights.weights[index] = weight;
+        weights.rows[row] = weights.weights[row * size..(row + 1) * size].iter().fold(0.0, |acc, w| acc + w);
+        weights.total = weights.rows.iter().fold(0.0, |acc, w| acc + w);
+    }
+
+    /// Picks a point at random with the weights as the
+    /// probabilities. Returns `None` if all weights are 0.
+    pub fn sample(&self, color: Color, rng: &mut XorShiftRng) -> Option<Coord> {
+        let size = self.size as usize;
+        let weights = self.of(color);
+        if weights.total <= 0.0 {
+            return None;
+        }
+        let mut r = rng.gen_range(0f32, weights.total);
+        let row = match weights.rows.iter().position(|&sum| if r < sum { true } else { r -= sum; false }) {
+            Some(row) => row,
+            // Rounding errors may leave r above the sum of the rows.
+            None => weights.rows.iter().rposition(|&sum| sum > 0.0).unwrap()
+        };
+        let points = &weights.weights[row * size..(row + 1) * size];
+        let col = match points.iter().position(|&weight| if r < weight { true } else { r -= weight; false }) {
+            Some(col) => col,
+            None => points.iter().rposition(|&weight| weight > 0.0).unwrap()
+        };
+        Some(Coord::new(col as u8 + 1, row as u8 + 1))
+    }
+
+    fn of(&self, color: Color) -> &ColorWeights {
+        match color {
+            Black => &self.black,
+            White => &self.white,
+            Empty => panic!("There are no weights for empty points")
+        }
+    }
+
+    fn of_mut(&mut self, color: Color) -> &mut ColorWeights {
+        match color {
+            Black => &mut self.black,
+            White => &mut self.white,
+            Empty => panic!("There are no weights for empty points")
+        }
+    }
+
+}
@@ -0,0 +1,135 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Board;
+use board::Coord;
+use board::Play;
+use board::White;
+use ruleset::KgsChinese;
+use super::Weights;
+
+use rand::SeedableRng;
+use rand::XorShiftRng;
+
+fn rng() -> XorShiftRng {
+    XorShiftRng::from_seed([1, 2, 3, 4])
+}
+
+#[test]
+fn all_empty_points_are_dirty_at_the_start() {
+    let board = Board::new(9, 6.5, KgsChinese);
+    let mut weights = Weights::new(9, 3);
+    weights.mark_all(&board);
+    assert_eq!(81, weights.dirty(Black).len());
+    assert_eq!(81, weights.dirty(White).len());
+    // The dirty points are only returned once.
+    assert_eq!(0, weights.dirty(Black).len());
+}
+
+#[test]
+fn set_replaces_the_weight() {
+    let mut weights = Weights::new(9, 3);
+    weights.set(Black, &Coord::new(1, 1), 2.0);
+    weights.set(Black, &Coord::new(5, 9), 3.0);
+    weights.set(Black, &Coord::new(1, 1), 0.0);
+    assert_eq!(0.0, weights.get(Black, &Coord::new(1, 1)));
+    assert_eq!(Some(Coord::new(5, 9)), weights.sample(Black, &mut rng()));
+    assert_eq!(None, weights.sample(White, &mut rng()));
+}
+
+#[test]
+fn sample_returns_none_without_weights() {
+    let weights = Weights::new(9, 3);
+    assert_eq!(None, weights.sample(Black, &mut rng()));
+}
+
+#[test]
+fn sample_only_picks_points_with_a_weight() {
+    let mut weights = Weights::new(9, 3);
+    weights.set(White, &Coord::new(2, 3), 1.0);
+    weights.set(White, &Coord::new(7, 8), 1.0);
+    let mut rng = rng();
+    for _ in 0..100 {
+        let coord = weights.sample(White, &mut rng).unwrap();
+        assert!(coord == Coord::new(2, 3) || coord == Coord::new(7, 8));
+    }
+}
+
+#[test]
+fn sample_follows_the_weights() {
+    let mut weights = Weights::new(9, 3);
+    weights.set(Black, &Coord::new(2, 3), 1.0);
+    weights.set(Black, &Coord::new(7, 8), 9.0);
+    let mut rng = rng();
+    let count = (0..1000)
+        .filter(|_| weights.sample(Black, &mut rng) == Some(Coord::new(7, 8)))
+        .count();
+    assert!(count > 850 && count < 950, "count: {}", count);
+}
+
+#[test]
+fn played_marks_the_points_around_the_move() {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    let mut weights = Weights::new(9, 3);
+    board.play(Play(Black, 5, 5)).unwrap();
+    weights.played(&board, &Play(Black, 5, 5));
+    let dirty = weights.dirty(White);
+    // The 3x3 square around the move
+    assert_eq!(9, dirty.len());
+    assert!(dirty.contains(&Coord::new(4, 4)));
+    assert!(dirty.contains(&Coord::new(6, 6)));
+}
+
+#[test]
+fn played_marks_the_liberties_of_the_chains_next_to_the_move() {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    let mut weights = Weights::new(9, 3);
+    for &m in [Play(Black, 2, 5), Play(White, 3, 5), Play(Black, 9, 1), Play(White, 3, 6), Play(Black, 9, 2), Play(White, 3, 7)].iter() {
+        board.play(m).unwrap();
+    }
+    board.play(Play(Black, 4, 5)).unwrap();
+    weights.played(&board, &Play(Black, 4, 5));
+    let dirty = weights.dirty(Black);
+    // The liberties of the white chain are further away from the
+    // move than the size of the patterns.
+    assert!(dirty.contains(&Coord::new(3, 8)));
+    assert!(dirty.contains(&Coord::new(2, 7)));
+    assert!(!dirty.contains(&Coord::new(1, 5)));
+}
+
+#[test]
+fn played_marks_the_captured_stones_again_after_the_next_move() {
+    let mut board = Board::new(9, 6.5, KgsChinese);
+    let mut weights = Weights::new(9, 3);
+    for &m in [Play(Black, 2, 1), Play(White, 3, 1), Play(Black, 1, 2), Play(White, 2, 2), Play(Black, 9, 9), Play(White, 1, 1)].iter() {
+        board.play(m).unwrap();
+        weights.played(&board, &m);
+    }
+    assert_eq!(vec!(Coord::new(2, 1)), *board.adv_stones_removed());
+    weights.dirty(Black);
+    weights.dirty(White);
+    board.play(Play(Black, 9, 8)).unwrap();
+    weights.played(&board, &Play(Black, 9, 8));
+    assert!(weights.dirty(White).contains(&Coord::new(2, 1)));
+}
@@ -22,6 +22,7 @@
 
 pub use self::Ruleset::AnySizeTrompTaylor;
 pub use self::Ruleset::CGOS;
+pub use self::Ruleset::Japanese;
 pub use self::Ruleset::KgsChinese;
 pub use self::Ruleset::Minimal;
 
@@ -34,6 +35,7 @@ mod test;
 pub enum Ruleset {
     AnySizeTrompTaylor,
     CGOS,
+    Japanese,
     KgsChinese,
     Minimal,
 }
@@ -61,6 +63,35 @@ impl Ruleset {
             _ => false
         }
     }
+
+    /// Whether the score is counted as territory plus prisoners
+    /// instead of territory plus stones on the board.
+    pub fn territory_scoring(&self) -> bool {
+        match *self {
+            Japanese => true,
+            _ => false
+        }
+    }
+
+    /// Whether the dead stones are taken off the board before
+    /// counting. Otherwise they have to be captured.
+    pub fn removes_dead_stones(&self) -> bool {
+        match *self {
+            Japanese   => true,
+            KgsChinese => true,
+            _ => false
+        }
+    }
+
+    /// The number of points white receives for the handicap stones
+    /// of black. On KGS white gets one point per handicap stone when
+    /// playing under Chinese rules.
+    pub fn handicap_compensation(&self, handicap: u16) -> f32 {
+        match *self {
+            KgsChinese => handicap as f32,
+            _ => 0.0
+        }
+    }
 }
 
 impl FromStr for Ruleset {
@@ -72,6 +103,7 @@ impl FromStr for Ruleset {
             "tromp-taylor" => Ok(AnySizeTrompTaylor),
             "cgos"         => Ok(CGOS),
             "chinese"      => Ok(KgsChinese),
+            "japanese"     => Ok(Japanese),
             "minimal"      => Ok(Minimal),
             _              => Err(format!("Unknown ruleset '{}'", s)),
         }
@@ -85,6 +117,7 @@ impl fmt::Display for Ruleset {
         let s = match *self {
             AnySizeTrompTaylor => "tromp-taylor",
             CGOS => "cgos",
+            Japanese => "japanese",
             KgsChinese => "chinese",
             Minimal => "minimal"
         };
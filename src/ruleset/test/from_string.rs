@@ -23,6 +23,7 @@
 
 use ruleset::AnySizeTrompTaylor;
 use ruleset::CGOS;
+use ruleset::Japanese;
 use ruleset::KgsChinese;
 use ruleset::Minimal;
 use ruleset::Ruleset;
@@ -44,6 +45,11 @@ fn parses_chinese() {
     assert_eq!(Ok(KgsChinese), Ruleset::from_str("chinese"));
 }
 
+#[test]
+fn parses_japanese() {
+    assert_eq!(Ok(Japanese), Ruleset::from_str("japanese"));
+}
+
 #[test]
 fn parses_minimal() {
     assert_eq!(Ok(Minimal), Ruleset::from_str("minimal"));
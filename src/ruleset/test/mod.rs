@@ -24,6 +24,7 @@
 
 use super::AnySizeTrompTaylor;
 use super::CGOS;
+use super::Japanese;
 use super::KgsChinese;
 use super::Minimal;
 
@@ -59,6 +60,31 @@ fn cgos_forbids_game_over_play() {
     assert_eq!(false, CGOS.game_over_play());
 }
 
+#[test]
+fn japanese_forbids_suicide() {
+    assert_eq!(false, Japanese.suicide_allowed());
+}
+
+#[test]
+fn japanese_forbids_a_player_playing_twice() {
+    assert_eq!(false, Japanese.same_player());
+}
+
+#[test]
+fn japanese_forbids_game_over_play() {
+    assert_eq!(false, Japanese.game_over_play());
+}
+
+#[test]
+fn japanese_uses_territory_scoring() {
+    assert_eq!(true, Japanese.territory_scoring());
+}
+
+#[test]
+fn kgs_chinese_uses_area_scoring() {
+    assert_eq!(false, KgsChinese.territory_scoring());
+}
+
 #[test]
 fn kgs_chinese_forbids_suicide() {
     assert_eq!(false, KgsChinese.suicide_allowed());
@@ -20,55 +20,140 @@
  ************************************************************************/
 
 use board::Board;
+use board::Color;
 use board::Coord;
 use board::Empty;
-use config::Config;
 use game::Game;
+use life_and_death::Solver;
+use life_and_death::Status;
 use ownership::OwnershipStatistics;
+use ruleset::Japanese;
 use ruleset::KgsChinese;
+use score::Score;
 
-use std::sync::Arc;
+use std::collections::HashMap;
 
 mod test;
 
+// The number of positions the life and death solver may visit per
+// search when scoring. Scoring has to be fast and the regions left
+// at the end of a game are usually simple.
+const MAX_NODES: usize = 2000;
+
 pub struct FinalScore {
     board: Board,
     decided: bool,
     dead: Vec<Coord>,
+    seki: Vec<Coord>,
 }
 
 impl FinalScore {
 
-    pub fn new(config: Arc<Config>, game: &Game, ownership: &OwnershipStatistics) -> FinalScore {
+    pub fn new(game: &Game, ownership: &OwnershipStatistics) -> FinalScore {
+        Self::with_solver(game, ownership, true)
+    }
+
+    /// Like `new`, but the life and death of the chains that aren't
+    /// pass-alive is only judged by the ownership statistics. Fast
+    /// enough to decide after every search whether the game is over.
+    pub fn without_solver(game: &Game, ownership: &OwnershipStatistics) -> FinalScore {
+        Self::with_solver(game, ownership, false)
+    }
+
+    fn with_solver(game: &Game, ownership: &OwnershipStatistics, solve: bool) -> FinalScore {
         let mut board = game.board();
-        let dead = Coord::for_board_size(board.size()).iter()
-            .filter(|c| board.color(c) != Empty)
-            .filter(|c| ownership.owner(c) != Empty)
-            .filter(|c| ownership.owner(c) != board.color(c))
-            .cloned()
+        // The outcome is known if the whole board is pass-alive.
+        let pass_alive = board.pass_alive();
+        let settled = pass_alive.iter().all(|&owner| owner != Empty);
+        let statuses = Self::statuses(&board, &pass_alive, solve);
+        // A dead chain (e.g. one that was killed by a nakade) isn't
+        // in seki even though it shares liberties with the opponent.
+        let mut seki: Vec<Coord> = board.stones_in_seki().into_iter()
+            .filter(|c| !statuses.contains(&(*c, Status::Dead)))
             .collect();
-        if config.ruleset == KgsChinese {
+        for &(coord, status) in statuses.iter() {
+            if status == Status::Seki && !seki.contains(&coord) {
+                seki.push(coord);
+            }
+        }
+        let dead = statuses.iter()
+            .filter(|&&(c, _)| !seki.contains(&c))
+            .filter(|&&(c, status)| match status {
+                Status::Dead => true,
+                Status::Alive => false,
+                _ => ownership.owner(&c) != Empty && ownership.owner(&c) != board.color(&c)
+            })
+            .map(|&(c, _)| c)
+            .collect();
+        let ruleset = game.ruleset();
+        if ruleset == KgsChinese || ruleset == Japanese {
             for coord in &dead {
                 board.remove_dead_stone(coord);
             }
-            let decided = ownership.decided() && board.winner() == ownership.winner();
+            let decided = settled || (ownership.decided() && board.winner() == ownership.winner());
             FinalScore {
                 board: board,
                 decided: decided,
                 dead: dead,
+                seki: seki,
             }
         } else {
-            let decided = ownership.decided() && dead.len() == 0;
+            let decided = (settled || ownership.decided()) && dead.len() == 0;
             FinalScore {
                 board: board,
                 decided: decided,
                 dead: vec!(),
+                seki: seki,
             }
         }
     }
 
+    // The status of every stone on the board (in board order).
+    // Pass-alive chains (and the chains inside their eye spaces) are
+    // settled without a search. The others are read out by the life
+    // and death solver (if `solve` is set) once per region, i.e. the
+    // chains of a region share the status of the first one.
+    fn statuses(board: &Board, pass_alive: &Vec<Color>, solve: bool) -> Vec<(Coord, Status)> {
+        let mut chains: HashMap<usize, Status> = HashMap::new();
+        let mut regions: HashMap<Vec<Coord>, Status> = HashMap::new();
+        let mut statuses = vec!();
+        for coord in Coord::for_board_size(board.size()) {
+            let color = board.color(&coord);
+            if color == Empty {
+                continue;
+            }
+            let id = board.get_chain(coord).unwrap().id();
+            if !chains.contains_key(&id) {
+                let owner = pass_alive[coord.to_index(board.size())];
+                let status = if owner == color {
+                    Status::Alive
+                } else if owner == color.opposite() {
+                    Status::Dead
+                } else if solve {
+                    Self::solve(board, coord, &mut regions)
+                } else {
+                    Status::Unknown
+                };
+                chains.insert(id, status);
+            }
+            statuses.push((coord, chains[&id]));
+        }
+        statuses
+    }
+
+    fn solve(board: &Board, coord: Coord, regions: &mut HashMap<Vec<Coord>, Status>) -> Status {
+        match Solver::with_max_nodes(board, coord, MAX_NODES) {
+            Some(mut solver) => {
+                let mut region = solver.region().clone();
+                region.sort();
+                *regions.entry(region).or_insert_with(|| solver.status(board))
+            },
+            None => Status::Unknown
+        }
+    }
+
     pub fn score(&self) -> String {
-        format!("{}", self.board.score())
+        format!("{}", Score::with_seki(&self.board))
     }
 
     pub fn status_list(&self, kind: &str) -> Result<String, String> {
@@ -92,12 +177,16 @@ impl FinalScore {
     }
 
     fn status_list_seki(&self) -> Result<String, String> {
-        Ok("".to_string())
+        let s = self.seki.iter()
+            .fold(String::new(), |acc, el| format!("{} {}", acc, el.to_gtp()));
+
+        Ok(String::from(s.trim()))
     }
 
     fn status_list_alive(&self) -> Result<String, String> {
         let s = Coord::for_board_size(self.board.size()).iter()
             .filter(|c| self.board.color(c) != Empty)
+            .filter(|c| !self.seki.contains(c))
             .fold(String::new(), |acc, el| format!("{} {}", acc, el.to_gtp()));
         Ok(String::from(s.trim()))
     }
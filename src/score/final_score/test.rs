@@ -1,6 +1,6 @@
 /************************************************************************
  *                                                                      *
- * Copyright 2015 Urban Hafner                                          *
+ * Copyright 2016 Urban Hafner                                          *
  *                                                                      *
  * This file is part of Iomrascálaí.                                    *
  *                                                                      *
@@ -19,4 +19,72 @@
  *                                                                      *
  ************************************************************************/
 
+
 #![cfg(test)]
+
+use board::Black;
+use board::Coord;
+use board::Empty;
+use board::Play;
+use config::Config;
+use fixtures::load_game;
+use game::Game;
+use ownership::OwnershipStatistics;
+use ruleset::KgsChinese;
+use score::Score;
+use super::FinalScore;
+
+use std::sync::Arc;
+
+// The fixture as a game with a ruleset that removes the dead stones.
+fn load_chinese_game(filename: &'static str) -> Game {
+    let board = load_game(filename).board();
+    let mut game = Game::new(board.size(), 6.5, KgsChinese);
+    for coord in Coord::for_board_size(board.size()) {
+        let color = board.color(&coord);
+        if color != Empty {
+            game.set_next_player(color);
+            game = game.play(Play(color, coord.col, coord.row)).unwrap();
+        }
+    }
+    game
+}
+
+fn ownership(game: &Game) -> OwnershipStatistics {
+    OwnershipStatistics::new(Arc::new(Config::test_config()), game.size(), game.komi())
+}
+
+#[test]
+fn a_dead_chain_is_removed_without_ownership_statistics() {
+    let game = load_chinese_game("life-and-death/dead");
+    let final_score = FinalScore::new(&game, &ownership(&game));
+    assert_eq!(Ok("D1 A2 B2 C2 D2".to_string()), final_score.status_list("dead"));
+}
+
+#[test]
+fn without_the_solver_a_dead_chain_needs_ownership_statistics() {
+    let game = load_chinese_game("life-and-death/dead");
+    let final_score = FinalScore::without_solver(&game, &ownership(&game));
+    assert_eq!(Ok("".to_string()), final_score.status_list("dead"));
+}
+
+#[test]
+fn a_chain_with_two_eyes_stays_alive_if_the_opponent_owns_its_points() {
+    let game = load_chinese_game("life-and-death/alive");
+    let mut ownership = ownership(&game);
+    // Black owns every point of a board with a single black stone.
+    let black = Game::new(9, 6.5, KgsChinese).play(Play(Black, 5, 5)).unwrap();
+    for _ in 0..100 {
+        ownership.merge(&Score::new(&black.board()));
+    }
+    let final_score = FinalScore::new(&game, &ownership);
+    assert_eq!(Ok("".to_string()), final_score.status_list("dead"));
+}
+
+#[test]
+fn a_chain_sharing_a_liberty_with_an_eye_is_in_seki() {
+    let game = load_chinese_game("seki/eyes");
+    let final_score = FinalScore::new(&game, &ownership(&game));
+    assert_eq!(Ok("".to_string()), final_score.status_list("dead"));
+    assert!(final_score.status_list("seki").unwrap().contains("A4"));
+}
@@ -47,16 +47,33 @@ pub struct Score {
 impl Score {
 
     pub fn new(board: &Board) -> Score {
-        let (bs, ws, owners) = Score::score_tt(board);
+        Score::compute(board, false)
+    }
+
+    /// The score at the end of the game. Unlike `new` the eyes of the
+    /// chains in seki don't count as territory, which is too slow to
+    /// find out in the playouts (and changes the score by at most a
+    /// point there).
+    pub fn with_seki(board: &Board) -> Score {
+        Score::compute(board, true)
+    }
+
+    fn compute(board: &Board, seki: bool) -> Score {
+        let (bs, ws, owners) = if board.ruleset().territory_scoring() {
+            Score::score_territory(board, seki)
+        } else {
+            Score::score_tt(board)
+        };
         Score {
             black_stones: bs,
-            komi: board.komi(),
+            komi: board.adjusted_komi(),
             owner: owners,
             size: board.size(),
             white_stones: ws,
         }
     }
 
+    #[cfg(test)]
     pub fn empty() -> Score {
         Score {
             black_stones: 0,
@@ -115,6 +132,51 @@ impl Score {
         (black_score, white_score, owners)
     }
 
+    /// Counts territory plus prisoners. If `seki` is set the empty
+    /// points surrounded by chains that are in seki don't count for
+    /// either player.
+    fn score_territory(board: &Board, seki: bool) -> (usize, usize, Vec<Color>) {
+        let len = board.size() as usize * board.size() as usize;
+        let mut owners = vec![Empty; len];
+        Score::count_stones(board, &mut owners);
+        Score::count_territory(board, &mut owners);
+        if seki {
+            for coord in Score::seki_eyes(board, &owners) {
+                owners[coord.to_index(board.size())] = Empty;
+            }
+        }
+        let mut black_score = board.prisoners(Black);
+        let mut white_score = board.prisoners(White);
+        for coord in board.vacant() {
+            match owners[coord.to_index(board.size())] {
+                Black => { black_score += 1; }
+                White => { white_score += 1; }
+                Empty => {}
+            }
+        }
+        (black_score, white_score, owners)
+    }
+
+    /// Finds the eyes of the chains in seki, i.e. the territories
+    /// that border a stone in seki.
+    fn seki_eyes(board: &Board, owners: &Vec<Color>) -> Vec<Coord> {
+        let seki = board.stones_in_seki();
+        let mut eyes: Vec<Coord> = vec!();
+        if seki.len() == 0 {
+            return eyes;
+        }
+        for &coord in board.vacant() {
+            if owners[coord.to_index(board.size())] == Empty || eyes.contains(&coord) {
+                continue;
+            }
+            if board.neighbours(coord).iter().any(|c| seki.contains(c)) {
+                let territory = Score::build_territory_chain(coord, board);
+                eyes.extend(territory.coords().iter().cloned());
+            }
+        }
+        eyes
+    }
+
     fn count_territory(board: &Board, owners: &mut Vec<Color>) {
         let mut empty_intersections = board.vacant().clone();
         while empty_intersections.len() > 0 {
@@ -36,6 +36,7 @@ pub use board::Pass;
 pub use board::Play;
 pub use board::White;
 pub use fixtures::load_board;
+pub use ruleset::Japanese;
 pub use super::Score;
 
 pub fn points_for_color(score: &Score, board: &Board, color: Color) -> Vec<String> {
@@ -52,6 +53,17 @@ pub fn points(strs: Vec<&'static str>) -> Vec<String> {
     strs.iter().map(|s| s.to_string()).collect()
 }
 
+pub fn japanese_board(size: u8, black: Vec<&'static str>, white: Vec<&'static str>) -> Board {
+    let mut board = Board::new(size, 6.5, Japanese);
+    for &(color, ref vertices) in [(Black, black), (White, white)].iter() {
+        for vertex in vertices {
+            let coord = Coord::from_gtp(vertex);
+            board.play_legal_move(Play(color, coord.col, coord.row));
+        }
+    }
+    board
+}
+
 describe! score {
 
     describe! simple {
@@ -213,4 +225,44 @@ describe! score {
             assert_that(score.adjusted(), is(equal_to(0.0)));
         }
     }
+
+    describe! japanese {
+
+        it "counts territory and prisoners" {
+            let mut board = Board::new(4, 6.5, Japanese);
+            let moves = vec![Play(Black, 2, 1), Play(White, 3, 1), Play(Black, 2, 2), Play(White, 3, 2),
+                             Play(Black, 2, 3), Play(White, 3, 3), Play(Black, 2, 4), Play(White, 3, 4),
+                             Pass(Black), Play(White, 1, 1), Play(Black, 1, 2)];
+            for m in moves {
+                board.play(m).unwrap();
+            }
+            let score = board.score();
+            assert_that(board.prisoners(Black), is(equal_to(1)));
+            assert_that(score.black_stones, is(equal_to(4)));
+            assert_that(score.white_stones, is(equal_to(4)));
+            assert_that(format!("{}", score), is(equal_to("W+6.5".to_string())));
+        }
+
+        it "doesn't count the eyes of groups in seki" {
+            let board = japanese_board(5,
+                vec!["B5", "A4", "B4", "C4", "D3", "E3", "D2", "E2"],
+                vec!["D5", "D4", "E4", "A3", "B3", "C3", "A2", "B2", "C2"]);
+            let score = Score::with_seki(&board);
+            assert_that(score.black_stones, is(equal_to(0)));
+            assert_that(score.white_stones, is(equal_to(0)));
+            let eye = Coord::from_gtp("A5").to_index(board.size());
+            assert_that(score.owner()[eye], is(equal_to(Empty)));
+        }
+
+        it "counts the eyes of groups in seki in the playouts" {
+            let board = japanese_board(5,
+                vec!["B5", "A4", "B4", "C4", "D3", "E3", "D2", "E2"],
+                vec!["D5", "D4", "E4", "A3", "B3", "C3", "A2", "B2", "C2"]);
+            let score = board.score();
+            let eye = Coord::from_gtp("A5").to_index(board.size());
+            assert_that(score.owner()[eye], is(equal_to(Black)));
+        }
+
+    }
+
 }
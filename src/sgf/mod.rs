@@ -20,6 +20,10 @@
  ************************************************************************/
 
 pub use self::parser::Parser;
+pub use self::writer::Writer;
 
 pub mod parser;
+pub mod property;
 mod test;
+pub mod tree;
+pub mod writer;
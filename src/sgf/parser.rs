@@ -19,137 +19,400 @@
  * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
  *                                                                      *
  ************************************************************************/
-use std::io::prelude::*;
-use std::fs::File;
-use std::path::Path;
+
 use board::Black;
 use board::Color;
+use board::Coord;
 use board::Empty;
 use board::IllegalMove;
+use board::Move;
 use board::Pass;
 use board::Play;
 use board::White;
 use game::Game;
+use game::Info;
 use ruleset::Minimal;
+use super::property::Point;
+use super::property::Property;
+use super::tree::Node;
 
-pub struct Parser {
-    sgf: String
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+
+/// A syntax error in an SGF file. Lines and columns start at 1.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub column: usize,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        format!("line {}, column {}: {}", self.line, self.column, self.message).fmt(f)
+    }
 }
 
 #[derive(Debug)]
-struct Property<'a> {
-    name: &'a str,
-    val:  &'a str
+pub enum Error {
+    IllegalMove(IllegalMove),
+    NoSuchVariation,
+    Parse(ParseError),
+}
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::IllegalMove(ref e) => format!("illegal move: {}", e).fmt(f),
+            Error::NoSuchVariation => "no such variation".fmt(f),
+            Error::Parse(ref e) => e.fmt(f),
+        }
+    }
+}
+
+pub struct Parser {
+    sgf: String
 }
 
-impl<'a> Property<'a> {
+impl Parser {
+    pub fn new(sgf: String) -> Parser {
+        Parser {sgf: sgf}
+    }
 
-    fn col(&self) -> u8 {
-        self.char_to_int(self.val.as_bytes()[0])
+    pub fn from_path(path: &Path) -> Result<Parser, ::std::io::Error> {
+        let mut file = try!(File::open(&path));
+        let mut contents = String::new();
+        try!(file.read_to_string(&mut contents));
+        Ok(Parser::new(contents))
     }
 
-    // SGF counts from top to bottom, while we count from bottom to
-    // top (and we start at 1).
-    fn row(&self, size: u8) -> u8 {
-        size - self.char_to_int(self.val.as_bytes()[1]) + 1
+    /// Parses all the game trees in the file.
+    pub fn collection(&self) -> Result<Vec<Node>, ParseError> {
+        Reader::new(&self.sgf).collection()
     }
 
-    fn char_to_int(&self, c: u8) -> u8 {
-        c - ('a' as u8) + 1
+    /// Parses the first game tree in the file.
+    pub fn tree(&self) -> Result<Node, ParseError> {
+        let mut trees = try!(self.collection());
+        Ok(trees.swap_remove(0))
     }
 
-    fn is_pass(&self) -> bool {
-        self.val == ""
+    /// Replays the main line of the first game tree.
+    pub fn game(&self) -> Result<Game, Error> {
+        let tree = try!(self.tree().map_err(Error::Parse));
+        Self::replay(&tree.main_line()).map_err(Error::IllegalMove)
     }
 
-    fn is_move(&self) -> bool {
-        match self.name {
-            "AB" | "AW" | "B" | "W" => true,
-            _                       => false
+    /// Replays a variation of the first game tree. See
+    /// `Node::variation` for the meaning of `choices`.
+    pub fn variation(&self, choices: &[usize]) -> Result<Game, Error> {
+        let tree = try!(self.tree().map_err(Error::Parse));
+        match tree.variation(choices) {
+            Some(nodes) => Self::replay(&nodes).map_err(Error::IllegalMove),
+            None => Err(Error::NoSuchVariation)
         }
     }
 
-    fn color(&self) -> Color {
-        match self.name {
-            "AB" | "B" => Black,
-            "AW" | "W" => White,
-            _          => Empty
+    /// Replays the given nodes into a game. The board size and komi
+    /// are taken from the first node. The game is always set up
+    /// using the minimal ruleset as the SGF file may contain moves
+    /// that are illegal under stricter rules.
+    pub fn replay(nodes: &[&Node]) -> Result<Game, IllegalMove> {
+        Self::replay_moves(nodes, &mut |_, _| {})
+    }
+
+    /// Like `Parser::replay()`, but calls `f` with the position
+    /// before each move (including passes) and the move. Setup
+    /// stones (AB and AW) aren't moves.
+    pub fn replay_moves(nodes: &[&Node], f: &mut FnMut(&Game, &Move)) -> Result<Game, IllegalMove> {
+        let mut size = 19;
+        let mut komi = 6.5;
+        let mut handicap = 0;
+        if let Some(root) = nodes.first() {
+            for property in root.properties.iter() {
+                match *property {
+                    Property::Size(s) => { size = s; },
+                    Property::Komi(k) => { komi = k; },
+                    Property::Handicap(h) => { handicap = h; },
+                    _ => {}
+                }
+            }
         }
+        let mut game = Game::new(size, komi, Minimal);
+        for node in nodes {
+            for property in node.properties.iter() {
+                game = try!(Self::apply(game, property, handicap, f));
+            }
+        }
+        Ok(game)
     }
 
-    fn play(&self, game: Game) -> Result<Game, IllegalMove> {
-        if self.is_move() {
-            if self.is_pass() {
-                game.play(Pass(self.color()))
-            } else {
-                game.play(Play(self.color(), self.col(), self.row(game.size())))
+    fn apply(game: Game, property: &Property, handicap: u8, f: &mut FnMut(&Game, &Move)) -> Result<Game, IllegalMove> {
+        let size = game.size();
+        match *property {
+            Property::Black(point) => Self::play(game, Black, point, f),
+            Property::White(point) => Self::play(game, White, point, f),
+            Property::AddBlack(ref points) => {
+                let coords = try!(Self::coords(points, size));
+                let empty = game.vacant_point_count() as usize == size as usize * size as usize;
+                if empty && handicap as usize == coords.len() && handicap > 1 {
+                    game.place_handicap(&coords)
+                } else {
+                    Self::add_stones(game, Black, &coords)
+                }
+            },
+            Property::AddWhite(ref points) => {
+                let coords = try!(Self::coords(points, size));
+                Self::add_stones(game, White, &coords)
+            },
+            Property::AddEmpty(ref points) => {
+                let coords = try!(Self::coords(points, size));
+                Self::clear_points(game, &coords)
+            },
+            Property::Player(color) => {
+                let mut game = game;
+                game.set_next_player(color);
+                Ok(game)
+            },
+            _ => Ok(game)
+        }
+    }
+
+    fn coords(points: &Vec<Point>, size: u8) -> Result<Vec<Coord>, IllegalMove> {
+        points.iter().map(|p| p.to_coord(size).ok_or(IllegalMove::PlayOutOfBoard)).collect()
+    }
+
+    fn play(game: Game, color: Color, point: Option<Point>, f: &mut FnMut(&Game, &Move)) -> Result<Game, IllegalMove> {
+        let m = match point {
+            Some(p) if !p.is_pass(game.size()) => match p.to_coord(game.size()) {
+                Some(coord) => Play(color, coord.col, coord.row),
+                None => return Err(IllegalMove::PlayOutOfBoard)
+            },
+            _ => Pass(color)
+        };
+        f(&game, &m);
+        game.play(m)
+    }
+
+    fn add_stones(game: Game, color: Color, coords: &Vec<Coord>) -> Result<Game, IllegalMove> {
+        let mut game = game;
+        for coord in coords {
+            game = try!(game.play(Play(color, coord.col, coord.row)));
+        }
+        Ok(game)
+    }
+
+    /// Removes stones from the board by setting up a new game with
+    /// all the remaining stones.
+    fn clear_points(game: Game, coords: &Vec<Coord>) -> Result<Game, IllegalMove> {
+        let board = game.board();
+        let mut new_game = Game::new(game.size(), game.komi(), game.ruleset());
+        for coord in Coord::for_board_size(game.size()) {
+            let color = board.color(&coord);
+            if color != Empty && !coords.contains(&coord) {
+                new_game = try!(new_game.play(Play(color, coord.col, coord.row)));
             }
-        } else {
-            Ok(game)
         }
+        new_game.set_next_player(board.next_player());
+        Ok(new_game)
     }
+
 }
 
-impl Parser {
-    pub fn new(sgf: String) -> Parser {
-        Parser {sgf: sgf}
+/// A recursive descent parser for the SGF FF[4] grammar:
+///
+/// ```text
+/// Collection = GameTree { GameTree }
+/// GameTree   = "(" Sequence { GameTree } ")"
+/// Sequence   = Node { Node }
+/// Node       = ";" { Property }
+/// Property   = PropIdent PropValue { PropValue }
+/// PropIdent  = UcLetter { UcLetter }
+/// PropValue  = "[" CValueType "]"
+/// ```
+///
+/// The points are checked against the board size (SZ) of the game
+/// tree they are in.
+struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    column: usize,
+    line: usize,
+    size: u8,
+}
+
+impl<'a> Reader<'a> {
+
+    fn new(sgf: &'a str) -> Reader<'a> {
+        Reader {
+            chars: sgf.chars().peekable(),
+            column: 1,
+            line: 1,
+            size: 19,
+        }
     }
 
-    pub fn from_path(path: &Path) -> Result<Parser, ::std::io::Error> {
-    	match File::open(&path) {
-    		Ok(mut file) => {
-		        let mut contents = String::new();
-                match file.read_to_string(&mut contents) {
-                	Ok(_) => Ok(Parser::new(contents)),
-                	Err(e) => Err(e)
-            	}
-			},
-    		Err(e) => Err(e)
-		}
-	}
-
-    pub fn game(&self) -> Result<Game, IllegalMove> {
-        let mut game = Game::new(self.size(), self.komi(), Minimal);
-        let props = self.tokenize();
-        for prop in props.iter() {
-            match prop.play(game) {
-                Ok(g) => {
-                    game = g;
+    fn collection(&mut self) -> Result<Vec<Node>, ParseError> {
+        let mut trees = vec!();
+        self.skip_whitespace();
+        while self.peek().is_some() {
+            self.size = 19;
+            trees.push(try!(self.game_tree()));
+            self.skip_whitespace();
+        }
+        if trees.len() == 0 {
+            Err(self.error("expected '('"))
+        } else {
+            Ok(trees)
+        }
+    }
+
+    fn game_tree(&mut self) -> Result<Node, ParseError> {
+        try!(self.expect('('));
+        let mut sequence = vec!();
+        self.skip_whitespace();
+        while self.peek() == Some(';') {
+            sequence.push(try!(self.node()));
+            self.skip_whitespace();
+        }
+        if sequence.len() == 0 {
+            return Err(self.error("expected ';'"));
+        }
+        let mut variations = vec!();
+        while self.peek() == Some('(') {
+            variations.push(try!(self.game_tree()));
+            self.skip_whitespace();
+        }
+        try!(self.expect(')'));
+        // Link the sequence into a chain of nodes with the
+        // variations as the children of the last node.
+        let mut current = sequence.pop().unwrap();
+        current.children = variations;
+        while let Some(mut parent) = sequence.pop() {
+            parent.children = vec!(current);
+            current = parent;
+        }
+        Ok(current)
+    }
+
+    fn node(&mut self) -> Result<Node, ParseError> {
+        try!(self.expect(';'));
+        let mut properties = vec!();
+        self.skip_whitespace();
+        while self.peek().map_or(false, |c| c.is_alphabetic()) {
+            properties.push(try!(self.property()));
+            self.skip_whitespace();
+        }
+        Ok(Node::new(properties))
+    }
+
+    fn property(&mut self) -> Result<Property, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_uppercase() {
+                ident.push(c);
+            } else if !c.is_lowercase() {
+                // Lowercase letters are allowed in old (FF[3])
+                // files, e.g. "AddBlack" instead of "AB".
+                break;
+            }
+            self.next();
+        }
+        if ident.len() == 0 {
+            return Err(ParseError { column: column, line: line, message: "invalid property identifier".to_string() });
+        }
+        let mut values = vec!();
+        self.skip_whitespace();
+        while self.peek() == Some('[') {
+            values.push(try!(self.value()));
+            self.skip_whitespace();
+        }
+        if values.len() == 0 {
+            return Err(self.error("expected '['"));
+        }
+        let error = |message: String| {
+            ParseError { column: column, line: line, message: format!("{}: {}", ident, message) }
+        };
+        let property = try!(Property::new(&ident, values).map_err(&error));
+        if let Property::Size(size) = property {
+            self.size = size;
+        }
+        let size = self.size;
+        if let Some(point) = property.points().into_iter().find(|p| !p.is_pass(size) && p.to_coord(size).is_none()) {
+            return Err(error(format!("point '{}' is outside of the {}x{} board", point, size, size)));
+        }
+        Ok(property)
+    }
+
+    fn value(&mut self) -> Result<String, ParseError> {
+        try!(self.expect('['));
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some(']') => return Ok(value),
+                Some('\\') => match self.next() {
+                    // An escaped line break is a soft line break
+                    // which is removed.
+                    Some('\n') => {},
+                    Some('\r') => {
+                        if self.peek() == Some('\n') {
+                            self.next();
+                        }
+                    },
+                    Some(c) => value.push(c),
+                    None => return Err(self.error("unterminated property value"))
                 },
-                Err(e) => return Err(e)
+                Some(c) => value.push(c),
+                None => return Err(self.error("unterminated property value"))
             }
         }
-        Ok(game)
     }
 
-    fn size(&self) -> u8 {
-        let props = self.tokenize();
-        let prop = props.iter().find(|p| p.name == "SZ");
-        prop.and_then(|p| p.val.parse().ok()).unwrap_or(19)
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            },
+            Some(c) => Err(self.error(&format!("expected '{}' but found '{}'", expected, c))),
+            None => Err(self.error(&format!("expected '{}' but reached the end of the file", expected)))
+        }
     }
 
-    fn komi(&self) -> f32 {
-        let props = self.tokenize();
-        let prop = props.iter().find(|p| p.name == "KM");
-        prop.and_then(|p| p.val.parse().ok()).unwrap_or(6.5)
+    fn skip_whitespace(&mut self) {
+        while self.peek().map_or(false, |c| c.is_whitespace()) {
+            self.next();
+        }
     }
 
-    fn tokenize<'a>(&'a self) -> Vec<Property<'a>> {
-        let mut tokens = Vec::new();
-        let mut prev_name = "";
-        let re = regex!(r"([:upper:]{1,2})?\[([^]]*)\]");
-        for caps in re.captures_iter(self.sgf.as_ref()) {
-            match caps.at(1) {
-                Some(name) => {
-                    tokens.push(Property {name: name, val: caps.at(2).unwrap()});
-                    prev_name = name;
-                }
-                None => {
-                    tokens.push(Property {name: prev_name, val: caps.at(2).unwrap()});
-                }
-            }
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if c.is_some() {
+            self.column += 1;
+        }
+        c
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            column: self.column,
+            line: self.line,
+            message: message.to_string(),
         }
-        tokens
     }
 
 }
@@ -0,0 +1,228 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Black;
+use board::Color;
+use board::Coord;
+use board::White;
+
+use std::fmt;
+
+/// A point as written in an SGF file. SGF counts from the top left
+/// corner starting at 0, while we count from the bottom left corner
+/// starting at 1.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Point {
+    pub x: u8,
+    pub y: u8,
+}
+
+impl Point {
+
+    pub fn new(x: u8, y: u8) -> Point {
+        Point { x: x, y: y }
+    }
+
+    /// Returns `None` if the point isn't on a board of the given
+    /// size.
+    pub fn to_coord(&self, size: u8) -> Option<Coord> {
+        if self.x < size && self.y < size {
+            Some(Coord::new(self.x + 1, size - self.y))
+        } else {
+            None
+        }
+    }
+
+    /// In FF[3] and earlier `tt` denotes a pass on boards up to
+    /// 19x19.
+    pub fn is_pass(&self, size: u8) -> bool {
+        size <= 19 && self.x == 19 && self.y == 19
+    }
+
+    fn letter(n: u8) -> char {
+        if n < 26 {
+            (b'a' + n) as char
+        } else {
+            (b'A' + n - 26) as char
+        }
+    }
+
+}
+
+impl fmt::Display for Point {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", Self::letter(self.x), Self::letter(self.y))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    /// B: A black move. `None` is a pass.
+    Black(Option<Point>),
+    /// W: A white move. `None` is a pass.
+    White(Option<Point>),
+    /// AB: Black setup stones.
+    AddBlack(Vec<Point>),
+    /// AW: White setup stones.
+    AddWhite(Vec<Point>),
+    /// AE: Points to clear.
+    AddEmpty(Vec<Point>),
+    /// PL: The player to move next.
+    Player(Color),
+    /// SZ: The board size.
+    Size(u8),
+    /// KM: The komi.
+    Komi(f32),
+    /// HA: The number of handicap stones.
+    Handicap(u8),
+    /// RU: The rules the game was played under.
+    Rules(String),
+    /// PB: The name of the black player.
+    PlayerBlack(String),
+    /// PW: The name of the white player.
+    PlayerWhite(String),
+    /// C: A comment.
+    Comment(String),
+    /// Any property we don't interpret, with its raw values.
+    Unknown(String, Vec<String>),
+}
+
+impl Property {
+
+    /// Builds a typed property from the identifier and the
+    /// (unescaped) values. Returns an error message if the values
+    /// don't match the type of the property.
+    pub fn new(ident: &str, values: Vec<String>) -> Result<Property, String> {
+        match ident {
+            "B"  => Ok(Property::Black(try!(Self::parse_move(&values)))),
+            "W"  => Ok(Property::White(try!(Self::parse_move(&values)))),
+            "AB" => Ok(Property::AddBlack(try!(Self::parse_point_list(&values)))),
+            "AW" => Ok(Property::AddWhite(try!(Self::parse_point_list(&values)))),
+            "AE" => Ok(Property::AddEmpty(try!(Self::parse_point_list(&values)))),
+            "PL" => Ok(Property::Player(try!(Self::parse_color(&values)))),
+            "SZ" => Ok(Property::Size(try!(Self::parse_number(&values)))),
+            "KM" => Ok(Property::Komi(try!(Self::parse_real(&values)))),
+            "HA" => Ok(Property::Handicap(try!(Self::parse_number(&values)))),
+            "RU" => Ok(Property::Rules(try!(Self::single(&values)).to_string())),
+            "PB" => Ok(Property::PlayerBlack(try!(Self::single(&values)).to_string())),
+            "PW" => Ok(Property::PlayerWhite(try!(Self::single(&values)).to_string())),
+            "C"  => Ok(Property::Comment(try!(Self::single(&values)).to_string())),
+            _    => Ok(Property::Unknown(ident.to_string(), values)),
+        }
+    }
+
+    /// The points of moves and setup stones (but not passes).
+    pub fn points(&self) -> Vec<Point> {
+        match *self {
+            Property::Black(Some(point)) | Property::White(Some(point)) => vec!(point),
+            Property::AddBlack(ref points) |
+            Property::AddWhite(ref points) |
+            Property::AddEmpty(ref points) => points.clone(),
+            _ => vec!()
+        }
+    }
+
+    fn single(values: &Vec<String>) -> Result<&str, String> {
+        if values.len() == 1 {
+            Ok(values[0].as_ref())
+        } else {
+            Err("expected a single value".to_string())
+        }
+    }
+
+    fn parse_move(values: &Vec<String>) -> Result<Option<Point>, String> {
+        let value = try!(Self::single(values));
+        if value == "" {
+            Ok(None)
+        } else {
+            Self::parse_point(value).map(|p| Some(p))
+        }
+    }
+
+    fn parse_point(value: &str) -> Result<Point, String> {
+        let bytes = value.as_bytes();
+        if bytes.len() != 2 {
+            return Err(format!("invalid point '{}'", value));
+        }
+        match (Self::parse_point_char(bytes[0]), Self::parse_point_char(bytes[1])) {
+            (Some(x), Some(y)) => Ok(Point::new(x, y)),
+            _ => Err(format!("invalid point '{}'", value))
+        }
+    }
+
+    fn parse_point_char(c: u8) -> Option<u8> {
+        if c >= b'a' && c <= b'z' {
+            Some(c - b'a')
+        } else if c >= b'A' && c <= b'Z' {
+            Some(c - b'A' + 26)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a list of points, where each value can either be a
+    /// single point or a compressed rectangle like `aa:cc`.
+    fn parse_point_list(values: &Vec<String>) -> Result<Vec<Point>, String> {
+        let mut points = vec!();
+        for value in values {
+            let parts: Vec<&str> = value.split(':').collect();
+            match parts.len() {
+                1 => points.push(try!(Self::parse_point(parts[0]))),
+                2 => {
+                    let from = try!(Self::parse_point(parts[0]));
+                    let to = try!(Self::parse_point(parts[1]));
+                    if from.x > to.x || from.y > to.y {
+                        return Err(format!("invalid rectangle '{}'", value));
+                    }
+                    for x in from.x..to.x+1 {
+                        for y in from.y..to.y+1 {
+                            points.push(Point::new(x, y));
+                        }
+                    }
+                },
+                _ => return Err(format!("invalid point list '{}'", value))
+            }
+        }
+        Ok(points)
+    }
+
+    fn parse_color(values: &Vec<String>) -> Result<Color, String> {
+        match try!(Self::single(values)) {
+            "B" => Ok(Black),
+            "W" => Ok(White),
+            value => Err(format!("invalid color '{}'", value))
+        }
+    }
+
+    fn parse_number(values: &Vec<String>) -> Result<u8, String> {
+        let value = try!(Self::single(values));
+        // SZ can also be "19:19" for rectangular boards, which we
+        // don't support.
+        value.trim().parse().map_err(|_| format!("invalid number '{}'", value))
+    }
+
+    fn parse_real(values: &Vec<String>) -> Result<f32, String> {
+        let value = try!(Self::single(values));
+        value.trim().parse().map_err(|_| format!("invalid real '{}'", value))
+    }
+
+}
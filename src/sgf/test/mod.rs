@@ -20,3 +20,4 @@
  ************************************************************************/
 
 mod parser;
+mod writer;
@@ -22,8 +22,11 @@
 #![cfg(test)]
 use board::Black;
 use board::Coord;
+use board::Empty;
 use board::White;
 use sgf::parser::Parser;
+use sgf::property::Point;
+use sgf::property::Property;
 
 use std::path::Path;
 
@@ -64,3 +67,141 @@ fn finished_game() {
     let game   = parser.game().unwrap();
     assert!(game.is_over());
 }
+
+#[test]
+fn handicap_stones_count_as_handicap() {
+    let parser = Parser::from_path(Path::new("fixtures/sgf/handicap.sgf")).unwrap();
+    let game  = parser.game().unwrap();
+    assert_eq!(game.handicap(), 3);
+    assert_eq!(game.board().next_player(), White);
+}
+
+#[test]
+fn parses_the_properties_of_a_node() {
+    let parser = Parser::new("(;SZ[9]KM[5.5]HA[2]RU[Japanese]PB[Foo]PW[Bar]XY[1][2])".to_string());
+    let tree = parser.tree().unwrap();
+    assert_eq!(tree.properties, vec!(
+        Property::Size(9),
+        Property::Komi(5.5),
+        Property::Handicap(2),
+        Property::Rules("Japanese".to_string()),
+        Property::PlayerBlack("Foo".to_string()),
+        Property::PlayerWhite("Bar".to_string()),
+        Property::Unknown("XY".to_string(), vec!("1".to_string(), "2".to_string()))));
+}
+
+#[test]
+fn handles_escapes_in_values() {
+    let parser = Parser::new("(;C[a \\] b \\\\ c\\\nd])".to_string());
+    let tree = parser.tree().unwrap();
+    assert_eq!(tree.properties, vec!(Property::Comment("a ] b \\ cd".to_string())));
+}
+
+#[test]
+fn expands_compressed_point_lists() {
+    let parser = Parser::new("(;SZ[5]AB[aa:bb][ee])".to_string());
+    let tree = parser.tree().unwrap();
+    let expected = vec!(Point::new(0, 0), Point::new(0, 1), Point::new(1, 0), Point::new(1, 1), Point::new(4, 4));
+    assert_eq!(tree.properties[1], Property::AddBlack(expected));
+    let game = parser.game().unwrap();
+    assert_eq!(game.board().color(&Coord::new(1, 5)), Black);
+    assert_eq!(game.board().color(&Coord::new(2, 4)), Black);
+    assert_eq!(game.board().color(&Coord::new(5, 1)), Black);
+}
+
+#[test]
+fn passes() {
+    let parser = Parser::new("(;SZ[9];B[];W[tt])".to_string());
+    let game = parser.game().unwrap();
+    assert!(game.is_over());
+}
+
+#[test]
+fn add_empty_removes_stones() {
+    let parser = Parser::new("(;SZ[9]AB[aa][bb]AW[cc];AE[aa])".to_string());
+    let game = parser.game().unwrap();
+    assert_eq!(game.board().color(&Coord::new(1, 9)), Empty);
+    assert_eq!(game.board().color(&Coord::new(2, 8)), Black);
+    assert_eq!(game.board().color(&Coord::new(3, 7)), White);
+}
+
+#[test]
+fn player_sets_the_next_player() {
+    let parser = Parser::new("(;SZ[9]AB[aa]PL[B])".to_string());
+    let game = parser.game().unwrap();
+    assert_eq!(game.board().next_player(), Black);
+}
+
+#[test]
+fn builds_the_tree_with_variations() {
+    let parser = Parser::new("(;SZ[9];B[aa](;W[bb];B[cc])(;W[dd]))".to_string());
+    let tree = parser.tree().unwrap();
+    assert_eq!(tree.main_line().len(), 4);
+    assert_eq!(tree.children[0].children.len(), 2);
+    assert_eq!(tree.variation(&[1]).unwrap().len(), 3);
+    assert!(tree.variation(&[2]).is_none());
+}
+
+#[test]
+fn main_line_follows_the_first_variation() {
+    let parser = Parser::new("(;SZ[9];B[aa](;W[bb];B[cc])(;W[dd]))".to_string());
+    let game = parser.game().unwrap();
+    assert_eq!(game.board().color(&Coord::new(2, 8)), White);
+    assert_eq!(game.board().color(&Coord::new(3, 7)), Black);
+    assert_eq!(game.board().color(&Coord::new(4, 6)), Empty);
+}
+
+#[test]
+fn replays_a_variation() {
+    let parser = Parser::new("(;SZ[9];B[aa](;W[bb];B[cc])(;W[dd]))".to_string());
+    let game = parser.variation(&[1]).unwrap();
+    assert_eq!(game.board().color(&Coord::new(2, 8)), Empty);
+    assert_eq!(game.board().color(&Coord::new(4, 6)), White);
+}
+
+#[test]
+fn reports_the_position_of_syntax_errors() {
+    let parser = Parser::new("(;SZ[9]\n;B[aa]\n;W[bb)".to_string());
+    let error = parser.tree().unwrap_err();
+    assert_eq!(error.line, 3);
+    assert_eq!(error.column, 7);
+    assert_eq!(error.message, "unterminated property value");
+}
+
+#[test]
+fn reports_the_position_of_invalid_values() {
+    let parser = Parser::new("(;SZ[9]\n  ;B[a1])".to_string());
+    let error = parser.tree().unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.column, 4);
+    assert_eq!(error.message, "B: invalid point 'a1'");
+}
+
+#[test]
+fn rejects_missing_nodes() {
+    let parser = Parser::new("()".to_string());
+    let error = parser.tree().unwrap_err();
+    assert_eq!(format!("{}", error), "line 1, column 2: expected ';'");
+}
+
+#[test]
+fn rejects_points_outside_of_the_board() {
+    let parser = Parser::new("(;SZ[9]\n;B[ak])".to_string());
+    let error = parser.tree().unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(error.column, 2);
+    assert_eq!(error.message, "B: point 'ak' is outside of the 9x9 board");
+}
+
+#[test]
+fn rejects_rectangles_past_the_edge_of_the_board() {
+    let parser = Parser::new("(;SZ[5]AB[aa:ff])".to_string());
+    let error = parser.tree().unwrap_err();
+    assert_eq!(format!("{}", error), "line 1, column 8: AB: point 'af' is outside of the 5x5 board");
+}
+
+#[test]
+fn still_accepts_tt_as_a_pass() {
+    let parser = Parser::new("(;SZ[9];B[tt])".to_string());
+    assert!(parser.game().is_ok());
+}
@@ -0,0 +1,90 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Black;
+use board::Coord;
+use board::Pass;
+use board::Play;
+use board::White;
+use game::Game;
+use ruleset::Japanese;
+use ruleset::KgsChinese;
+use sgf::Parser;
+use sgf::Writer;
+use version;
+
+#[test]
+fn writes_the_game_information() {
+    let game = Game::new(9, 5.5, Japanese);
+    let mut writer = Writer::new(&game);
+    writer.set_player(Black, "Foo");
+    writer.set_player(White, "Bar");
+    writer.set_result("W+R");
+    let expected = format!("(;GM[1]FF[4]CA[UTF-8]AP[Iomrascalai:{}]SZ[9]KM[5.5]RU[japanese]PB[Foo]PW[Bar]RE[W+R])\n", version::version());
+    assert_eq!(expected, writer.sgf());
+}
+
+#[test]
+fn writes_the_moves_with_comments() {
+    let game = Game::new(9, 6.5, KgsChinese)
+        .play(Play(Black, 3, 3)).unwrap()
+        .play(Play(White, 7, 7)).unwrap()
+        .play(Pass(Black)).unwrap();
+    let mut writer = Writer::new(&game);
+    writer.set_comment(1, "100 playouts");
+    let sgf = writer.sgf();
+    assert!(sgf.ends_with("\n;B[cg]\n;W[gc]C[100 playouts]\n;B[])\n"));
+}
+
+#[test]
+fn writes_the_handicap_stones_as_setup() {
+    let game = Game::new(9, 0.5, KgsChinese)
+        .place_handicap(&[Coord::new(3, 3), Coord::new(7, 7)]).unwrap()
+        .play(Play(White, 5, 5)).unwrap();
+    let sgf = Writer::new(&game).sgf();
+    assert!(sgf.contains("HA[2]"));
+    assert!(sgf.ends_with("AB[cg][gc]\n;W[ee])\n"));
+}
+
+#[test]
+fn escapes_text_values() {
+    let game = Game::new(9, 6.5, KgsChinese);
+    let mut writer = Writer::new(&game);
+    writer.set_player(Black, "a]b\\c");
+    assert!(writer.sgf().contains("PB[a\\]b\\\\c]"));
+}
+
+#[test]
+fn can_be_read_by_the_parser() {
+    let game = Game::new(9, 6.5, KgsChinese)
+        .place_handicap(&[Coord::new(3, 3), Coord::new(7, 7)]).unwrap()
+        .play(Play(White, 5, 5)).unwrap()
+        .play(Play(Black, 1, 9)).unwrap()
+        .play(Pass(White)).unwrap();
+    let mut writer = Writer::new(&game);
+    writer.set_comment(2, "a comment with a ] in it");
+    let parsed = Parser::new(writer.sgf()).game().unwrap();
+    assert_eq!(parsed.moves(), game.moves());
+    assert_eq!(parsed.handicap(), 2);
+    assert_eq!(parsed.komi(), 6.5);
+}
@@ -0,0 +1,73 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use super::property::Property;
+
+/// A node in an SGF game tree. The first child continues the main
+/// line, all other children start variations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub children: Vec<Node>,
+    pub properties: Vec<Property>,
+}
+
+impl Node {
+
+    pub fn new(properties: Vec<Property>) -> Node {
+        Node {
+            children: vec!(),
+            properties: properties,
+        }
+    }
+
+    /// The nodes of the main line starting with this node.
+    pub fn main_line(&self) -> Vec<&Node> {
+        self.variation(&[]).unwrap()
+    }
+
+    /// The nodes of a variation starting with this node. At the n-th
+    /// node with more than one child `choices[n]` decides which
+    /// child to follow. Once all choices are used up the first child
+    /// is followed. Returns `None` if a choice doesn't exist.
+    pub fn variation(&self, choices: &[usize]) -> Option<Vec<&Node>> {
+        let mut nodes = vec!(self);
+        let mut current = self;
+        let mut remaining = choices;
+        while current.children.len() > 0 {
+            let index = if current.children.len() > 1 && remaining.len() > 0 {
+                let index = remaining[0];
+                remaining = &remaining[1..];
+                index
+            } else {
+                0
+            };
+            match current.children.get(index) {
+                Some(child) => {
+                    nodes.push(child);
+                    current = child;
+                },
+                None => return None
+            }
+        }
+        Some(nodes)
+    }
+
+}
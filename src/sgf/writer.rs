@@ -0,0 +1,133 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Black;
+use board::Color;
+use board::Coord;
+use board::Pass;
+use board::Play;
+use board::White;
+use game::Game;
+use version;
+
+use std::collections::HashMap;
+
+/// Serializes the history of a game to SGF (FF[4]).
+pub struct Writer<'a> {
+    black_player: Option<String>,
+    comments: HashMap<usize, String>,
+    game: &'a Game,
+    result: Option<String>,
+    white_player: Option<String>,
+}
+
+impl<'a> Writer<'a> {
+
+    pub fn new(game: &'a Game) -> Writer<'a> {
+        Writer {
+            black_player: None,
+            comments: HashMap::new(),
+            game: game,
+            result: None,
+            white_player: None,
+        }
+    }
+
+    pub fn set_player(&mut self, color: Color, name: &str) {
+        match color {
+            Black => { self.black_player = Some(name.to_string()); },
+            White => { self.white_player = Some(name.to_string()); },
+            _ => {}
+        }
+    }
+
+    /// The result in SGF notation, e.g. "B+R" or "W+3.5".
+    pub fn set_result(&mut self, result: &str) {
+        self.result = Some(result.to_string());
+    }
+
+    /// Adds a comment to the move with the given index into
+    /// `Game::moves()`.
+    pub fn set_comment(&mut self, index: usize, comment: &str) {
+        self.comments.insert(index, comment.to_string());
+    }
+
+    pub fn sgf(&self) -> String {
+        let mut sgf = String::from("(;GM[1]FF[4]CA[UTF-8]");
+        sgf.push_str(&format!("AP[Iomrascalai:{}]", version::version()));
+        sgf.push_str(&format!("SZ[{}]", self.game.size()));
+        sgf.push_str(&format!("KM[{}]", self.game.komi()));
+        sgf.push_str(&format!("RU[{}]", Self::escape(&format!("{}", self.game.ruleset()))));
+        let handicap = self.game.handicap() as usize;
+        if handicap > 0 {
+            sgf.push_str(&format!("HA[{}]", handicap));
+        }
+        if let Some(ref name) = self.black_player {
+            sgf.push_str(&format!("PB[{}]", Self::escape(name)));
+        }
+        if let Some(ref name) = self.white_player {
+            sgf.push_str(&format!("PW[{}]", Self::escape(name)));
+        }
+        if let Some(ref result) = self.result {
+            sgf.push_str(&format!("RE[{}]", Self::escape(result)));
+        }
+        let moves = self.game.moves();
+        if handicap > 0 {
+            sgf.push_str("AB");
+            for m in moves[0..handicap].iter() {
+                sgf.push_str(&format!("[{}]", self.point(m.coord())));
+            }
+        }
+        for (index, m) in moves.iter().enumerate().skip(handicap) {
+            let node = match *m {
+                Play(color, col, row) => {
+                    format!("\n;{}[{}]", Self::color(color), self.point(Coord::new(col, row)))
+                },
+                Pass(color) => format!("\n;{}[]", Self::color(color)),
+                _ => continue
+            };
+            sgf.push_str(&node);
+            if let Some(comment) = self.comments.get(&index) {
+                sgf.push_str(&format!("C[{}]", Self::escape(comment)));
+            }
+        }
+        sgf.push_str(")\n");
+        sgf
+    }
+
+    fn color(color: Color) -> &'static str {
+        match color {
+            White => "W",
+            _ => "B"
+        }
+    }
+
+    fn point(&self, coord: Coord) -> String {
+        let x = (b'a' + coord.col - 1) as char;
+        let y = (b'a' + self.game.size() - coord.row) as char;
+        format!("{}{}", x, y)
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace("\\", "\\\\").replace("]", "\\]")
+    }
+
+}
@@ -57,6 +57,10 @@ impl Timer {
 
     }
 
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.config = config;
+    }
+
     pub fn setup(&mut self, main_in_s: i64, byo_in_s: i64, stones: i32) {
         self.set_main_time(main_in_s * 1000);
         self.set_byo_time(byo_in_s * 1000);
@@ -0,0 +1,384 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Move;
+use board::NoMove;
+use board::White;
+use book::Book;
+use config::Config;
+use config::describe_errors;
+use game::Game;
+use patterns::Matcher;
+use playout::Playout;
+use ruleset::KgsChinese;
+use sgf::Parser;
+use sgf::property::Property;
+use sgf::tree::Node;
+use version;
+
+use getopts::Options;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::XorShiftRng;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+mod test;
+
+// The exploration constant of the UCB1 formula used to pick the
+// moves inside of the book.
+const EXPLORATION: f32 = 1.0;
+
+/// The settings of a book building run.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub depth: usize,
+    pub games: usize,
+    pub komi: f32,
+    pub seed: u32,
+    pub size: u8,
+}
+
+// A position of the book, reached by playing `m`. The positions are
+// identified by their canonical Zobrist hash (see `Book`), so the
+// rotations and reflections of a move share an entry.
+#[derive(Debug)]
+pub struct Entry {
+    pub children: Vec<usize>,
+    pub key: u64,
+    pub m: Move,
+    pub visits: usize,
+    pub wins: usize,
+}
+
+/// Builds an opening book by playing games from the empty board.
+///
+/// The opening moves of each game (up to `depth` moves) are picked
+/// from the positions already in the book with UCB1, the rest of the
+/// game is played out with the playout policy. Each game adds one new
+/// position to the book. The random numbers of each game only depend
+/// on the seed and the number of the game, so the same seed always
+/// produces the same book, even if the building is interrupted and
+/// resumed from the output.
+pub struct Builder {
+    entries: Vec<Entry>,
+    games: usize,
+    playout: Playout,
+    settings: Settings,
+}
+
+impl Builder {
+
+    pub fn new(settings: Settings, config: Arc<Config>) -> Builder {
+        let root = Entry { children: vec!(), key: 0, m: NoMove, visits: 0, wins: 0 };
+        Builder {
+            entries: vec!(root),
+            games: 0,
+            playout: Playout::new(config, Arc::new(Matcher::new())),
+            settings: settings,
+        }
+    }
+
+    /// Continues from a book written by `output()`.
+    pub fn resume(&mut self, sgf: &str) -> Result<(), String> {
+        let root = try!(Parser::new(sgf.to_string()).tree().map_err(|e| e.to_string()));
+        for property in root.properties.iter() {
+            match *property {
+                Property::Comment(ref comment) => {
+                    self.games = try!(Self::statistic(comment, "games"));
+                },
+                Property::Size(size) if size != self.settings.size => {
+                    return Err(format!("the book is for {}x{} instead of {}x{}", size, size, self.settings.size, self.settings.size));
+                },
+                _ => {}
+            }
+        }
+        self.entries[0].visits = self.games;
+        let game = self.new_game();
+        self.resume_children(0, &root, &game)
+    }
+
+    fn resume_children(&mut self, parent: usize, node: &Node, game: &Game) -> Result<(), String> {
+        for child in node.children.iter() {
+            let m = match Book::move_of(child, game.size()) {
+                Some(m) => m,
+                None => return Err("every node needs a move".to_string())
+            };
+            let comment = child.properties.iter()
+                .filter_map(|property| match *property {
+                    Property::Comment(ref comment) => Some(comment.clone()),
+                    _ => None
+                })
+                .next()
+                .unwrap_or(String::new());
+            let next = try!(game.play(m).map_err(|e| format!("illegal move: {}", e)));
+            let index = self.entries.len();
+            self.entries.push(Entry {
+                children: vec!(),
+                key: Book::canonical_key(&next.board()),
+                m: m,
+                visits: try!(Self::statistic(&comment, "visits")),
+                wins: try!(Self::statistic(&comment, "wins")),
+            });
+            self.entries[parent].children.push(index);
+            try!(self.resume_children(index, child, &next));
+        }
+        Ok(())
+    }
+
+    // Finds "name N" in a comment like "visits 10 wins 4".
+    fn statistic(comment: &str, name: &str) -> Result<usize, String> {
+        let words: Vec<&str> = comment.split_whitespace().collect();
+        match words.iter().position(|&word| word == name).and_then(|i| words.get(i + 1)) {
+            Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for {}", value, name)),
+            None => Err(format!("missing {} in {:?}", name, comment))
+        }
+    }
+
+    fn new_game(&self) -> Game {
+        Game::new(self.settings.size, self.settings.komi, KgsChinese)
+    }
+
+    /// Plays one game and records the result for all book positions
+    /// of the game.
+    pub fn play_game(&mut self) {
+        let seed = [self.settings.seed, self.games as u32, 0x193a6754, 0xa8a7d469];
+        let mut rng = XorShiftRng::from_seed(seed);
+        let mut game = self.new_game();
+        let mut path = vec!(0);
+        let mut current = 0;
+        while path.len() <= self.settings.depth {
+            match self.select(current, &game, &mut rng) {
+                Some((index, next)) => {
+                    path.push(index);
+                    game = next;
+                    let expanded = self.entries[index].visits == 0;
+                    current = index;
+                    if expanded {
+                        break;
+                    }
+                },
+                None => break
+            }
+        }
+        let mut board = game.board();
+        let winner = self.playout.run(&mut board, None, &mut rng).winner();
+        for &index in path.iter() {
+            let entry = &mut self.entries[index];
+            entry.visits += 1;
+            if index > 0 && *entry.m.color() == winner {
+                entry.wins += 1;
+            }
+        }
+        self.games += 1;
+    }
+
+    // Picks a position that isn't in the book yet at random (and adds
+    // it) or the child with the highest UCB1 value once all moves
+    // were tried. Symmetric moves lead to the same position and are
+    // only tried once.
+    fn select(&mut self, parent: usize, game: &Game, rng: &mut XorShiftRng) -> Option<(usize, Game)> {
+        let mut new = vec!();
+        let mut known = vec!();
+        for m in game.legal_moves_without_eyes() {
+            let next = game.play(m).unwrap();
+            let key = Book::canonical_key(&next.board());
+            let existing = self.entries[parent].children.iter().cloned().find(|&i| self.entries[i].key == key);
+            match existing {
+                Some(index) => if !known.iter().any(|&(i, _)| i == index) {
+                    known.push((index, next));
+                },
+                None => if !new.iter().any(|&(k, _, _)| k == key) {
+                    new.push((key, m, next));
+                }
+            }
+        }
+        if !new.is_empty() {
+            let (key, m, next) = new.swap_remove(rng.gen_range(0, new.len()));
+            let index = self.entries.len();
+            self.entries.push(Entry { children: vec!(), key: key, m: m, visits: 0, wins: 0 });
+            self.entries[parent].children.push(index);
+            return Some((index, next));
+        }
+        let log_visits = (self.entries[parent].visits as f32).ln();
+        let mut best: Option<(f32, usize, Game)> = None;
+        for (index, next) in known {
+            let entry = &self.entries[index];
+            let visits = entry.visits as f32;
+            let value = entry.wins as f32 / visits + EXPLORATION * (log_visits / visits).sqrt();
+            if best.as_ref().map(|&(v, _, _)| value > v).unwrap_or(true) {
+                best = Some((value, index, next));
+            }
+        }
+        best.map(|(_, index, next)| (index, next))
+    }
+
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// The number of positions in the book (not counting the empty
+    /// board).
+    pub fn position_count(&self) -> usize {
+        self.entries.len() - 1
+    }
+
+    /// The book as an SGF game tree. The comment of each move holds
+    /// the number of games that went through it and how many of them
+    /// the player of the move won.
+    pub fn output(&self) -> String {
+        let mut sgf = String::from("(;GM[1]FF[4]CA[UTF-8]");
+        sgf.push_str(&format!("AP[Iomrascalai:{}]", version::version()));
+        sgf.push_str(&format!("SZ[{}]KM[{}]", self.settings.size, self.settings.komi));
+        sgf.push_str(&format!("C[games {}]", self.games));
+        self.output_children(0, &mut sgf);
+        sgf.push_str(")\n");
+        sgf
+    }
+
+    fn output_children(&self, parent: usize, sgf: &mut String) {
+        let children = &self.entries[parent].children;
+        for &index in children.iter() {
+            let entry = &self.entries[index];
+            if children.len() > 1 {
+                sgf.push_str("\n(");
+            } else {
+                sgf.push_str("\n");
+            }
+            let color = if *entry.m.color() == White { "W" } else { "B" };
+            let coord = entry.m.coord();
+            let x = (b'a' + coord.col - 1) as char;
+            let y = (b'a' + self.settings.size - coord.row) as char;
+            sgf.push_str(&format!(";{}[{}{}]C[visits {} wins {}]", color, x, y, entry.visits, entry.wins));
+            self.output_children(index, sgf);
+            if children.len() > 1 {
+                sgf.push_str(")");
+            }
+        }
+    }
+
+}
+
+pub fn run(program: &str, args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "Print this help menu");
+    opts.optflag("r", "resume", "Continue with the book in the output file");
+    opts.optopt("c", "config", "Config file (for the playout policy)", "FILE");
+    opts.optopt("d", "depth", "Number of moves in the book (defaults to 10)", "NUM");
+    opts.optopt("g", "games", "Number of games to play (defaults to 1000)", "NUM");
+    opts.optopt("k", "komi", "Komi (defaults to 7)", "NUM");
+    opts.optopt("o", "output", "Book file to write (defaults to book.sgf)", "FILE");
+    opts.optopt("s", "seed", "Seed of the random numbers (defaults to 1)", "NUM");
+    opts.optopt("z", "size", "Board size (defaults to 9)", "NUM");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            println!("{}", f.to_string());
+            return 1;
+        }
+    };
+    if matches.opt_present("h") {
+        let brief = format!("Usage: {} [options]", program);
+        println!("{}", opts.usage(brief.as_ref()));
+        return 0;
+    }
+    let settings = match parse_settings(&matches) {
+        Ok(settings) => settings,
+        Err(error) => {
+            println!("{}", error);
+            return 1;
+        }
+    };
+    let config = match matches.opt_str("c") {
+        Some(filename) => Config::from_file(filename.clone(), false, false, KgsChinese)
+            .map_err(|errors| describe_errors(&filename, &errors)),
+        None => Ok(Config::default(false, false, KgsChinese))
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{}", error);
+            return 1;
+        }
+    };
+    let output = matches.opt_str("o").unwrap_or("book.sgf".to_string());
+    let mut builder = Builder::new(settings, Arc::new(config));
+    if matches.opt_present("r") && Path::new(&output).exists() {
+        let mut contents = String::new();
+        let result = File::open(&output)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| e.to_string())
+            .and_then(|_| builder.resume(&contents));
+        if let Err(error) = result {
+            println!("{}: {}", output, error);
+            return 1;
+        }
+        println!("Resuming after {} games", builder.games());
+    }
+    // Writing the book every now and then makes it possible to
+    // resume after an interruption.
+    while builder.games() < settings.games {
+        builder.play_game();
+        if builder.games() % 100 == 0 || builder.games() == settings.games {
+            if let Err(error) = write(&output, &builder) {
+                println!("{}", error);
+                return 1;
+            }
+            println!("Played {} games ({} positions)", builder.games(), builder.position_count());
+        }
+    }
+    println!("Wrote {}", output);
+    0
+}
+
+fn write(output: &str, builder: &Builder) -> Result<(), String> {
+    File::create(output)
+        .and_then(|mut file| file.write_all(builder.output().as_bytes()))
+        .map_err(|e| format!("{}: {}", output, e))
+}
+
+fn parse_settings(matches: &::getopts::Matches) -> Result<Settings, String> {
+    let size = try!(parse_opt(matches, "z", 9));
+    if size < 2 || size > 19 {
+        return Err("the board size needs to be between 2 and 19".to_string());
+    }
+    let komi = match matches.opt_str("k") {
+        Some(value) => try!(value.parse().map_err(|_| format!("invalid komi {:?}", value))),
+        None => 7.0
+    };
+    Ok(Settings {
+        depth: try!(parse_opt(matches, "d", 10)),
+        games: try!(parse_opt(matches, "g", 1000)),
+        komi: komi,
+        seed: try!(parse_opt(matches, "s", 1)) as u32,
+        size: size as u8,
+    })
+}
+
+fn parse_opt(matches: &::getopts::Matches, name: &str, default: usize) -> Result<usize, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
@@ -0,0 +1,113 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+
+#![cfg(test)]
+
+use board::Black;
+use book::Book;
+use config::Config;
+use game::Game;
+use ruleset::KgsChinese;
+use sgf::Parser;
+use super::Builder;
+use super::Settings;
+
+use rand::SeedableRng;
+use rand::XorShiftRng;
+use std::sync::Arc;
+
+fn builder() -> Builder {
+    let settings = Settings { depth: 4, games: 20, komi: 7.0, seed: 7, size: 5 };
+    Builder::new(settings, Arc::new(Config::test_config()))
+}
+
+fn build(games: usize) -> Builder {
+    let mut builder = builder();
+    for _ in 0..games {
+        builder.play_game();
+    }
+    builder
+}
+
+#[test]
+fn is_deterministic_given_a_seed() {
+    assert_eq!(build(20).output(), build(20).output());
+}
+
+#[test]
+fn resuming_gives_the_same_book() {
+    let mut resumed = builder();
+    resumed.resume(&build(10).output()).unwrap();
+    assert_eq!(10, resumed.games());
+    for _ in 0..10 {
+        resumed.play_game();
+    }
+    assert_eq!(build(20).output(), resumed.output());
+}
+
+#[test]
+fn adds_one_position_per_game() {
+    let builder = build(20);
+    assert_eq!(20, builder.games());
+    assert_eq!(20, builder.position_count());
+}
+
+#[test]
+fn merges_symmetric_moves() {
+    // A 5x5 board has only 6 first moves that aren't rotations or
+    // reflections of each other.
+    let builder = build(20);
+    let root = Parser::new(builder.output()).tree().unwrap();
+    assert_eq!(6, root.children.len());
+}
+
+#[test]
+fn writes_a_book_that_can_be_loaded() {
+    let root = Parser::new(build(20).output()).tree().unwrap();
+    let mut book = Book::new();
+    book.add_tree(&root, 10).unwrap();
+    let game = Game::new(5, 7.0, KgsChinese);
+    let m = book.pick(&game, Black, &mut XorShiftRng::from_seed([1, 2, 3, 4]));
+    assert!(m.is_some());
+    assert!(game.play(m.unwrap()).is_ok());
+}
+
+#[test]
+fn reports_invalid_statistics_when_resuming() {
+    let mut builder = builder();
+    let result = builder.resume("(;SZ[5]C[games 1];B[cc]C[visits x wins 0])");
+    assert_eq!(Err("invalid number \"x\" for visits".to_string()), result);
+}
+
+#[test]
+fn reports_passes_when_resuming() {
+    let mut builder = builder();
+    let result = builder.resume("(;SZ[5]C[games 1];B[tt]C[visits 1 wins 0])");
+    assert_eq!(Err("every node needs a move".to_string()), result);
+}
+
+#[test]
+fn reports_books_for_other_board_sizes_when_resuming() {
+    let mut builder = builder();
+    let result = builder.resume("(;SZ[19]C[games 1];B[ss]C[visits 1 wins 0])");
+    assert_eq!(Err("the book is for 19x19 instead of 5x5".to_string()), result);
+}
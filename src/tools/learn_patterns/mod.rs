@@ -0,0 +1,364 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Board;
+use board::Coord;
+use board::Move;
+use board::Play;
+use game::Game;
+use patterns::Feature;
+use patterns::dictionary::MAX_DISTANCE;
+use patterns::dictionary::MIN_DISTANCE;
+use patterns::dictionary::PointState;
+use patterns::dictionary::Shapes;
+use sgf::Parser;
+
+use getopts::Options;
+use std::cmp;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+mod test;
+
+/// The settings of a learning run.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub iterations: usize,
+    pub max_distance: u8,
+    pub min_count: usize,
+}
+
+// A position of the training set. Each candidate is the list of
+// features (the indexes into the gammas) of one or more legal moves,
+// together with the number of moves that share them.
+#[derive(Debug)]
+pub struct Position {
+    pub candidates: Vec<(Vec<usize>, usize)>,
+    pub winner: usize,
+}
+
+/// Harvests the patterns and the tactical features of the moves
+/// played in a collection of games and fits their gammas with the
+/// Minorization-Maximization algorithm described in "Computing Elo
+/// Ratings of Move Patterns in the Game of Go" by Rémi Coulom.
+pub struct Learner {
+    counts: HashMap<u64, usize>,
+    gammas: Vec<f64>,
+    patterns: HashMap<u64, usize>,
+    positions: Vec<Position>,
+    settings: Settings,
+    shapes: Shapes,
+    states: Vec<Vec<PointState>>,
+}
+
+impl Learner {
+
+    pub fn new(settings: Settings) -> Learner {
+        Learner {
+            counts: HashMap::new(),
+            gammas: vec!(1.0; Feature::all().len()),
+            patterns: HashMap::new(),
+            positions: vec!(),
+            settings: settings,
+            shapes: Shapes::new(),
+            states: vec!(),
+        }
+    }
+
+    /// Counts the patterns of the played moves. Only the patterns
+    /// that are played at least `min_count` times are learned.
+    pub fn count_patterns(&mut self, game: &Game, m: &Move) {
+        let states = self.shapes.extract(&game.board(), &m.coord(), *m.color(), self.settings.max_distance);
+        for distance in MIN_DISTANCE..self.settings.max_distance+1 {
+            let shape = &states[0..self.shapes.size(distance)];
+            let hash = self.shapes.canonical_hash(shape);
+            let count = self.counts.entry(hash).or_insert(0);
+            *count += 1;
+            if *count == self.settings.min_count {
+                self.patterns.insert(hash, Feature::all().len() + self.states.len());
+                self.states.push(shape.to_vec());
+                self.gammas.push(1.0);
+            }
+        }
+    }
+
+    /// Adds the position before the move to the training set. The
+    /// position is skipped if the played move isn't one of the
+    /// candidates (e.g. because it fills an eye).
+    pub fn add_position(&mut self, game: &Game, m: &Move) {
+        let board = game.board();
+        if board.next_player() != *m.color() {
+            return;
+        }
+        let last_move = match game.last_move() {
+            Play(_, col, row) => Some(Coord::new(col, row)),
+            _ => None
+        };
+        let mut teams: HashMap<Vec<usize>, usize> = HashMap::new();
+        let mut winner = None;
+        for candidate in game.legal_moves_without_eyes() {
+            let team = self.team(&board, &candidate, last_move);
+            if candidate == *m {
+                winner = Some(team.clone());
+            }
+            *teams.entry(team).or_insert(0) += 1;
+        }
+        if let Some(winner) = winner {
+            let candidates: Vec<(Vec<usize>, usize)> = teams.into_iter().collect();
+            let index = candidates.iter().position(|&(ref team, _)| *team == winner).unwrap();
+            self.positions.push(Position { candidates: candidates, winner: index });
+        }
+    }
+
+    // The features of the move: the largest pattern that is learned
+    // and the tactical features.
+    fn team(&self, board: &Board, m: &Move, last_move: Option<Coord>) -> Vec<usize> {
+        let mut team: Vec<usize> = Feature::of_move(board, m, last_move).iter()
+            .map(|feature| Feature::all().iter().position(|f| f == feature).unwrap())
+            .collect();
+        if !self.patterns.is_empty() {
+            let states = self.shapes.extract(board, &m.coord(), *m.color(), self.settings.max_distance);
+            let pattern = (MIN_DISTANCE..self.settings.max_distance+1).rev()
+                .map(|distance| self.shapes.canonical_hash(&states[0..self.shapes.size(distance)]))
+                .filter_map(|hash| self.patterns.get(&hash))
+                .next();
+            if let Some(&index) = pattern {
+                team.push(index);
+            }
+        }
+        team.sort();
+        team
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn position_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Runs the given number of iterations of the
+    /// Minorization-Maximization algorithm.
+    pub fn fit(&mut self) {
+        let groups = self.groups();
+        for _ in 0..self.settings.iterations {
+            for group in groups.iter() {
+                minorization_maximization(&mut self.gammas, group, &self.positions);
+            }
+        }
+    }
+
+    // Each tactical feature is a group of its own and all patterns
+    // form one group, as at most one of them matches a move.
+    fn groups(&self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = (0..Feature::all().len()).map(|i| vec!(i)).collect();
+        if !self.states.is_empty() {
+            groups.push((Feature::all().len()..self.gammas.len()).collect());
+        }
+        groups
+    }
+
+    pub fn feature_gamma(&self, feature: Feature) -> f32 {
+        let index = Feature::all().iter().position(|&f| f == feature).unwrap();
+        self.gammas[index] as f32
+    }
+
+    /// The learned gammas in the format of the pattern file (see
+    /// `Dictionary`). The patterns are sorted by their gamma.
+    pub fn output(&self) -> String {
+        let mut s = String::new();
+        s.push_str(&format!("# Learned from {} positions\n", self.positions.len()));
+        s.push_str(&format!("# {} patterns (distance {} to {}) and {} features\n",
+            self.states.len(), MIN_DISTANCE, self.settings.max_distance, Feature::all().len()));
+        for feature in Feature::all() {
+            s.push_str(&format!("{:.4} {}\n", self.feature_gamma(feature), feature.name()));
+        }
+        let offset = Feature::all().len();
+        let mut patterns: Vec<usize> = (0..self.states.len()).collect();
+        patterns.sort_by(|&a, &b| self.gammas[offset + b].partial_cmp(&self.gammas[offset + a]).unwrap());
+        for i in patterns {
+            let shape: String = self.states[i].iter().map(|state| state.to_char()).collect();
+            s.push_str(&format!("{:.4} {}\n", self.gammas[offset + i], shape));
+        }
+        s
+    }
+
+}
+
+// Updates the gammas of one group. Every feature gets a prior of one
+// virtual win and one virtual loss against an opponent with a gamma
+// of 1.0 so that features that never win don't end up with a gamma
+// of 0.
+pub fn minorization_maximization(gammas: &mut Vec<f64>, group: &[usize], positions: &[Position]) {
+    let mut wins = vec!(0.0; gammas.len());
+    let mut denominators = vec!(0.0; gammas.len());
+    for position in positions {
+        for &i in position.candidates[position.winner].0.iter() {
+            wins[i] += 1.0;
+        }
+        let strengths: Vec<f64> = position.candidates.iter()
+            .map(|&(ref team, _)| team.iter().fold(1.0, |acc, &i| acc * gammas[i]))
+            .collect();
+        let total = position.candidates.iter()
+            .zip(strengths.iter())
+            .fold(0.0, |acc, (&(_, count), strength)| acc + count as f64 * strength);
+        for (&(ref team, count), strength) in position.candidates.iter().zip(strengths.iter()) {
+            for &i in team.iter() {
+                denominators[i] += count as f64 * strength / gammas[i] / total;
+            }
+        }
+    }
+    for &i in group {
+        gammas[i] = (wins[i] + 1.0) / (denominators[i] + 2.0 / (gammas[i] + 1.0));
+    }
+}
+
+/// Calls `f` with every position of the main line of the game and
+/// the move that was played in it. Passes and setup stones are
+/// skipped.
+pub fn each_position<F>(path: &Path, mut f: F) -> Result<(), String> where F: FnMut(&Game, &Move) {
+    let parser = try!(Parser::from_path(path).map_err(|e| format!("{}: {}", path.display(), e)));
+    let tree = try!(parser.tree().map_err(|e| format!("{}: {}", path.display(), e)));
+    let mut report = |game: &Game, m: &Move| {
+        if !m.is_pass() {
+            f(game, m);
+        }
+    };
+    try!(Parser::replay_moves(&tree.main_line(), &mut report).map_err(|e| format!("{}: {}", path.display(), e)));
+    Ok(())
+}
+
+/// Collects the SGF files in the given paths. Directories are
+/// searched recursively.
+pub fn sgf_files(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec!();
+    for path in paths {
+        try!(collect_sgf_files(Path::new(path), &mut files).map_err(|e| format!("{}: {}", path, e)));
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn collect_sgf_files(path: &Path, files: &mut Vec<PathBuf>) -> ::std::io::Result<()> {
+    if try!(fs::metadata(path)).is_dir() {
+        for entry in try!(fs::read_dir(path)) {
+            let entry = try!(entry);
+            let path = entry.path();
+            let is_sgf = path.extension().map(|e| e == "sgf").unwrap_or(false);
+            if is_sgf || try!(fs::metadata(&path)).is_dir() {
+                try!(collect_sgf_files(&path, files));
+            }
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+/// Replays all games twice (first to count the patterns and then to
+/// collect the positions) and fits the gammas.
+pub fn learn(files: &[PathBuf], settings: Settings) -> Result<Learner, String> {
+    let mut learner = Learner::new(settings);
+    for file in files {
+        try!(each_position(file, |game, m| learner.count_patterns(game, m)));
+    }
+    for file in files {
+        try!(each_position(file, |game, m| learner.add_position(game, m)));
+    }
+    learner.fit();
+    Ok(learner)
+}
+
+pub fn run(program: &str, args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "Print this help menu");
+    opts.optopt("d", "max-distance", &format!("Largest pattern distance ({} to {}, defaults to {})", MIN_DISTANCE, MAX_DISTANCE, MAX_DISTANCE), "NUM");
+    opts.optopt("i", "iterations", "Number of iterations (defaults to 20)", "NUM");
+    opts.optopt("m", "min-count", "Only learn patterns played at least this often (defaults to 5)", "NUM");
+    opts.optopt("o", "output", "Pattern file to write (defaults to patterns.pat)", "FILE");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            println!("{}", f.to_string());
+            return 1;
+        }
+    };
+    if matches.opt_present("h") || matches.free.is_empty() {
+        let brief = format!("Usage: {} [options] FILE|DIR...", program);
+        println!("{}", opts.usage(brief.as_ref()));
+        return if matches.opt_present("h") { 0 } else { 1 };
+    }
+    let settings = match parse_settings(&matches) {
+        Ok(settings) => settings,
+        Err(error) => {
+            println!("{}", error);
+            return 1;
+        }
+    };
+    let output = matches.opt_str("o").unwrap_or("patterns.pat".to_string());
+    let result = sgf_files(&matches.free)
+        .and_then(|files| {
+            println!("Learning from {} files", files.len());
+            learn(&files, settings)
+        })
+        .and_then(|learner| {
+            println!("Fitted {} patterns to {} positions", learner.pattern_count(), learner.position_count());
+            File::create(&output)
+                .and_then(|mut file| file.write_all(learner.output().as_bytes()))
+                .map_err(|e| format!("{}: {}", output, e))
+        });
+    match result {
+        Ok(_) => {
+            println!("Wrote {}", output);
+            0
+        },
+        Err(error) => {
+            println!("{}", error);
+            1
+        }
+    }
+}
+
+fn parse_settings(matches: &::getopts::Matches) -> Result<Settings, String> {
+    let iterations = try!(parse_opt(matches, "i", 20));
+    let max_distance = try!(parse_opt(matches, "d", MAX_DISTANCE as usize));
+    if max_distance < MIN_DISTANCE as usize || max_distance > MAX_DISTANCE as usize {
+        return Err(format!("the max distance needs to be between {} and {}", MIN_DISTANCE, MAX_DISTANCE));
+    }
+    let min_count = try!(parse_opt(matches, "m", 5));
+    Ok(Settings {
+        iterations: iterations,
+        max_distance: max_distance as u8,
+        min_count: cmp::max(min_count, 1),
+    })
+}
+
+fn parse_opt(matches: &::getopts::Matches, name: &str, default: usize) -> Result<usize, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
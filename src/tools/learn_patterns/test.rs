@@ -0,0 +1,128 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+#![cfg(test)]
+
+use board::Play;
+use board::White;
+use patterns::Dictionary;
+use patterns::Feature;
+use super::Learner;
+use super::Position;
+use super::Settings;
+use super::each_position;
+use super::learn;
+use super::minorization_maximization;
+use super::sgf_files;
+
+use std::path::Path;
+
+fn settings() -> Settings {
+    Settings { iterations: 10, max_distance: 4, min_count: 1 }
+}
+
+#[test]
+fn minorization_maximization_favours_the_winning_feature() {
+    // Feature 0 wins 3 out of 4 times against feature 1.
+    let mut positions = vec!();
+    for i in 0..4 {
+        let winner = if i == 0 { 1 } else { 0 };
+        positions.push(Position { candidates: vec!((vec!(0), 1), (vec!(1), 1)), winner: winner });
+    }
+    let mut gammas = vec!(1.0, 1.0);
+    for _ in 0..50 {
+        minorization_maximization(&mut gammas, &[0], &positions);
+        minorization_maximization(&mut gammas, &[1], &positions);
+    }
+    let ratio = gammas[0] / gammas[1];
+    assert!(ratio > 2.0 && ratio < 3.0, "ratio: {}", ratio);
+}
+
+#[test]
+fn minorization_maximization_takes_the_counts_into_account() {
+    // The feature is one of ten moves and always wins.
+    let positions = vec!(Position { candidates: vec!((vec!(), 9), (vec!(0), 1)), winner: 1 });
+    let mut gammas = vec!(1.0);
+    for _ in 0..50 {
+        minorization_maximization(&mut gammas, &[0], &positions);
+    }
+    assert!(gammas[0] > 3.0, "gamma: {}", gammas[0]);
+}
+
+#[test]
+fn each_position_skips_the_setup_stones() {
+    let mut moves = vec!();
+    each_position(Path::new("fixtures/sgf/games/more/handicap.sgf"), |game, m| {
+        moves.push((game.board().vacant_point_count(), *m));
+    }).unwrap();
+    assert_eq!(17, moves.len());
+    assert_eq!((79, Play(White, 7, 7)), moves[0]);
+}
+
+#[test]
+fn sgf_files_are_found_recursively() {
+    let files = sgf_files(&["fixtures/sgf/games".to_string()]).unwrap();
+    assert_eq!(2, files.len());
+    assert!(files.iter().all(|file| file.extension().unwrap() == "sgf"));
+}
+
+#[test]
+fn sgf_files_fails_for_missing_paths() {
+    assert!(sgf_files(&["fixtures/sgf/missing".to_string()]).is_err());
+}
+
+#[test]
+fn the_patterns_and_features_are_learned() {
+    let files = sgf_files(&["fixtures/sgf/games".to_string()]).unwrap();
+    let learner = learn(&files, settings()).unwrap();
+    assert!(learner.pattern_count() > 0);
+    // Most of the 43 moves of the two games are candidates.
+    assert!(learner.position_count() > 20);
+    // The distance to the last move is a strong feature in every
+    // game.
+    assert!(learner.feature_gamma(Feature::Distance2) > 1.0);
+}
+
+#[test]
+fn min_count_limits_the_patterns() {
+    let files = sgf_files(&["fixtures/sgf/games".to_string()]).unwrap();
+    let all = learn(&files, settings()).unwrap();
+    let frequent = learn(&files, Settings { min_count: 3, .. settings() }).unwrap();
+    assert!(frequent.pattern_count() < all.pattern_count());
+}
+
+#[test]
+fn the_output_can_be_loaded_by_the_dictionary() {
+    let files = sgf_files(&["fixtures/sgf/games".to_string()]).unwrap();
+    let learner = learn(&files, settings()).unwrap();
+    let dictionary = Dictionary::parse(&learner.output()).unwrap();
+    assert_eq!(learner.pattern_count(), dictionary.len());
+    let gamma = dictionary.feature_gamma(Feature::Atari).unwrap();
+    assert!((learner.feature_gamma(Feature::Atari) - gamma).abs() < 0.001);
+}
+
+#[test]
+fn an_empty_learner_has_neutral_gammas() {
+    let mut learner = Learner::new(settings());
+    learner.fit();
+    assert_eq!(0, learner.pattern_count());
+    assert_eq!(1.0, learner.feature_gamma(Feature::Capture));
+}
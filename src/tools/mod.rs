@@ -0,0 +1,53 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+mod build_book;
+mod learn_patterns;
+mod play_match;
+mod referee;
+mod tune;
+
+/// The names and descriptions of the subcommands.
+pub const SUBCOMMANDS: &'static [(&'static str, &'static str)] = &[
+    ("build-book", "Grow an opening book from self-play games"),
+    ("learn-patterns", "Fit pattern and feature gammas to a collection of SGF files"),
+    ("match", "Play games between two configurations and compare their strength"),
+    ("referee", "Play games against another GTP engine"),
+    ("tune", "Tune configuration parameters with SPSA"),
+];
+
+/// Runs the subcommand given as the first argument and returns its
+/// exit code. Returns `None` if the first argument isn't a
+/// subcommand.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.len() < 2 {
+        return None;
+    }
+    let program = format!("{} {}", args[0], args[1]);
+    match args[1].as_ref() {
+        "build-book" => Some(build_book::run(&program, &args[2..])),
+        "learn-patterns" => Some(learn_patterns::run(&program, &args[2..])),
+        "match" => Some(play_match::run(&program, &args[2..])),
+        "referee" => Some(referee::run(&program, &args[2..])),
+        "tune" => Some(tune::run(&program, &args[2..])),
+        _ => None
+    }
+}
@@ -0,0 +1,383 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+pub use self::statistics::Decision;
+pub use self::statistics::Results;
+pub use self::statistics::Sprt;
+use board::Black;
+use board::Color;
+use board::Empty;
+use board::White;
+use book::Book;
+use config::Config;
+use config::describe_errors;
+use engine;
+use game::Game;
+use patterns::Matcher;
+use ruleset::KgsChinese;
+use score::FinalScore;
+use sgf::Writer;
+use timer::Timer;
+
+use getopts::Options;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::channel;
+use std::thread;
+
+mod statistics;
+mod test;
+
+/// The settings of a match.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub games: usize,
+    pub komi: f32,
+    pub max_moves: usize,
+    pub parallel: usize,
+    pub size: u8,
+    /// Stops the match early once decided (if set).
+    pub sprt: Option<Sprt>,
+    /// The main time of each player per game (in seconds).
+    pub time: i64,
+}
+
+/// One side of a match: a configuration and everything loaded from
+/// it.
+pub struct Player {
+    pub book: Arc<Book>,
+    pub config: Arc<Config>,
+    pub matcher: Arc<Matcher>,
+    pub name: String,
+}
+
+impl Player {
+
+    pub fn new(name: &str, config: Config) -> Result<Player, String> {
+        let matcher = try!(Matcher::from_config(&config));
+        let book = try!(Book::from_config(&config));
+        Ok(Player {
+            book: Arc::new(book),
+            config: Arc::new(config),
+            matcher: Arc::new(matcher),
+            name: name.to_string(),
+        })
+    }
+
+}
+
+/// A finished game of a match.
+pub struct Outcome {
+    /// The color the first player played.
+    pub color: Color,
+    pub index: usize,
+    pub result: String,
+    pub sgf: String,
+    pub winner: Color,
+}
+
+impl Outcome {
+
+    /// Adds the game to the results of the first player.
+    pub fn record(&self, results: &mut Results) {
+        if self.winner == Empty {
+            results.draws += 1;
+        } else if self.winner == self.color {
+            results.wins += 1;
+        } else {
+            results.losses += 1;
+        }
+    }
+
+}
+
+/// Plays one game between the two players. The first player takes
+/// black in the even games and white in the odd ones.
+pub fn play_game(index: usize, settings: &Settings, first: &Player, second: &Player) -> Outcome {
+    let (color, black, white) = if index % 2 == 0 { (Black, first, second) } else { (White, second, first) };
+    let mut black_engine = engine::factory(black.config.clone(), black.matcher.clone(), black.book.clone());
+    let mut white_engine = engine::factory(white.config.clone(), white.matcher.clone(), white.book.clone());
+    let mut black_timer = Timer::new(black.config.clone());
+    let mut white_timer = Timer::new(white.config.clone());
+    black_timer.setup(settings.time, 0, 0);
+    white_timer.setup(settings.time, 0, 0);
+    let mut game = Game::new(settings.size, settings.komi, KgsChinese);
+    let mut forfeited_by = Empty;
+    while !game.is_over() && game.move_count() < settings.max_moves {
+        let next_player = game.board().next_player();
+        let (engine, timer) = if next_player == Black {
+            (&mut black_engine, &mut black_timer)
+        } else {
+            (&mut white_engine, &mut white_timer)
+        };
+        timer.start(&game);
+        let (m, _) = engine.genmove(next_player, &game, timer);
+        timer.stop();
+        match game.play(m) {
+            Ok(g) => game = g,
+            // An illegal move loses the game.
+            Err(_) => {
+                forfeited_by = next_player;
+                break;
+            }
+        }
+    }
+    let result = if forfeited_by != Empty {
+        format!("{}+Forfeit", if forfeited_by == White { "B" } else { "W" })
+    } else if game.last_move().is_resign() {
+        format!("{}+Resign", if game.winner() == Black { "B" } else { "W" })
+    } else {
+        // The dead stones are taken from the search of the player
+        // who moved last.
+        let engine = if game.board().next_player() == Black { &white_engine } else { &black_engine };
+        FinalScore::new(&game, engine.ownership()).score()
+    };
+    let winner = winner_of(&result);
+    let mut writer = Writer::new(&game);
+    writer.set_player(Black, &black.name);
+    writer.set_player(White, &white.name);
+    writer.set_result(&result);
+    Outcome {
+        color: color,
+        index: index,
+        result: result,
+        sgf: writer.sgf(),
+        winner: winner,
+    }
+}
+
+/// The winner of a game given its result (e.g. `B+Resign` or
+/// `W+3.5`). Returns `Empty` for a draw.
+pub fn winner_of(result: &str) -> Color {
+    match result.chars().next() {
+        Some('B') => Black,
+        Some('W') => White,
+        _ => Empty
+    }
+}
+
+/// Plays the games of the match on `settings.parallel` threads and
+/// calls `report` for each finished game with the results so far.
+/// Stops starting new games once the SPRT accepts one of the
+/// hypotheses.
+pub fn play_match<F>(settings: &Settings, first: Arc<Player>, second: Arc<Player>, mut report: F) -> Results where F: FnMut(&Outcome, &Results) {
+    let next_game = Arc::new(AtomicUsize::new(0));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (send_outcome, receive_outcome) = channel();
+    let workers: Vec<thread::JoinHandle<()>> = (0..settings.parallel)
+        .map(|_| {
+            let next_game = next_game.clone();
+            let stop = stop.clone();
+            let send_outcome = send_outcome.clone();
+            let first = first.clone();
+            let second = second.clone();
+            let settings = *settings;
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    let index = next_game.fetch_add(1, Ordering::SeqCst);
+                    if index >= settings.games {
+                        break;
+                    }
+                    let outcome = play_game(index, &settings, &first, &second);
+                    if send_outcome.send(outcome).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(send_outcome);
+    let mut results = Results::new();
+    for outcome in receive_outcome.iter() {
+        outcome.record(&mut results);
+        report(&outcome, &results);
+        if settings.sprt.map(|sprt| sprt.decision(&results) != Decision::Continue).unwrap_or(false) {
+            stop.store(true, Ordering::SeqCst);
+        }
+    }
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    results
+}
+
+/// A summary of the results of the match from the point of view of
+/// the first player.
+pub fn summary(settings: &Settings, first: &str, second: &str, results: &Results) -> String {
+    let (elo_low, elo_high) = results.elo_interval();
+    let mut s = String::new();
+    s.push_str(&format!("{} vs. {} ({}x{}, komi {})\n", first, second, settings.size, settings.size, settings.komi));
+    s.push_str(&format!("Games: {} ({} won, {} lost, {} drawn)\n", results.games(), results.wins, results.losses, results.draws));
+    s.push_str(&format!("Win rate: {:.1}% +/- {:.1}%\n", results.score() * 100.0, results.margin() * 100.0));
+    s.push_str(&format!("Elo difference: {:+.1} (95% confidence interval {:+.1} to {:+.1})\n", results.elo(), elo_low, elo_high));
+    if let Some(sprt) = settings.sprt {
+        let (lower, upper) = sprt.bounds();
+        let decision = match sprt.decision(results) {
+            Decision::AcceptH0 => "H0 accepted",
+            Decision::AcceptH1 => "H1 accepted",
+            Decision::Continue => "inconclusive",
+        };
+        s.push_str(&format!(
+            "SPRT (elo0 {}, elo1 {}, alpha {}, beta {}): LLR {:.2} ({:.2}, {:.2}), {}\n",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.alpha,
+            sprt.beta,
+            sprt.llr(results),
+            lower,
+            upper,
+            decision));
+    }
+    s
+}
+
+pub fn run(program: &str, args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "Print this help menu");
+    opts.optopt("", "alpha", "SPRT false positive rate (defaults to 0.05)", "NUM");
+    opts.optopt("", "beta", "SPRT false negative rate (defaults to 0.05)", "NUM");
+    opts.optopt("", "elo0", "SPRT Elo difference of H0 (defaults to 0)", "NUM");
+    opts.optopt("", "elo1", "SPRT Elo difference of H1 (defaults to 20)", "NUM");
+    opts.optopt("g", "games", "Number of games to play (defaults to 100)", "NUM");
+    opts.optopt("k", "komi", "Komi (defaults to 7)", "NUM");
+    opts.optopt("m", "max-moves", "Score the game after this many moves (defaults to three times the number of points)", "NUM");
+    opts.optopt("o", "output", "Directory for the SGF files and the summary (defaults to match)", "DIR");
+    opts.optopt("p", "parallel", "Number of games to play at the same time (defaults to 1)", "NUM");
+    opts.optopt("t", "time", "Main time per player and game in seconds (defaults to 60)", "NUM");
+    opts.optopt("T", "threads", "Number of search threads of each engine (defaults to the configuration)", "NUM");
+    opts.optopt("z", "size", "Board size (defaults to 9)", "NUM");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            println!("{}", f.to_string());
+            return 1;
+        }
+    };
+    if matches.opt_present("h") || matches.free.len() != 2 {
+        let brief = format!("Usage: {} [options] FIRST.toml SECOND.toml", program);
+        println!("{}", opts.usage(brief.as_ref()));
+        return if matches.opt_present("h") { 0 } else { 1 };
+    }
+    let result = parse_settings(&matches)
+        .and_then(|settings| {
+            let threads = try!(parse_opt(&matches, "T", 0));
+            let first = try!(load_player(&matches.free[0], threads));
+            let second = try!(load_player(&matches.free[1], threads));
+            let output = PathBuf::from(matches.opt_str("o").unwrap_or("match".to_string()));
+            try!(fs::create_dir_all(&output).map_err(|e| format!("{}: {}", output.display(), e)));
+            let (first_name, second_name) = (first.name.clone(), second.name.clone());
+            let mut error = None;
+            let results = play_match(&settings, Arc::new(first), Arc::new(second), |outcome, results| {
+                let color = if outcome.color == Black { "B" } else { "W" };
+                println!(
+                    "Game {} ({} as {}): {}, {} won, {} lost, {} drawn",
+                    outcome.index + 1, first_name, color, outcome.result, results.wins, results.losses, results.draws);
+                let path = output.join(format!("game-{:04}.sgf", outcome.index + 1));
+                if let Err(e) = write(&path, &outcome.sgf) {
+                    error = Some(e);
+                }
+            });
+            if let Some(e) = error {
+                return Err(e);
+            }
+            let summary = summary(&settings, &first_name, &second_name, &results);
+            try!(write(&output.join("summary.txt"), &summary));
+            Ok(summary)
+        });
+    match result {
+        Ok(summary) => {
+            print!("{}", summary);
+            0
+        },
+        Err(error) => {
+            println!("{}", error);
+            1
+        }
+    }
+}
+
+fn load_player(filename: &str, threads: usize) -> Result<Player, String> {
+    if !Path::new(filename).is_file() {
+        return Err(format!("{}: no such file", filename));
+    }
+    let mut config = try!(Config::from_file(filename.to_string(), false, false, KgsChinese)
+                          .map_err(|errors| describe_errors(filename, &errors)));
+    if threads > 0 {
+        config.threads = threads;
+    }
+    Player::new(filename, config)
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    File::create(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_settings(matches: &::getopts::Matches) -> Result<Settings, String> {
+    let size = try!(parse_opt(matches, "z", 9));
+    if size < 2 || size > 19 {
+        return Err("the board size needs to be between 2 and 19".to_string());
+    }
+    let parallel = try!(parse_opt(matches, "p", 1));
+    if parallel == 0 {
+        return Err("at least one game needs to be played at a time".to_string());
+    }
+    let sprt = Sprt {
+        alpha: try!(parse_float(matches, "alpha", 0.05)),
+        beta: try!(parse_float(matches, "beta", 0.05)),
+        elo0: try!(parse_float(matches, "elo0", 0.0)),
+        elo1: try!(parse_float(matches, "elo1", 20.0)),
+    };
+    if sprt.alpha <= 0.0 || sprt.alpha >= 1.0 || sprt.beta <= 0.0 || sprt.beta >= 1.0 {
+        return Err("alpha and beta need to be between 0 and 1".to_string());
+    }
+    Ok(Settings {
+        games: try!(parse_opt(matches, "g", 100)),
+        komi: try!(parse_float(matches, "k", 7.0)) as f32,
+        max_moves: try!(parse_opt(matches, "m", 3 * size * size)),
+        parallel: parallel,
+        size: size as u8,
+        sprt: Some(sprt),
+        time: try!(parse_opt(matches, "t", 60)) as i64,
+    })
+}
+
+fn parse_opt(matches: &::getopts::Matches, name: &str, default: usize) -> Result<usize, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
+
+fn parse_float(matches: &::getopts::Matches, name: &str, default: f64) -> Result<f64, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for {}", value, name)),
+        None => Ok(default)
+    }
+}
@@ -0,0 +1,157 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+// The quantile of the normal distribution for 95% confidence
+// intervals.
+const Z_95: f64 = 1.959964;
+
+/// The games won and lost by the first player of a match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Results {
+    pub draws: usize,
+    pub losses: usize,
+    pub wins: usize,
+}
+
+impl Results {
+
+    pub fn new() -> Results {
+        Results { draws: 0, losses: 0, wins: 0 }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The average score of the first player (a draw counts as half a
+    /// win).
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    // The variance of the result of a single game.
+    fn variance(&self) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return 0.0;
+        }
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2)
+         + self.losses as f64 * s.powi(2)
+         + self.draws as f64 * (0.5 - s).powi(2)) / n
+    }
+
+    /// The half-width of the 95% confidence interval of the score.
+    pub fn margin(&self) -> f64 {
+        let n = self.games() as f64;
+        if n == 0.0 {
+            return 0.5;
+        }
+        Z_95 * (self.variance() / n).sqrt()
+    }
+
+    /// The Elo difference between the first and the second player.
+    pub fn elo(&self) -> f64 {
+        elo(self.score())
+    }
+
+    /// The 95% confidence interval of the Elo difference.
+    pub fn elo_interval(&self) -> (f64, f64) {
+        let s = self.score();
+        let margin = self.margin();
+        (elo(s - margin), elo(s + margin))
+    }
+
+}
+
+/// Converts a score to an Elo difference.
+pub fn elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        ::std::f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        ::std::f64::INFINITY
+    } else {
+        400.0 * (score / (1.0 - score)).log10()
+    }
+}
+
+/// The expected score for an Elo difference.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decision {
+    AcceptH0,
+    AcceptH1,
+    Continue,
+}
+
+/// A sequential probability ratio test of the hypothesis that the
+/// first player is `elo0` Elo stronger than the second one (H0)
+/// against the hypothesis that it's `elo1` Elo stronger (H1).
+///
+/// The log-likelihood ratio is approximated with a normal
+/// distribution of the game results (like Fishtest does), which works
+/// with draws as well.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub alpha: f64,
+    pub beta: f64,
+    pub elo0: f64,
+    pub elo1: f64,
+}
+
+impl Sprt {
+
+    /// The results are regularised with one pseudo-win and one
+    /// pseudo-loss. Otherwise a clean sweep has a variance of 0 and
+    /// the test would never stop.
+    pub fn llr(&self, results: &Results) -> f64 {
+        let results = Results { draws: results.draws, losses: results.losses + 1, wins: results.wins + 1 };
+        let variance = results.variance();
+        let s0 = expected_score(self.elo0);
+        let s1 = expected_score(self.elo1);
+        let n = results.games() as f64;
+        n * (s1 - s0) * (2.0 * results.score() - s0 - s1) / (2.0 * variance)
+    }
+
+    /// The LLR bounds at which H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn decision(&self, results: &Results) -> Decision {
+        let llr = self.llr(results);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Decision::AcceptH0
+        } else if llr >= upper {
+            Decision::AcceptH1
+        } else {
+            Decision::Continue
+        }
+    }
+
+}
@@ -0,0 +1,167 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+
+#![cfg(test)]
+
+use board::Black;
+use board::White;
+use config::Config;
+use super::Decision;
+use super::Player;
+use super::Results;
+use super::Settings;
+use super::Sprt;
+use super::play_game;
+use super::play_match;
+use super::summary;
+
+use std::sync::Arc;
+
+fn sprt() -> Sprt {
+    Sprt { alpha: 0.05, beta: 0.05, elo0: 0.0, elo1: 20.0 }
+}
+
+fn results(wins: usize, losses: usize, draws: usize) -> Results {
+    Results { draws: draws, losses: losses, wins: wins }
+}
+
+fn settings(games: usize, parallel: usize) -> Settings {
+    Settings { games: games, komi: 0.5, max_moves: 20, parallel: parallel, size: 5, sprt: Some(sprt()), time: 0 }
+}
+
+fn player(name: &str) -> Player {
+    let mut config = Config::test_config();
+    config.threads = 1;
+    Player::new(name, config).unwrap()
+}
+
+#[test]
+fn even_results_are_zero_elo() {
+    let results = results(50, 50, 0);
+    assert_eq!(0.5, results.score());
+    assert!(results.elo().abs() < 1e-9);
+}
+
+#[test]
+fn draws_count_as_half_a_win() {
+    assert_eq!(0.5, results(1, 1, 2).score());
+    assert_eq!(0.75, results(1, 0, 1).score());
+}
+
+#[test]
+fn computes_the_elo_difference() {
+    // A score of 76% is about 200 Elo.
+    let elo = results(76, 24, 0).elo();
+    assert!(elo > 199.0 && elo < 201.0, "elo: {}", elo);
+}
+
+#[test]
+fn the_confidence_interval_contains_the_elo_difference() {
+    let results = results(60, 40, 0);
+    let (low, high) = results.elo_interval();
+    assert!(low < results.elo() && results.elo() < high);
+    assert!(low > 0.0 && low < 5.0, "low: {}", low);
+    assert!(high > 140.0 && high < 150.0, "high: {}", high);
+    // About two standard errors of a 100 game match.
+    assert!((results.margin() - 0.096).abs() < 0.001, "margin: {}", results.margin());
+}
+
+#[test]
+fn sprt_continues_with_few_games() {
+    assert_eq!(Decision::Continue, sprt().decision(&results(6, 4, 0)));
+}
+
+#[test]
+fn sprt_accepts_h1_for_a_much_stronger_player() {
+    assert_eq!(Decision::AcceptH1, sprt().decision(&results(80, 20, 0)));
+}
+
+#[test]
+fn sprt_accepts_h0_for_a_weaker_player() {
+    assert_eq!(Decision::AcceptH0, sprt().decision(&results(25, 75, 0)));
+}
+
+#[test]
+fn sprt_stops_after_a_clean_sweep() {
+    assert_eq!(Decision::AcceptH1, sprt().decision(&results(20, 0, 0)));
+    assert_eq!(Decision::AcceptH0, sprt().decision(&results(0, 20, 0)));
+}
+
+#[test]
+fn sprt_continues_without_games() {
+    assert_eq!(Decision::Continue, sprt().decision(&results(0, 0, 0)));
+}
+
+#[test]
+fn sprt_bounds_depend_on_alpha_and_beta() {
+    let (lower, upper) = sprt().bounds();
+    assert!((lower + 2.944).abs() < 0.001);
+    assert!((upper - 2.944).abs() < 0.001);
+}
+
+#[test]
+fn alternates_the_colors() {
+    let first = player("first");
+    let second = player("second");
+    let settings = settings(2, 1);
+    let even = play_game(0, &settings, &first, &second);
+    let odd = play_game(1, &settings, &first, &second);
+    assert_eq!(Black, even.color);
+    assert_eq!(White, odd.color);
+    assert!(even.sgf.contains("PB[first]PW[second]"));
+    assert!(odd.sgf.contains("PB[second]PW[first]"));
+}
+
+#[test]
+fn writes_the_result_into_the_sgf() {
+    let outcome = play_game(0, &settings(1, 1), &player("first"), &player("second"));
+    assert!(outcome.sgf.contains(&format!("RE[{}]", outcome.result)));
+}
+
+#[test]
+fn plays_all_games_in_parallel() {
+    let mut indices = vec!();
+    let results = play_match(&settings(4, 2), Arc::new(player("a")), Arc::new(player("b")), |outcome, _| {
+        indices.push(outcome.index);
+    });
+    indices.sort();
+    assert_eq!(vec!(0, 1, 2, 3), indices);
+    assert_eq!(4, results.games());
+}
+
+#[test]
+fn stops_early_once_the_sprt_is_decided() {
+    let mut settings = settings(100, 1);
+    // Any result accepts one of the hypotheses right away.
+    settings.sprt = Some(Sprt { alpha: 0.9, beta: 0.9, elo0: -1000.0, elo1: 1000.0 });
+    let results = play_match(&settings, Arc::new(player("a")), Arc::new(player("b")), |_, _| {});
+    assert!(results.games() < 100);
+}
+
+#[test]
+fn summarizes_the_results() {
+    let summary = summary(&settings(100, 1), "a.toml", "b.toml", &results(60, 38, 2));
+    assert!(summary.contains("a.toml vs. b.toml (5x5, komi 0.5)"));
+    assert!(summary.contains("Games: 100 (60 won, 38 lost, 2 drawn)"));
+    assert!(summary.contains("Win rate: 61.0%"));
+    assert!(summary.contains("Elo difference: +77.7"));
+}
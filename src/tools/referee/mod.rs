@@ -0,0 +1,485 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use board::Black;
+use board::Color;
+use board::Move;
+use board::Pass;
+use board::Play;
+use board::Resign;
+use board::White;
+use book::Book;
+use config::Config;
+use config::describe_errors;
+use engine;
+use game::Game;
+use gtp::GTPInterpreter;
+use ownership::OwnershipStatistics;
+use patterns::Matcher;
+use ruleset::KgsChinese;
+use score::FinalScore;
+use sgf::Writer;
+use super::play_match::Outcome;
+use super::play_match::Results;
+use super::play_match::winner_of;
+
+use getopts::Options;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::ChildStdin;
+use std::process::ChildStdout;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use time::PreciseTime;
+
+mod test;
+
+/// The settings of the games played by the referee. The times are in
+/// seconds and follow the GTP `time_settings` command: Canadian byo
+/// yomi of `byo_time` for `byo_stones` stones after the main time.
+/// Without main and byo yomi time the games are untimed.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub byo_stones: i32,
+    pub byo_time: i64,
+    pub games: usize,
+    pub komi: f32,
+    pub main_time: i64,
+    pub max_moves: usize,
+    pub size: u8,
+}
+
+/// Something that answers GTP commands.
+pub trait GtpEngine {
+
+    /// Sends the command and returns the response (without the `=`)
+    /// or the error message (without the `?`).
+    fn send(&mut self, command: &str) -> Result<String, String>;
+
+}
+
+impl<'a> GtpEngine for GTPInterpreter<'a> {
+
+    fn send(&mut self, command: &str) -> Result<String, String> {
+        self.read(command)
+    }
+
+}
+
+/// A GTP engine running as a child process that we talk to over its
+/// standard input and output.
+pub struct Process {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl Process {
+
+    /// Starts the command line (split on whitespace).
+    pub fn spawn(command_line: &str) -> Result<Process, String> {
+        let words: Vec<&str> = command_line.split_whitespace().collect();
+        if words.is_empty() {
+            return Err("empty command line".to_string());
+        }
+        let mut child = try!(Command::new(words[0])
+            .args(&words[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("{}: {}", words[0], e)));
+        let input = child.stdin.take().unwrap();
+        let output = BufReader::new(child.stdout.take().unwrap());
+        Ok(Process { child: child, input: input, output: output })
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.output.read_line(&mut line) {
+            Ok(0) => Err("the engine exited".to_string()),
+            Ok(_) => Ok(line.trim_right().to_string()),
+            Err(e) => Err(e.to_string())
+        }
+    }
+
+}
+
+impl GtpEngine for Process {
+
+    fn send(&mut self, command: &str) -> Result<String, String> {
+        try!(write!(self.input, "{}\n", command)
+             .and_then(|_| self.input.flush())
+             .map_err(|e| e.to_string()));
+        let mut first = try!(self.read_line());
+        while first.is_empty() {
+            first = try!(self.read_line());
+        }
+        let mut lines = vec!(first[1..].trim().to_string());
+        loop {
+            let line = try!(self.read_line());
+            if line.is_empty() {
+                break;
+            }
+            lines.push(line);
+        }
+        let response = lines.join("\n");
+        match first.chars().next() {
+            Some('=') => Ok(response),
+            Some('?') => Err(response),
+            _ => Err(format!("invalid response: {}", first))
+        }
+    }
+
+}
+
+impl Drop for Process {
+
+    fn drop(&mut self) {
+        // The engine may already be gone, so the errors are ignored.
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+}
+
+/// The time left of one player (in milliseconds).
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    byo_stones: i32,
+    byo_stones_left: i32,
+    byo_time: i64,
+    byo_time_left: i64,
+    main_time_left: i64,
+}
+
+impl Clock {
+
+    pub fn new(settings: &Settings) -> Clock {
+        Clock {
+            byo_stones: settings.byo_stones,
+            byo_stones_left: settings.byo_stones,
+            byo_time: settings.byo_time * 1000,
+            byo_time_left: settings.byo_time * 1000,
+            main_time_left: settings.main_time * 1000,
+        }
+    }
+
+    pub fn is_timed(&self) -> bool {
+        self.main_time_left > 0 || self.byo_time > 0
+    }
+
+    /// Takes the time of a move off the clock. Returns `false` if the
+    /// player ran out of time.
+    pub fn spend(&mut self, ms: i64) -> bool {
+        if !self.is_timed() {
+            return true;
+        }
+        if ms <= self.main_time_left {
+            self.main_time_left -= ms;
+            return true;
+        }
+        let overtime = ms - self.main_time_left;
+        self.main_time_left = 0;
+        if overtime > self.byo_time_left || self.byo_stones == 0 {
+            return false;
+        }
+        self.byo_time_left -= overtime;
+        self.byo_stones_left -= 1;
+        if self.byo_stones_left == 0 {
+            self.byo_time_left = self.byo_time;
+            self.byo_stones_left = self.byo_stones;
+        }
+        true
+    }
+
+    /// The arguments of the GTP `time_left` command.
+    pub fn time_left(&self) -> (i64, i32) {
+        if self.main_time_left > 0 {
+            (self.main_time_left / 1000, 0)
+        } else {
+            (self.byo_time_left / 1000, self.byo_stones_left)
+        }
+    }
+
+}
+
+fn color_name(color: Color) -> &'static str {
+    if color == White { "W" } else { "B" }
+}
+
+/// Referees one game between the two engines. The moves are checked
+/// with our `Game` (including superko) and a player that plays an
+/// illegal move, fails to answer or runs out of time loses. A game
+/// that ends with two passes (or reaches the maximum number of
+/// moves) is scored by the engine playing `scorer` (our engine, which
+/// uses the ownership statistics of its last search), by the other
+/// engine if it can't, and with `FinalScore` as a last resort.
+///
+/// Returns the finished game and its result.
+pub fn play_game(settings: &Settings, config: Arc<Config>, black: &mut GtpEngine, white: &mut GtpEngine, scorer: Color) -> Result<(Game, String), String> {
+    let setup = [
+        format!("boardsize {}", settings.size),
+        "clear_board".to_string(),
+        format!("komi {}", settings.komi),
+        format!("time_settings {} {} {}", settings.main_time, settings.byo_time, settings.byo_stones),
+    ];
+    for command in setup.iter() {
+        try!(black.send(command).map_err(|e| format!("black: {}: {}", command, e)));
+        try!(white.send(command).map_err(|e| format!("white: {}: {}", command, e)));
+    }
+    let mut game = Game::new(settings.size, settings.komi, KgsChinese);
+    let mut black_clock = Clock::new(settings);
+    let mut white_clock = Clock::new(settings);
+    while !game.is_over() && game.move_count() < settings.max_moves {
+        let next = if game.board().next_player() == Black {
+            play_move(&game, settings, black, white, &mut black_clock)
+        } else {
+            play_move(&game, settings, white, black, &mut white_clock)
+        };
+        match next {
+            Ok(g) => game = g,
+            Err(result) => return Ok((game, result))
+        }
+    }
+    let result = if game.last_move().is_resign() {
+        format!("{}+Resign", color_name(game.last_move().color().opposite()))
+    } else {
+        let score = if scorer == Black {
+            final_score(black).or_else(|| final_score(white))
+        } else {
+            final_score(white).or_else(|| final_score(black))
+        };
+        match score {
+            Some(score) => score,
+            None => {
+                // Without any ownership statistics every chain counts
+                // as alive.
+                let ownership = OwnershipStatistics::new(config, game.size(), game.komi());
+                FinalScore::new(&game, &ownership).score()
+            }
+        }
+    };
+    Ok((game, result))
+}
+
+// Asks the engine for the score of the finished game. Returns `None`
+// if the engine doesn't answer with a result like `B+3.5` or `0`.
+fn final_score(engine: &mut GtpEngine) -> Option<String> {
+    let score = match engine.send("final_score") {
+        Ok(score) => score.trim().to_string(),
+        Err(_) => return None
+    };
+    let valid = score == "0"
+        || ((score.starts_with("B+") || score.starts_with("W+")) && score[2..].parse::<f32>().is_ok());
+    if valid { Some(score) } else { None }
+}
+
+// Asks the player to move and tells the opponent about it. Returns
+// the result of the game if the player (or the opponent) lost by
+// breaking the rules.
+fn play_move(game: &Game, settings: &Settings, player: &mut GtpEngine, opponent: &mut GtpEngine, clock: &mut Clock) -> Result<Game, String> {
+    let color = game.board().next_player();
+    if clock.is_timed() {
+        let (time, stones) = clock.time_left();
+        // Not every engine keeps track of the time.
+        let _ = player.send(&format!("time_left {} {} {}", color_name(color), time, stones));
+    }
+    let started_at = PreciseTime::now();
+    let response = player.send(&format!("genmove {}", color_name(color)));
+    if !clock.spend(started_at.to(PreciseTime::now()).num_milliseconds()) {
+        return Err(format!("{}+Time", color_name(color.opposite())));
+    }
+    let forfeit = format!("{}+Forfeit", color_name(color.opposite()));
+    let m = match response.map(|vertex| parse_move(color, &vertex, settings.size)) {
+        Ok(Some(m)) => m,
+        _ => return Err(forfeit)
+    };
+    let next = try!(game.play(m).map_err(|_| forfeit));
+    if !m.is_resign() && opponent.send(&format!("play {} {}", color_name(color), m.to_gtp())).is_err() {
+        return Err(format!("{}+Forfeit", color_name(color)));
+    }
+    Ok(next)
+}
+
+fn parse_move(color: Color, vertex: &str, size: u8) -> Option<Move> {
+    match vertex.to_lowercase().as_ref() {
+        "pass" => Some(Pass(color)),
+        "resign" => Some(Resign(color)),
+        _ => GTPInterpreter::parse_vertex(vertex)
+            .and_then(|coord| if coord.is_inside(size) { Some(Play(color, coord.col, coord.row)) } else { None })
+    }
+}
+
+/// Plays `settings.games` games between our engine and the other one,
+/// alternating the colours (we take black in the even games). Calls
+/// `report` for each finished game with our results so far.
+pub fn play_games<F>(settings: &Settings, config: Arc<Config>, ours: &mut GtpEngine, theirs: &mut GtpEngine, mut report: F) -> Result<Results, String> where F: FnMut(&Outcome, &Results) {
+    let our_name = ours.send("name").unwrap_or("Iomrascálaí".to_string());
+    let their_name = theirs.send("name").unwrap_or("unknown".to_string());
+    let mut results = Results::new();
+    for index in 0..settings.games {
+        let (color, (game, result)) = if index % 2 == 0 {
+            (Black, try!(play_game(settings, config.clone(), ours, theirs, Black)))
+        } else {
+            (White, try!(play_game(settings, config.clone(), theirs, ours, White)))
+        };
+        let (black_name, white_name) = if color == Black { (&our_name, &their_name) } else { (&their_name, &our_name) };
+        let mut writer = Writer::new(&game);
+        writer.set_player(Black, black_name);
+        writer.set_player(White, white_name);
+        writer.set_result(&result);
+        let outcome = Outcome {
+            color: color,
+            index: index,
+            sgf: writer.sgf(),
+            winner: winner_of(&result),
+            result: result,
+        };
+        outcome.record(&mut results);
+        report(&outcome, &results);
+    }
+    Ok(results)
+}
+
+pub fn run(program: &str, args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "Print this help menu");
+    opts.optopt("b", "byo-time", "Byo yomi time per period in seconds (defaults to 0)", "NUM");
+    opts.optopt("c", "config", "Config file of our engine", "FILE");
+    opts.optopt("g", "games", "Number of games to play (defaults to 1)", "NUM");
+    opts.optopt("k", "komi", "Komi (defaults to 7)", "NUM");
+    opts.optopt("m", "max-moves", "Score the game after this many moves (defaults to three times the number of points)", "NUM");
+    opts.optopt("o", "output", "Directory for the SGF files and the summary (defaults to referee)", "DIR");
+    opts.optopt("s", "byo-stones", "Stones per byo yomi period (defaults to 0)", "NUM");
+    opts.optopt("t", "time", "Main time per player and game in seconds (defaults to 300)", "NUM");
+    opts.optopt("z", "size", "Board size (defaults to 9)", "NUM");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            println!("{}", f.to_string());
+            return 1;
+        }
+    };
+    if matches.opt_present("h") || matches.free.is_empty() {
+        let brief = format!("Usage: {} [options] \"COMMAND\"\n\nPlays against the GTP engine started with COMMAND (e.g. \"gnugo --mode gtp\").", program);
+        println!("{}", opts.usage(brief.as_ref()));
+        return if matches.opt_present("h") { 0 } else { 1 };
+    }
+    let config = match matches.opt_str("c") {
+        Some(filename) => Config::from_file(filename.clone(), false, false, KgsChinese)
+            .map_err(|errors| describe_errors(&filename, &errors)),
+        None => Ok(Config::default(false, false, KgsChinese))
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(error) => {
+            println!("{}", error);
+            return 1;
+        }
+    };
+    let config = Arc::new(config);
+    let output = PathBuf::from(matches.opt_str("o").unwrap_or("referee".to_string()));
+    let result = parse_settings(&matches)
+        .and_then(|settings| {
+            let matcher = Arc::new(try!(Matcher::from_config(&config)));
+            let book = Arc::new(try!(Book::from_config(&config)));
+            let mut ours = GTPInterpreter::new(config.clone(), engine::factory(config.clone(), matcher, book));
+            let mut theirs = try!(Process::spawn(&matches.free.join(" ")));
+            try!(fs::create_dir_all(&output).map_err(|e| format!("{}: {}", output.display(), e)));
+            let mut error = None;
+            let results = try!(play_games(&settings, config.clone(), &mut ours, &mut theirs, |outcome, results| {
+                println!(
+                    "Game {} (we're {}): {}, {} won, {} lost, {} drawn",
+                    outcome.index + 1, color_name(outcome.color), outcome.result, results.wins, results.losses, results.draws);
+                let path = output.join(format!("game-{:04}.sgf", outcome.index + 1));
+                if let Err(e) = write(&path, &outcome.sgf) {
+                    error = Some(e);
+                }
+            }));
+            if let Some(e) = error {
+                return Err(e);
+            }
+            let summary = summary(&results);
+            try!(write(&output.join("summary.txt"), &summary));
+            Ok(summary)
+        });
+    match result {
+        Ok(summary) => {
+            print!("{}", summary);
+            0
+        },
+        Err(error) => {
+            println!("{}", error);
+            1
+        }
+    }
+}
+
+/// Our results against the other engine.
+pub fn summary(results: &Results) -> String {
+    let (elo_low, elo_high) = results.elo_interval();
+    let mut s = String::new();
+    s.push_str(&format!("Games: {} ({} won, {} lost, {} drawn)\n", results.games(), results.wins, results.losses, results.draws));
+    s.push_str(&format!("Win rate: {:.1}% +/- {:.1}%\n", results.score() * 100.0, results.margin() * 100.0));
+    s.push_str(&format!("Elo difference: {:+.1} (95% confidence interval {:+.1} to {:+.1})\n", results.elo(), elo_low, elo_high));
+    s
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    File::create(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse_settings(matches: &::getopts::Matches) -> Result<Settings, String> {
+    let size = try!(parse_opt(matches, "z", 9));
+    if size < 2 || size > 19 {
+        return Err("the board size needs to be between 2 and 19".to_string());
+    }
+    let komi = match matches.opt_str("k") {
+        Some(value) => try!(value.parse().map_err(|_| format!("invalid komi {:?}", value))),
+        None => 7.0
+    };
+    Ok(Settings {
+        byo_stones: try!(parse_opt(matches, "s", 0)) as i32,
+        byo_time: try!(parse_opt(matches, "b", 0)) as i64,
+        games: try!(parse_opt(matches, "g", 1)),
+        komi: komi,
+        main_time: try!(parse_opt(matches, "t", 300)) as i64,
+        max_moves: try!(parse_opt(matches, "m", 3 * size * size)),
+        size: size as u8,
+    })
+}
+
+fn parse_opt(matches: &::getopts::Matches, name: &str, default: usize) -> Result<usize, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
@@ -0,0 +1,129 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+
+#![cfg(test)]
+
+use board::Black;
+use board::White;
+use book::Book;
+use config::Config;
+use engine;
+use gtp::GTPInterpreter;
+use patterns::Matcher;
+use super::Clock;
+use super::Process;
+use super::Settings;
+use super::play_game;
+use super::play_games;
+
+use std::sync::Arc;
+
+fn settings() -> Settings {
+    Settings { byo_stones: 0, byo_time: 0, games: 2, komi: 7.0, main_time: 0, max_moves: 20, size: 5 }
+}
+
+fn config() -> Arc<Config> {
+    let mut config = Config::test_config();
+    config.threads = 1;
+    Arc::new(config)
+}
+
+fn stub(name: &str) -> Process {
+    Process::spawn(&format!("sh fixtures/gtp/{}.sh", name)).unwrap()
+}
+
+#[test]
+fn scores_the_game_after_two_passes() {
+    let (game, result) = play_game(&settings(), config(), &mut stub("pass"), &mut stub("pass"), Black).unwrap();
+    assert_eq!(2, game.moves().len());
+    assert_eq!("W+7", result);
+}
+
+#[test]
+fn asks_the_scorer_for_the_final_score() {
+    let (_, result) = play_game(&settings(), config(), &mut stub("pass"), &mut stub("score"), White).unwrap();
+    assert_eq!("B+12", result);
+}
+
+#[test]
+fn asks_the_other_engine_if_the_scorer_cannot_score() {
+    let (_, result) = play_game(&settings(), config(), &mut stub("score"), &mut stub("pass"), White).unwrap();
+    assert_eq!("B+12", result);
+}
+
+#[test]
+fn an_illegal_move_loses_the_game() {
+    let (game, result) = play_game(&settings(), config(), &mut stub("illegal"), &mut stub("pass"), Black).unwrap();
+    assert_eq!(2, game.moves().len());
+    assert_eq!("W+Forfeit", result);
+}
+
+#[test]
+fn running_out_of_time_loses_the_game() {
+    let mut settings = settings();
+    settings.main_time = 1;
+    let (_, result) = play_game(&settings, config(), &mut stub("pass"), &mut stub("slow"), Black).unwrap();
+    assert_eq!("B+Time", result);
+}
+
+#[test]
+fn reports_engines_that_cannot_be_started() {
+    assert!(Process::spawn("this-engine-does-not-exist").is_err());
+    assert!(Process::spawn("").is_err());
+}
+
+#[test]
+fn plays_against_our_engine_with_alternating_colors() {
+    let config = config();
+    let engine = engine::factory(config.clone(), Arc::new(Matcher::new()), Arc::new(Book::new()));
+    let mut ours = GTPInterpreter::new(config.clone(), engine);
+    let mut colors = vec!();
+    let results = play_games(&settings(), config, &mut ours, &mut stub("pass"), |outcome, _| {
+        assert!(outcome.sgf.contains(&format!("RE[{}]", outcome.result)));
+        colors.push(outcome.color);
+    }).unwrap();
+    assert_eq!(vec!(Black, White), colors);
+    assert_eq!(2, results.games());
+}
+
+#[test]
+fn the_clock_uses_the_main_time_first() {
+    let mut settings = settings();
+    settings.main_time = 10;
+    settings.byo_time = 5;
+    settings.byo_stones = 2;
+    let mut clock = Clock::new(&settings);
+    assert!(clock.spend(9000));
+    assert_eq!((1, 0), clock.time_left());
+    assert!(clock.spend(3000));
+    assert_eq!((3, 1), clock.time_left());
+    assert!(clock.spend(3000));
+    assert_eq!((5, 2), clock.time_left());
+    assert!(!clock.spend(6000));
+}
+
+#[test]
+fn untimed_games_never_run_out_of_time() {
+    let mut clock = Clock::new(&settings());
+    assert!(!clock.is_timed());
+    assert!(clock.spend(1000000));
+}
@@ -0,0 +1,406 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+use config::Config;
+use config::describe_errors;
+use ruleset::KgsChinese;
+use super::play_match::Player;
+use super::play_match::Results;
+use super::play_match::Settings as MatchSettings;
+use super::play_match::play_match;
+
+use getopts::Options;
+use rand::Rng;
+use rand::SeedableRng;
+use rand::XorShiftRng;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use toml;
+
+mod test;
+
+// The exponents of the SPSA gain sequences recommended by Spall.
+const ALPHA: f32 = 0.602;
+const GAMMA: f32 = 0.101;
+
+/// A `Config` field to tune, e.g. `time_control.c`, and the range of
+/// values to try.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameter {
+    /// Whether the field holds an integer (the values are rounded).
+    pub integer: bool,
+    pub max: f32,
+    pub min: f32,
+    pub name: String,
+}
+
+impl Parameter {
+
+    /// The value at `x` (between 0 and 1) of the range.
+    pub fn value(&self, x: f32) -> f32 {
+        let value = self.min + x * (self.max - self.min);
+        if self.integer { value.round() } else { value }
+    }
+
+    fn section(&self) -> &str {
+        self.name.split('.').next().unwrap()
+    }
+
+    fn field(&self) -> &str {
+        self.name.split('.').nth(1).unwrap()
+    }
+
+}
+
+/// Reads the parameters from a spec in the format of CLOP experiment
+/// files, so the existing ones can be used:
+///
+/// ```text
+/// LinearParameter time_control.c 0.1 2.0
+/// IntegerParameter time_control.min_stones 5 100
+/// ```
+///
+/// All other lines (the settings of CLOP) are ignored. The fields need
+/// to be numbers in the default configuration.
+pub fn parse_spec(spec: &str) -> Result<Vec<Parameter>, String> {
+    let defaults = toml::Parser::new(&Config::toml()).parse().unwrap();
+    let mut parameters = vec!();
+    for (index, line) in spec.lines().enumerate() {
+        let words: Vec<&str> = line.split('#').next().unwrap().split_whitespace().collect();
+        let integer = match words.get(0) {
+            Some(&"LinearParameter") => false,
+            Some(&"IntegerParameter") => true,
+            _ => continue
+        };
+        let error = |message: &str| format!("line {}: {}", index + 1, message);
+        if words.len() != 4 {
+            return Err(error("expected a name, a minimum and a maximum"));
+        }
+        let name = words[1].to_string();
+        let default = {
+            let mut parts = name.split('.');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(section), Some(field), None) => defaults.get(section)
+                    .and_then(|table| table.as_table())
+                    .and_then(|table| table.get(field)),
+                _ => None
+            }
+        };
+        let integer = match default.map(|value| value.type_str()) {
+            Some("integer") => true,
+            Some("float") => integer,
+            Some(_) => return Err(error(&format!("{} isn't a number", name))),
+            None => return Err(error(&format!("unknown parameter {}", name)))
+        };
+        let min: f32 = try!(words[2].parse().map_err(|_| error(&format!("invalid minimum {:?}", words[2]))));
+        let max: f32 = try!(words[3].parse().map_err(|_| error(&format!("invalid maximum {:?}", words[3]))));
+        if min >= max {
+            return Err(error("the minimum needs to be smaller than the maximum"));
+        }
+        parameters.push(Parameter { integer: integer, max: max, min: min, name: name });
+    }
+    if parameters.is_empty() {
+        return Err("no parameters to tune".to_string());
+    }
+    Ok(parameters)
+}
+
+/// The settings of the optimisation. `a` and `c` are the step size
+/// and the size of the perturbations at the start, both as fractions
+/// of the ranges of the parameters.
+#[derive(Clone, Copy, Debug)]
+pub struct Settings {
+    pub a: f32,
+    pub c: f32,
+    pub iterations: usize,
+    pub seed: u32,
+}
+
+/// Tunes the parameters with SPSA (simultaneous perturbation
+/// stochastic approximation).
+///
+/// Each iteration perturbs all parameters at once in a random
+/// direction, plays the two resulting configurations against each
+/// other and moves the parameters towards the winner. The random
+/// numbers only depend on the seed and the iteration, so an
+/// interrupted run can be resumed from its checkpoint.
+pub struct Tuner {
+    iteration: usize,
+    parameters: Vec<Parameter>,
+    settings: Settings,
+    // The current parameters, mapped to the range 0 to 1.
+    values: Vec<f32>,
+}
+
+impl Tuner {
+
+    /// Starts in the middle of the ranges.
+    pub fn new(parameters: Vec<Parameter>, settings: Settings) -> Tuner {
+        let values = vec!(0.5; parameters.len());
+        Tuner {
+            iteration: 0,
+            parameters: parameters,
+            settings: settings,
+            values: values,
+        }
+    }
+
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.iteration >= self.settings.iterations
+    }
+
+    /// Runs one iteration. `play` gets the configurations (as TOML)
+    /// of the positive and the negative perturbation and returns the
+    /// results of the first one.
+    pub fn step<F>(&mut self, mut play: F) where F: FnMut(String, String) -> Results {
+        let k = self.iteration as f32;
+        let big_a = 0.1 * self.settings.iterations as f32;
+        let a_k = self.settings.a / (k + 1.0 + big_a).powf(ALPHA);
+        let c_k = self.settings.c / (k + 1.0).powf(GAMMA);
+        let seed = [self.settings.seed, self.iteration as u32 + 1, 0x5bd1e995, 0x27d4eb2f];
+        let mut rng = XorShiftRng::from_seed(seed);
+        let delta: Vec<f32> = self.values.iter().map(|_| if rng.gen() { 1.0 } else { -1.0 }).collect();
+        let plus: Vec<f32> = self.values.iter().zip(delta.iter()).map(|(x, d)| clamp(x + c_k * d)).collect();
+        let minus: Vec<f32> = self.values.iter().zip(delta.iter()).map(|(x, d)| clamp(x - c_k * d)).collect();
+        let results = play(self.toml_of(&plus), self.toml_of(&minus));
+        // The score is between -1 (all games lost) and 1 (all won).
+        let score = 2.0 * results.score() as f32 - 1.0;
+        for (value, d) in self.values.iter_mut().zip(delta.iter()) {
+            *value = clamp(*value + a_k * score / (2.0 * c_k * d));
+        }
+        self.iteration += 1;
+    }
+
+    /// The current values of the parameters.
+    pub fn values(&self) -> Vec<(String, f32)> {
+        self.parameters.iter()
+            .zip(self.values.iter())
+            .map(|(parameter, &x)| (parameter.name.clone(), parameter.value(x)))
+            .collect()
+    }
+
+    /// The current parameters as a configuration file.
+    pub fn toml(&self) -> String {
+        self.toml_of(&self.values)
+    }
+
+    fn toml_of(&self, values: &[f32]) -> String {
+        let mut sections: Vec<&str> = vec!();
+        for parameter in self.parameters.iter() {
+            if !sections.contains(&parameter.section()) {
+                sections.push(parameter.section());
+            }
+        }
+        let mut toml = String::new();
+        for &section in sections.iter() {
+            if !toml.is_empty() {
+                toml.push_str("\n");
+            }
+            toml.push_str(&format!("[{}]\n\n", section));
+            for (parameter, &x) in self.parameters.iter().zip(values.iter()) {
+                if parameter.section() != section {
+                    continue;
+                }
+                let value = parameter.value(x);
+                if parameter.integer {
+                    toml.push_str(&format!("{} = {}\n", parameter.field(), value as i64));
+                } else {
+                    // The TOML parser doesn't understand scientific notation.
+                    toml.push_str(&format!("{} = {:.10}\n", parameter.field(), value));
+                }
+            }
+        }
+        toml
+    }
+
+    /// The state of the tuner: the number of the next iteration and
+    /// the parameters (in the range 0 to 1).
+    pub fn checkpoint(&self) -> String {
+        let mut checkpoint = format!("iteration {}\n", self.iteration);
+        for (parameter, x) in self.parameters.iter().zip(self.values.iter()) {
+            checkpoint.push_str(&format!("{} {:?}\n", parameter.name, x));
+        }
+        checkpoint
+    }
+
+    /// Continues from a checkpoint written by `checkpoint()`.
+    pub fn resume(&mut self, checkpoint: &str) -> Result<(), String> {
+        for line in checkpoint.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() != 2 {
+                continue;
+            }
+            if words[0] == "iteration" {
+                self.iteration = try!(words[1].parse().map_err(|_| format!("invalid iteration {:?}", words[1])));
+                continue;
+            }
+            match self.parameters.iter().position(|p| p.name == words[0]) {
+                Some(i) => self.values[i] = try!(words[1].parse().map_err(|_| format!("invalid value {:?} for {}", words[1], words[0]))),
+                None => return Err(format!("unknown parameter {}", words[0]))
+            }
+        }
+        Ok(())
+    }
+
+}
+
+fn clamp(x: f32) -> f32 {
+    x.max(0.0).min(1.0)
+}
+
+pub fn run(program: &str, args: &[String]) -> i32 {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "Print this help menu");
+    opts.optflag("r", "resume", "Continue from the checkpoint");
+    opts.optopt("a", "step", "Initial step size as a fraction of the ranges (defaults to 0.05)", "NUM");
+    opts.optopt("c", "perturbation", "Initial perturbation as a fraction of the ranges (defaults to 0.1)", "NUM");
+    opts.optopt("g", "games", "Number of games per iteration (defaults to 2)", "NUM");
+    opts.optopt("i", "iterations", "Number of iterations (defaults to 1000)", "NUM");
+    opts.optopt("k", "komi", "Komi (defaults to 7)", "NUM");
+    opts.optopt("o", "output", "Configuration file to write (defaults to tuned.toml)", "FILE");
+    opts.optopt("p", "parallel", "Number of games to play at the same time (defaults to 1)", "NUM");
+    opts.optopt("s", "seed", "Seed of the random numbers (defaults to 1)", "NUM");
+    opts.optopt("t", "time", "Main time per player and game in seconds (defaults to 10)", "NUM");
+    opts.optopt("T", "threads", "Number of search threads of each engine (defaults to 1)", "NUM");
+    opts.optopt("z", "size", "Board size (defaults to 9)", "NUM");
+    let matches = match opts.parse(args) {
+        Ok(m) => m,
+        Err(f) => {
+            println!("{}", f.to_string());
+            return 1;
+        }
+    };
+    if matches.opt_present("h") || matches.free.len() != 1 {
+        let brief = format!("Usage: {} [options] SPEC.clop", program);
+        println!("{}", opts.usage(brief.as_ref()));
+        return if matches.opt_present("h") { 0 } else { 1 };
+    }
+    let output = matches.opt_str("o").unwrap_or("tuned.toml".to_string());
+    let checkpoint = format!("{}.checkpoint", output);
+    let result = read(&matches.free[0])
+        .and_then(|spec| parse_spec(&spec))
+        .and_then(|parameters| {
+            let (settings, match_settings, threads) = try!(parse_settings(&matches));
+            let mut tuner = Tuner::new(parameters, settings);
+            if matches.opt_present("r") && Path::new(&checkpoint).exists() {
+                try!(read(&checkpoint).and_then(|contents| tuner.resume(&contents)));
+                println!("Resuming at iteration {}", tuner.iteration() + 1);
+            }
+            while !tuner.is_done() {
+                let mut error = None;
+                tuner.step(|plus, minus| {
+                    match (player("plus", &plus, threads), player("minus", &minus, threads)) {
+                        (Ok(plus), Ok(minus)) => play_match(&match_settings, Arc::new(plus), Arc::new(minus), |_, _| {}),
+                        (Err(e), _) | (_, Err(e)) => {
+                            error = Some(e);
+                            Results::new()
+                        }
+                    }
+                });
+                if let Some(e) = error {
+                    return Err(e);
+                }
+                let values: Vec<String> = tuner.values().iter().map(|&(ref name, value)| format!("{} = {}", name, value)).collect();
+                println!("Iteration {}: {}", tuner.iteration(), values.join(", "));
+                try!(write(&output, &tuner.toml()));
+                try!(write(&checkpoint, &tuner.checkpoint()));
+            }
+            Ok(())
+        });
+    match result {
+        Ok(_) => {
+            println!("Wrote {}", output);
+            0
+        },
+        Err(error) => {
+            println!("{}", error);
+            1
+        }
+    }
+}
+
+fn player(name: &str, toml: &str, threads: usize) -> Result<Player, String> {
+    let mut config = try!(Config::from_toml(toml.to_string(), false, false, KgsChinese)
+                          .map_err(|errors| describe_errors(name, &errors)));
+    config.threads = threads;
+    Player::new(name, config)
+}
+
+fn read(filename: &str) -> Result<String, String> {
+    let mut contents = String::new();
+    File::open(filename)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|e| format!("{}: {}", filename, e))
+        .map(|_| contents)
+}
+
+fn write(filename: &str, contents: &str) -> Result<(), String> {
+    File::create(filename)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn parse_settings(matches: &::getopts::Matches) -> Result<(Settings, MatchSettings, usize), String> {
+    let size = try!(parse_opt(matches, "z", 9));
+    if size < 2 || size > 19 {
+        return Err("the board size needs to be between 2 and 19".to_string());
+    }
+    let settings = Settings {
+        a: try!(parse_float(matches, "a", 0.05)),
+        c: try!(parse_float(matches, "c", 0.1)),
+        iterations: try!(parse_opt(matches, "i", 1000)),
+        seed: try!(parse_opt(matches, "s", 1)) as u32,
+    };
+    let match_settings = MatchSettings {
+        games: try!(parse_opt(matches, "g", 2)),
+        komi: try!(parse_float(matches, "k", 7.0)),
+        max_moves: 3 * size * size,
+        parallel: try!(parse_opt(matches, "p", 1)),
+        size: size as u8,
+        sprt: None,
+        time: try!(parse_opt(matches, "t", 10)) as i64,
+    };
+    if match_settings.parallel == 0 {
+        return Err("at least one game needs to be played at a time".to_string());
+    }
+    Ok((settings, match_settings, try!(parse_opt(matches, "T", 1))))
+}
+
+fn parse_opt(matches: &::getopts::Matches, name: &str, default: usize) -> Result<usize, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
+
+fn parse_float(matches: &::getopts::Matches, name: &str, default: f32) -> Result<f32, String> {
+    match matches.opt_str(name) {
+        Some(value) => value.parse().map_err(|_| format!("invalid number {:?} for -{}", value, name)),
+        None => Ok(default)
+    }
+}
@@ -0,0 +1,130 @@
+/************************************************************************
+ *                                                                      *
+ * Copyright 2016 Urban Hafner                                          *
+ *                                                                      *
+ * This file is part of Iomrascálaí.                                    *
+ *                                                                      *
+ * Iomrascálaí is free software: you can redistribute it and/or modify  *
+ * it under the terms of the GNU General Public License as published by *
+ * the Free Software Foundation, either version 3 of the License, or    *
+ * (at your option) any later version.                                  *
+ *                                                                      *
+ * Iomrascálaí is distributed in the hope that it will be useful,       *
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of       *
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the        *
+ * GNU General Public License for more details.                         *
+ *                                                                      *
+ * You should have received a copy of the GNU General Public License    *
+ * along with Iomrascálaí.  If not, see <http://www.gnu.org/licenses/>. *
+ *                                                                      *
+ ************************************************************************/
+
+
+#![cfg(test)]
+
+use config::Config;
+use ruleset::KgsChinese;
+use super::Parameter;
+use super::Settings;
+use super::Tuner;
+use super::parse_spec;
+use tools::play_match::Results;
+
+fn spec() -> &'static str {
+    "# Use for the naming of the .log and .dat files
+Name time_control
+Processor main
+
+LinearParameter time_control.c 0.1 2.0
+# LinearParameter time_control.fastplay_threshold 0.0 1.0
+IntegerParameter time_control.min_stones 5 100
+LinearParameter priors.capture_many 10 50
+"
+}
+
+fn settings() -> Settings {
+    Settings { a: 0.05, c: 0.1, iterations: 50, seed: 3 }
+}
+
+fn tuner() -> Tuner {
+    Tuner::new(parse_spec(spec()).unwrap(), settings())
+}
+
+// The first configuration wins if its `time_control.c` is larger.
+fn prefer_larger_c(plus: String, minus: String) -> Results {
+    let plus = Config::from_toml(plus, false, false, KgsChinese).unwrap();
+    let minus = Config::from_toml(minus, false, false, KgsChinese).unwrap();
+    let won = plus.time_control.c > minus.time_control.c;
+    Results { draws: 0, losses: if won { 0 } else { 1 }, wins: if won { 1 } else { 0 } }
+}
+
+#[test]
+fn reads_the_parameters_of_clop_files() {
+    let parameters = parse_spec(spec()).unwrap();
+    assert_eq!(3, parameters.len());
+    assert_eq!(Parameter { integer: false, max: 2.0, min: 0.1, name: "time_control.c".to_string() }, parameters[0]);
+    assert!(parameters[1].integer);
+    // The field is an integer in the configuration.
+    assert!(parameters[2].integer);
+}
+
+#[test]
+fn rejects_unknown_parameters() {
+    let result = parse_spec("LinearParameter time_control.d 0 1");
+    assert_eq!(Err("line 1: unknown parameter time_control.d".to_string()), result);
+}
+
+#[test]
+fn rejects_parameters_that_are_not_numbers() {
+    let result = parse_spec("\nLinearParameter time_control.ponder 0 1");
+    assert_eq!(Err("line 2: time_control.ponder isn't a number".to_string()), result);
+}
+
+#[test]
+fn rejects_empty_ranges() {
+    let result = parse_spec("LinearParameter time_control.c 1 1");
+    assert_eq!(Err("line 1: the minimum needs to be smaller than the maximum".to_string()), result);
+}
+
+#[test]
+fn rejects_specs_without_parameters() {
+    assert!(parse_spec("Name test").is_err());
+}
+
+#[test]
+fn writes_a_configuration_that_can_be_loaded() {
+    let config = Config::from_toml(tuner().toml(), false, false, KgsChinese).unwrap();
+    assert!((config.time_control.c - 1.05).abs() < 1e-6);
+    assert_eq!(53, config.time_control.min_stones);
+    assert_eq!(30, config.priors.capture_many);
+}
+
+#[test]
+fn moves_the_parameters_towards_the_winner() {
+    let mut tuner = tuner();
+    while !tuner.is_done() {
+        tuner.step(prefer_larger_c);
+    }
+    let values = tuner.values();
+    assert_eq!("time_control.c", values[0].0);
+    assert!(values[0].1 > 1.5, "c: {}", values[0].1);
+}
+
+#[test]
+fn resuming_from_a_checkpoint_gives_the_same_result() {
+    let mut tuner1 = tuner();
+    for _ in 0..10 {
+        tuner1.step(prefer_larger_c);
+    }
+    let mut tuner2 = tuner();
+    for _ in 0..5 {
+        tuner2.step(prefer_larger_c);
+    }
+    let mut resumed = tuner();
+    resumed.resume(&tuner2.checkpoint()).unwrap();
+    assert_eq!(5, resumed.iteration());
+    for _ in 0..5 {
+        resumed.step(prefer_larger_c);
+    }
+    assert_eq!(tuner1.checkpoint(), resumed.checkpoint());
+}